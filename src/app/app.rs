@@ -1,8 +1,10 @@
 use std::cell::RefCell;
 use std::fs::File;
 use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Instant;
 use dec_gl::{GLHandler, UICamera, Vertex2d};
 use dec_gl::framebuffer::SimpleFramebuffer;
@@ -12,17 +14,25 @@ use mockall_double::double;
 #[double]
 use dec_gl::texture::{Texture2Du8, Texture3Du8};
 use dec_gl::types::{ivec2, vec4, Vec3};
-use dialog::{DialogBox, FileSelection};
-use glfw::{Action, Key, WindowEvent};
+use dialog::{DialogBox, FileSelection, Message};
+use glfw::{Action, Key, Modifiers, WindowEvent};
 use parking_lot::Mutex;
 use crate::app::PerformanceTimer;
+use crate::audio::{AudioSink, LiveSink, WavSink, APU};
 use crate::cpu::GameBoyCPU;
+use crate::input::{GamepadPoller, InputConfig, InputMapper};
 use crate::memory::{MemoryController, MemoryTrait};
-use crate::renderer::VideoProcessor;
+use crate::link::{LinkStream, LockStepPeer};
+use crate::movie::{Movie, MoviePlayer, MovieStart};
+use crate::printer::GameBoyPrinter;
+use crate::memory::io_map::{SerialConsole, SerialPeer};
+use crate::renderer::{Renderer, ScreenPresenter, SoftwareRenderer, VideoProcessor};
+use crate::rewind::RewindBuffer;
+use crate::save_state::SaveStateFile;
 use crate::system::MainBoard;
 
 pub struct App {
-    pub _args: Vec<String>,
+    pub args: Vec<String>,
 
     camera: UICamera,
 
@@ -31,6 +41,8 @@ pub struct App {
     shader_manager: ShaderManager,
 
     rom_path: Option<String>,
+    rom_title: String,
+    save_state_slot: u8,
 
     performance_timer: PerformanceTimer,
 }
@@ -40,8 +52,19 @@ const GB_COLUR_1: Vec3 = Vec3{x: 0.45, y: 0.7, z: 0.3};
 const GB_COLUR_2: Vec3 = Vec3{x: 0.3, y: 0.45, z: 0.15};
 const GB_COLUR_3: Vec3 = Vec3{x: 0.2, y: 0.3, z: 0.0};
 
+const FRAME_DURATION: std::time::Duration = std::time::Duration::from_micros(16500);
+const AUDIO_FRAMES_QUEUED: u32 = 2; //audio-driven pacing runs the next frame once the sink is down to this many frames of samples
+
 
 impl App {
+    /**
+        Every key the run loop handles itself. A hotkey added to the event match must be listed here so the input config can't bind it.
+    */
+    pub const HOTKEYS: &'static [Key] = &[
+        Key::R, Key::L, Key::V, Key::Backspace, Key::Num1, Key::Num2, Key::Num3, Key::Num4,
+        Key::F5, Key::F8, Key::F9, Key::F10, Key::F12,
+    ];
+
      pub fn new(args: Vec<String>, gl_handler: Rc<RefCell<GLHandler>>) -> App {
          let window_size = gl_handler.borrow().get_window().get_window_size();
 
@@ -55,6 +78,10 @@ impl App {
              "OBJECT".to_string(),
              Box::new(GLShaderProgram::load_shader_program("assets/graphics/shaders/object", "OBJECT", false).unwrap())
          ).unwrap();
+         shader_manager.register_shader(
+             "SCREEN".to_string(),
+             Box::new(GLShaderProgram::load_shader_program("assets/graphics/shaders/screen", "SCREEN", false).unwrap())
+         ).unwrap();
 
          let framebuffer = SimpleFramebuffer::new(window_size.x as i32, window_size.y as i32).unwrap();
 
@@ -62,7 +89,7 @@ impl App {
 
 
         App {
-            _args: args,
+            args,
 
             camera,
 
@@ -70,6 +97,8 @@ impl App {
             framebuffer,
             shader_manager,
             rom_path: None,
+            rom_title: String::new(),
+            save_state_slot: 1,
             performance_timer: PerformanceTimer::new_fake(),
         }
      }
@@ -115,45 +144,63 @@ impl App {
             Err(_) => return
         };
 
-        let memory_controller = Arc::new(Mutex::new(MemoryController::new()));
-        let cpu = Box::new(GameBoyCPU::new_with_nop());
+        match self.shader_manager.bind("SCREEN".to_string()) {
+            Ok(shader) => {
+                shader.set_uniform("pv".to_string(), &self.camera.get_matrix());
 
-        self.rom_path = self.get_rom_path();
-        match &self.rom_path {
-            Some(path) => memory_controller.lock().load_rom(path),
-            None => {}
+                shader.set_uniform("screen".to_string(), &0);
+
+                shader.set_uniform("gbColour0".to_string(), &GB_COLUR_0);
+                shader.set_uniform("gbColour1".to_string(), &GB_COLUR_1);
+                shader.set_uniform("gbColour2".to_string(), &GB_COLUR_2);
+                shader.set_uniform("gbColour3".to_string(), &GB_COLUR_3);
+
+                shader
+            }
+            Err(_) => return
         };
 
-        let video_processor = {
-            let vram = memory_controller.lock().get_vram_arc();
-            let oam = memory_controller.lock().get_oam_arc();
-            let video_io = memory_controller.lock().get_io_map().lock().get_video_io();
+        let memory_controller = Arc::new(Mutex::new(MemoryController::new()));
 
-            VideoProcessor::new(
-                Texture3Du8::default(),
-                Texture3Du8::default(),
-                Texture3Du8::default(),
+        let serial_peer = self.create_serial_peer();
+        memory_controller.lock().get_io_map().lock().set_serial_peer(serial_peer);
 
-                Texture2Du8::default(),
-                Texture2Du8::default(),
+        let rumble = Arc::new(AtomicBool::new(false));
+        let callback_rumble = rumble.clone();
+        memory_controller.lock().set_rumble_callback(Arc::new(move |state| callback_rumble.store(state, Ordering::Relaxed)));
+        let cpu = Box::new(GameBoyCPU::new_with_nop());
 
-                Box::new(GlRenderable::<Vertex2d>::new::<Vertex2d>()),
-                Box::new(GlRenderable::<Vertex2d>::new::<Vertex2d>()),
+        self.rom_path = self.get_rom_path();
+        self.load_rom(&memory_controller);
 
-                vram,
-                oam,
-                video_io,
-            ).unwrap()
-        };
+        let renderer = self.create_renderer(&memory_controller);
 
         let mut main_board = MainBoard::new(
             cpu,
             memory_controller.clone(),
-            video_processor
+            renderer
         );
 
+        let mut audio_sink = self.create_audio_sink();
+        let audio_sync = self.has_argument("--audio-sync");
+        main_board.set_audio_sink(audio_sink.clone());
+
         let joypad = memory_controller.lock().get_io_map().lock().get_joypad_io();
 
+        let mut input_mapper = InputMapper::new(self.load_input_config());
+        let mut gamepad_poller = match glfw::init_no_callbacks() {
+            Ok(glfw) => Some(GamepadPoller::new(glfw)),
+            Err(error) => {
+                eprintln!("Gamepads disabled: {}", error);
+                None
+            }
+        };
+        let mut rewind_buffer = self.create_rewind_buffer();
+        let mut rewinding = false;
+
+        let mut movie_recording: Option<Movie> = None;
+        let mut movie_player: Option<MoviePlayer> = None;
+
         let mut _frame: u64 = 0;
 
         let mut last_frame = Instant::now();
@@ -166,6 +213,9 @@ impl App {
             let events = self.gl_handler.borrow_mut().handle_events();
             for event in events.clone() {
                 match event {
+                    WindowEvent::Key(key, _, action, _) if !Self::HOTKEYS.contains(&key) && input_mapper.is_bound(key) => {
+                        input_mapper.handle_key(key, action);
+                    }
                     WindowEvent::Key(Key::F12, _, Action::Press, _) => {
                         let mut file = File::create("memdump.bin").unwrap();
                         let mut memory: Vec<u8> = vec![];
@@ -176,86 +226,76 @@ impl App {
 
                         file.write_all(memory.as_slice()).unwrap(); },
 
-                    WindowEvent::Key(Key::W, _, action, _) => {
-                        if action == Action::Press || action == Action::Repeat {
-                            joypad.lock().set_up(true);
-                        } else {
-                            joypad.lock().set_up(false);
-                        }
-                    }
-                    WindowEvent::Key(Key::A, _, action, _) => {
-                        if action == Action::Press || action == Action::Repeat {
-                            joypad.lock().set_left(true);
-                        } else {
-                            joypad.lock().set_left(false);
-                        }
-                    }
-                    WindowEvent::Key(Key::S, _, action, _) => {
-                        if action == Action::Press || action == Action::Repeat {
-                            joypad.lock().set_down(true);
-                        } else {
-                            joypad.lock().set_down(false);
-                        }
+                    WindowEvent::Key(Key::V, _, Action::Press, _) => {
+                        let new_vsync = { !self.gl_handler.borrow().get_vsync() };
+                        self.gl_handler.borrow_mut().set_vsync(new_vsync);
                     }
-                    WindowEvent::Key(Key::D, _, action, _) => {
-                        if action == Action::Press || action == Action::Repeat {
-                            joypad.lock().set_right(true);
-                        } else {
-                            joypad.lock().set_right(false);
+                    WindowEvent::Key(Key::R, _, Action::Press, _) => {
+                        self.save_movie(movie_recording.take());
+                        movie_player = None;
+
+                        match main_board.reset() {
+                            Ok(_) => {}
+                            Err(error) => Self::show_error(error.to_string())
                         }
+                        self.load_rom(&memory_controller);
+                        rewind_buffer.clear();
                     }
-                    WindowEvent::Key(Key::Space, _, action, _) => {
-                        if action == Action::Press || action == Action::Repeat {
-                            joypad.lock().set_a(true);
-                        } else {
-                            joypad.lock().set_a(false);
-                        }
+                    WindowEvent::Key(Key::Backspace, _, Action::Press, _) => rewinding = true,
+                    WindowEvent::Key(Key::Backspace, _, Action::Release, _) => rewinding = false,
+                    WindowEvent::Key(Key::Num1, _, Action::Press, _) => self.save_state_slot = 1,
+                    WindowEvent::Key(Key::Num2, _, Action::Press, _) => self.save_state_slot = 2,
+                    WindowEvent::Key(Key::Num3, _, Action::Press, _) => self.save_state_slot = 3,
+                    WindowEvent::Key(Key::Num4, _, Action::Press, _) => self.save_state_slot = 4,
+                    WindowEvent::Key(Key::F5, _, Action::Press, _) => {
+                        self.save_state(&main_board, &memory_controller);
                     }
-                    WindowEvent::Key(Key::Enter, _, action, _) => {
-                        if action == Action::Press || action == Action::Repeat {
-                            joypad.lock().set_b(true);
-                        } else {
-                            joypad.lock().set_b(false);
-                        }
-                    }
-                    WindowEvent::Key(Key::Q, _, action, _) => {
-                        if action == Action::Press || action == Action::Repeat {
-                            joypad.lock().set_start(true);
-                        } else {
-                            joypad.lock().set_start(false);
-                        }
+                    WindowEvent::Key(Key::F8, _, Action::Press, _) => {
+                        self.save_movie(movie_recording.take());
+                        movie_player = None;
+
+                        self.load_state(&mut main_board, &memory_controller);
+                        rewind_buffer.clear();
                     }
-                    WindowEvent::Key(Key::E, _, action, _) => {
-                        if action == Action::Press || action == Action::Repeat {
-                            joypad.lock().set_select(true);
-                        } else {
-                            joypad.lock().set_select(false);
+                    WindowEvent::Key(Key::F9, _, Action::Press, modifiers) => {
+                        movie_player = None;
+
+                        match movie_recording.take() {
+                            Some(movie) => self.save_movie(Some(movie)),
+                            None => {
+                                movie_recording = self.start_movie_recording(&mut main_board, &memory_controller, !modifiers.contains(Modifiers::Shift));
+                                rewind_buffer.clear();
+                            }
                         }
                     }
-                    WindowEvent::Key(Key::V, _, Action::Press, _) => {
-                        let new_vsync = { !self.gl_handler.borrow().get_vsync() };
-                        self.gl_handler.borrow_mut().set_vsync(new_vsync);
-                    }
-                    WindowEvent::Key(Key::R, _, Action::Press, _) => {
-                        main_board.reset().unwrap();
-                        match &self.rom_path {
-                            Some(path) => memory_controller.lock().load_rom(path),
-                            None => {}
-                        };
+                    WindowEvent::Key(Key::F10, _, Action::Press, _) => {
+                        self.save_movie(movie_recording.take());
+
+                        movie_player = self.start_movie_playback(&mut main_board);
+                        rewind_buffer.clear();
                     }
                     WindowEvent::Key(Key::L, _, Action::Press, _) => {
+                        self.save_movie(movie_recording.take());
+                        movie_player = None;
+
                         self.rom_path = self.get_rom_path();
 
-                        main_board.reset().unwrap();
-                        match &self.rom_path {
-                            Some(path) => memory_controller.lock().load_rom(path),
-                            None => {}
-                        };
+                        match main_board.reset() {
+                            Ok(_) => {}
+                            Err(error) => Self::show_error(error.to_string())
+                        }
+                        self.load_rom(&memory_controller);
+                        rewind_buffer.clear();
                     }
                     _ => {}
                 }
             }
 
+            if let Some(gamepad_poller) = &mut gamepad_poller {
+                self.performance_timer.set_category("Gamepads");
+                input_mapper.handle_gamepads(&gamepad_poller.poll());
+            }
+
             self.performance_timer.set_category("Render (Framebuffer)");
 
             self.framebuffer.clear();
@@ -264,8 +304,48 @@ impl App {
 
             self.framebuffer.bind_draw_target();
 
+            let movie_active = movie_recording.is_some() || movie_player.is_some();
+
+            if rewinding && !movie_active {
+                self.performance_timer.set_category("Rewind");
+                Self::rewind(&mut main_board, &mut rewind_buffer);
+            }
+
+            self.performance_timer.set_category("Movie");
+            match &mut movie_player {
+                Some(player) if player.is_finished() => movie_player = None,
+                Some(player) => { player.apply_next_input(&mut joypad.lock()); }
+                None => {}
+            }
+            if movie_player.is_none() {
+                joypad.lock().set_buttons(input_mapper.get_buttons()); //also gives back the keys still held when playback ends
+            }
+            match &mut movie_recording {
+                Some(movie) => movie.push_input(joypad.lock().get_buttons()),
+                None => {}
+            }
+
             main_board.perform_frame(&mut self.shader_manager, &mut self.performance_timer).unwrap();
 
+            if !rewinding || movie_active {
+                self.performance_timer.set_category("Rewind");
+                rewind_buffer.record_frame(&main_board);
+            }
+
+            self.performance_timer.set_category("Audio");
+            let drain_result = match &audio_sink {
+                Some(sink) => sink.lock().drain(),
+                None => Ok(())
+            };
+            match drain_result {
+                Ok(_) => {}
+                Err(error) => {
+                    Self::show_error(error.to_string());
+                    audio_sink = None;
+                    main_board.set_audio_sink(None);
+                }
+            }
+
             self.performance_timer.set_category("Render (Framebuffer)");
             SimpleFramebuffer::bind_default_framebuffer();
             self.framebuffer.blit(
@@ -279,12 +359,17 @@ impl App {
 
             self.performance_timer.set_category("idle");
 
+            let audio_paced = audio_sync && match &audio_sink {
+                Some(sink) => Self::wait_for_audio(sink),
+                None => false
+            };
+
             now = Instant::now();
 
-            while self.gl_handler.borrow().get_vsync() &&
-                now.duration_since(last_frame) < std::time::Duration::from_micros(16500)
+            while !audio_paced && self.gl_handler.borrow().get_vsync() &&
+                now.duration_since(last_frame) < FRAME_DURATION
             {
-                std::thread::sleep(std::time::Duration::from_micros(16500) - now.duration_since(last_frame));
+                std::thread::sleep(FRAME_DURATION - now.duration_since(last_frame));
                 now = Instant::now();
             }
 
@@ -292,10 +377,41 @@ impl App {
             let elapsed = now.duration_since(last_frame);
             last_frame = now;
 
-            self.gl_handler.borrow_mut().get_window_mut().set_title(format!("GB EMULATOR - {} FPS", (1.0 / elapsed.as_secs_f64()).round()).as_str());
+            let rewind_status = if rewinding && !movie_active {
+                format!(" - REWIND {} ({} KiB)", rewind_buffer.get_snapshot_count(), rewind_buffer.get_used_bytes() / 1024)
+            } else {
+                String::new()
+            };
+
+            self.gl_handler.borrow_mut().get_window_mut().set_title(format!(
+                "GB EMULATOR - {}{} FPS - SLOT {}{}{}{}",
+                self.rom_title,
+                (1.0 / elapsed.as_secs_f64()).round(),
+                self.save_state_slot,
+                if movie_recording.is_some() { " - REC" } else if movie_player.is_some() { " - PLAY" } else { "" },
+                rewind_status,
+                if rumble.load(Ordering::Relaxed) { " - RUMBLE" } else { "" }
+            ).as_str());
 
             _frame += 1;
         }
+
+        self.save_movie(movie_recording);
+
+        let finish_result = match &audio_sink {
+            Some(sink) => sink.lock().finish(),
+            None => Ok(())
+        };
+        match finish_result {
+            Ok(_) => {}
+            Err(error) => Self::show_error(error.to_string())
+        }
+
+        let save_result = memory_controller.lock().save_battery();
+        match save_result {
+            Ok(_) => {}
+            Err(error) => Self::show_error(format!("Save Error: {}", error))
+        }
     }
 
     fn resize(&mut self) {
@@ -304,6 +420,337 @@ impl App {
         self.framebuffer.resize(window_size.x, window_size.y);
     }
 
+    fn load_rom(&mut self, memory_controller: &Arc<Mutex<MemoryController>>) {
+        self.rom_title = String::new();
+
+        match &self.rom_path {
+            Some(path) => {
+                let load_result = memory_controller.lock().load_rom(path, true);
+                match load_result {
+                    Ok(_) => {}
+                    Err(error) => Self::show_error(format!("Could not load ROM: {}", error))
+                }
+            }
+            None => {}
+        }
+
+        match memory_controller.lock().get_rom().get_cartridge_info() {
+            Some(cartridge_info) => self.rom_title = format!("{} - ", cartridge_info.get_title()),
+            None => {}
+        }
+    }
+
+    /**
+        --software-renderer draws on the CPU and only uploads the finished screen, otherwise lines are drawn with shaders
+    */
+    fn create_renderer(&self, memory_controller: &Arc<Mutex<MemoryController>>) -> Box<dyn Renderer> {
+        let vram = memory_controller.lock().get_vram_arc();
+        let oam = memory_controller.lock().get_oam_arc();
+        let video_io = memory_controller.lock().get_io_map().lock().get_video_io();
+
+        if self.has_argument("--software-renderer") {
+            let software_renderer = SoftwareRenderer::new(vram, oam, video_io.clone());
+
+            Box::new(ScreenPresenter::new(
+                software_renderer,
+                Texture2Du8::default(),
+                Box::new(GlRenderable::<Vertex2d>::new::<Vertex2d>()),
+                video_io,
+            ).unwrap())
+        }
+        else {
+            Box::new(VideoProcessor::new(
+                Texture3Du8::default(),
+                Texture3Du8::default(),
+                Texture3Du8::default(),
+
+                Texture2Du8::default(),
+                Texture2Du8::default(),
+
+                Box::new(GlRenderable::<Vertex2d>::new::<Vertex2d>()),
+                Box::new(GlRenderable::<Vertex2d>::new::<Vertex2d>()),
+
+                vram,
+                oam,
+                video_io,
+            ).unwrap())
+        }
+    }
+
+    /**
+        --input-config <path> reads key and gamepad bindings from path, otherwise from input.cfg if there is one
+    */
+    fn load_input_config(&self) -> InputConfig {
+        let path = match self.get_argument_value("--input-config") {
+            Some(path) => PathBuf::from(path),
+            None if Path::new(InputConfig::DEFAULT_PATH).exists() => PathBuf::from(InputConfig::DEFAULT_PATH),
+            None => return InputConfig::new()
+        };
+
+        match InputConfig::load(&path) {
+            Ok(config) => config,
+            Err(error) => {
+                Self::show_error(error.to_string());
+                InputConfig::new()
+            }
+        }
+    }
+
+    /**
+        --rewind-interval <frames> sets how often a rewind snapshot is taken, --rewind-memory <MiB> how much memory they can use
+    */
+    fn create_rewind_buffer(&self) -> RewindBuffer {
+        let interval_frames = match self.get_argument_value("--rewind-interval").map(|value| value.parse::<u32>()) {
+            Some(Ok(interval_frames)) => interval_frames,
+            Some(Err(error)) => {
+                Self::show_error(format!("Invalid rewind interval: {}", error));
+                RewindBuffer::DEFAULT_INTERVAL_FRAMES
+            }
+            None => RewindBuffer::DEFAULT_INTERVAL_FRAMES
+        };
+
+        let memory_budget = match self.get_argument_value("--rewind-memory").map(|value| value.parse::<usize>()) {
+            Some(Ok(megabytes)) => match megabytes.checked_mul(1024 * 1024) {
+                Some(memory_budget) => memory_budget,
+                None => {
+                    Self::show_error(format!("Invalid rewind memory: {} MiB is too large", megabytes));
+                    RewindBuffer::DEFAULT_MEMORY_BUDGET
+                }
+            }
+            Some(Err(error)) => {
+                Self::show_error(format!("Invalid rewind memory: {}", error));
+                RewindBuffer::DEFAULT_MEMORY_BUDGET
+            }
+            None => RewindBuffer::DEFAULT_MEMORY_BUDGET
+        };
+
+        RewindBuffer::new(interval_frames, memory_budget)
+    }
+
+    /**
+        --no-audio disables sound, --record-audio <path> writes a WAV file instead of playing it
+    */
+    fn create_audio_sink(&self) -> Option<Arc<Mutex<dyn AudioSink>>> {
+        if self.has_argument("--no-audio") {
+            return None;
+        }
+
+        match self.get_argument_value("--record-audio") {
+            Some(path) => match WavSink::create(Path::new(&path), APU::DEFAULT_SAMPLE_RATE) {
+                Ok(sink) => Some(Arc::new(Mutex::new(sink))),
+                Err(error) => {
+                    Self::show_error(error.to_string());
+                    None
+                }
+            }
+            None => match LiveSink::new() {
+                Ok(sink) => Some(Arc::new(Mutex::new(sink))),
+                Err(error) => {
+                    eprintln!("Audio disabled: {}", error);
+                    None
+                }
+            }
+        }
+    }
+
+    /**
+        --link-host <address> waits for another instance to --link-join <address>, addresses are host:port or unix:<path>.
+        --printer <directory> plugs in a Game Boy Printer saving printouts there, --print-serial writes serial output to stdout instead.
+    */
+    fn create_serial_peer(&self) -> Option<Box<dyn SerialPeer>> {
+        let stream = match (self.get_argument_value("--link-host"), self.get_argument_value("--link-join")) {
+            (Some(address), _) => Some(LinkStream::host(&address)),
+            (None, Some(address)) => Some(LinkStream::join(&address)),
+            (None, None) => None
+        };
+
+        match stream {
+            Some(Ok(stream)) => match LockStepPeer::new(stream) {
+                Ok(peer) => Some(Box::new(peer)),
+                Err(error) => {
+                    Self::show_error(format!("Link cable error: {}", error));
+                    None
+                }
+            }
+            Some(Err(error)) => {
+                Self::show_error(error.to_string());
+                None
+            }
+            None => match self.get_argument_value("--printer") {
+                Some(directory) => Some(Box::new(GameBoyPrinter::new(PathBuf::from(directory)))),
+                None if self.has_argument("--print-serial") => Some(Box::new(SerialConsole::new())),
+                None => None
+            }
+        }
+    }
+
+    /**
+        Sleeps until the sink has played enough to need the next frame, returns false if the sink can't pace emulation
+    */
+    fn wait_for_audio(audio_sink: &Arc<Mutex<dyn AudioSink>>) -> bool {
+        let sample_rate = audio_sink.lock().get_sample_rate();
+        let target = (sample_rate * AUDIO_FRAMES_QUEUED / 60) as usize;
+
+        loop {
+            let queued_samples = audio_sink.lock().get_queued_samples();
+
+            match queued_samples {
+                Some(queued) if queued > target => std::thread::sleep(std::time::Duration::from_millis(1)),
+                Some(_) => return true,
+                None => return false
+            }
+        }
+    }
+
+    /**
+        F5 saves to the selected slot, chosen with 1-4
+    */
+    fn save_state(&self, main_board: &MainBoard, memory_controller: &Arc<Mutex<MemoryController>>) {
+        let (path, rom_checksum) = match self.get_save_state_target(memory_controller) {
+            Some(target) => target,
+            None => return
+        };
+
+        match SaveStateFile::write(&path, rom_checksum, &main_board.save_state()) {
+            Ok(_) => {}
+            Err(error) => Self::show_error(error.to_string())
+        }
+    }
+
+    /**
+        F8 loads the selected slot. If the state turns out to be invalid part way through, the machine is put back how it was.
+    */
+    fn load_state(&self, main_board: &mut MainBoard, memory_controller: &Arc<Mutex<MemoryController>>) {
+        let (path, rom_checksum) = match self.get_save_state_target(memory_controller) {
+            Some(target) => target,
+            None => return
+        };
+
+        let state = match SaveStateFile::read(&path, rom_checksum) {
+            Ok(state) => state,
+            Err(error) => return Self::show_error(error.to_string())
+        };
+
+        let backup = main_board.save_state();
+
+        match main_board.load_state(&state) {
+            Ok(_) => {}
+            Err(error) => {
+                let _ = main_board.load_state(&backup);
+                Self::show_error(error.to_string());
+            }
+        }
+    }
+
+    /**
+        Backspace is held to rewind, loading an older snapshot every frame
+    */
+    fn rewind(main_board: &mut MainBoard, rewind_buffer: &mut RewindBuffer) {
+        let result = rewind_buffer.rewind()
+            .and_then(|state| match state {
+                Some(state) => main_board.load_state(&state),
+                None => Ok(())
+            });
+
+        match result {
+            Ok(_) => {}
+            Err(error) => {
+                rewind_buffer.clear();
+                Self::show_error(error.to_string());
+            }
+        }
+    }
+
+    /**
+        F9 records a movie from power-on, or from the current state with shift held. Pressing it again writes game.gbm.
+    */
+    fn start_movie_recording(&mut self, main_board: &mut MainBoard, memory_controller: &Arc<Mutex<MemoryController>>, from_power_on: bool) -> Option<Movie> {
+        let rom_hash = match self.get_rom_hash() {
+            Some(rom_hash) => rom_hash,
+            None => return None
+        };
+
+        let start = if from_power_on {
+            match main_board.reset() {
+                Ok(_) => {}
+                Err(error) => Self::show_error(error.to_string())
+            }
+            self.load_rom(memory_controller);
+
+            MovieStart::PowerOn
+        } else {
+            MovieStart::SaveState
+        };
+
+        Some(Movie::new(rom_hash, start, main_board.save_state()))
+    }
+
+    fn save_movie(&self, movie: Option<Movie>) {
+        let (movie, rom_path) = match (movie, &self.rom_path) {
+            (Some(movie), Some(rom_path)) => (movie, rom_path),
+            _ => return
+        };
+
+        match movie.save(&Movie::get_path(Path::new(rom_path))) {
+            Ok(_) => {}
+            Err(error) => Self::show_error(error.to_string())
+        }
+    }
+
+    /**
+        F10 plays game.gbm back from its start state, ignoring the keyboard and gamepads until it ends
+    */
+    fn start_movie_playback(&self, main_board: &mut MainBoard) -> Option<MoviePlayer> {
+        let rom_hash = self.get_rom_hash()?;
+        let rom_path = self.rom_path.as_ref()?;
+
+        let result = Movie::load(&Movie::get_path(Path::new(rom_path)))
+            .map(MoviePlayer::new)
+            .and_then(|mut movie_player| movie_player.start(main_board, rom_hash).map(|_| movie_player));
+
+        match result {
+            Ok(movie_player) => Some(movie_player),
+            Err(error) => {
+                Self::show_error(error.to_string());
+                None
+            }
+        }
+    }
+
+    fn get_rom_hash(&self) -> Option<u32> {
+        let rom_path = self.rom_path.as_ref()?;
+
+        match Movie::hash_rom_file(Path::new(rom_path)) {
+            Ok(rom_hash) => Some(rom_hash),
+            Err(error) => {
+                Self::show_error(error.to_string());
+                None
+            }
+        }
+    }
+
+    fn get_save_state_target(&self, memory_controller: &Arc<Mutex<MemoryController>>) -> Option<(PathBuf, u16)> {
+        let rom_path = self.rom_path.as_ref()?;
+        let rom_checksum = memory_controller.lock().get_rom().get_cartridge_info()?.get_global_checksum();
+
+        Some((SaveStateFile::get_slot_path(Path::new(rom_path), self.save_state_slot), rom_checksum))
+    }
+
+    fn has_argument(&self, name: &str) -> bool {
+        self.args.iter().any(|arg| arg == name)
+    }
+
+    fn get_argument_value(&self, name: &str) -> Option<String> {
+        self.args.iter()
+            .position(|arg| arg == name)
+            .and_then(|index| self.args.get(index + 1))
+            .cloned()
+    }
+
+    fn show_error(message: String) {
+        let _ = Message::new(message).title("Error").show();
+    }
+
     fn get_rom_path(&self) -> Option<String> {
         FileSelection::new("Open ROM File")
             .title("Rom File")
@@ -0,0 +1,459 @@
+
+use std::collections::VecDeque;
+use crate::audio::noise_channel::NoiseChannel;
+use crate::audio::square_channel::SquareChannel;
+use crate::audio::wave_channel::WaveChannel;
+use crate::memory::MemoryTrait;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
+
+/**
+    Maps 0xFF10-0xFF3F. The frame sequencer is stepped by the falling edge of bit 4 of DIV (512Hz), so it
+    shares the divider counter with the timer and is affected by DIV resets.
+    Stereo samples are produced at the configured sample rate and buffered until taken by the frontend.
+*/
+pub struct APU {
+    powered: bool,
+
+    square_channel_1: SquareChannel,
+    square_channel_2: SquareChannel,
+    wave_channel: WaveChannel,
+    noise_channel: NoiseChannel,
+    wave_ram: [u8; 16],
+
+    master_volume: u8, //NR50
+    panning: u8,       //NR51
+
+    frame_sequencer_step: u8,
+    last_divider_bit: bool,
+
+    sample_rate: u32,
+    sample_counter: u32,
+    high_pass_charge_factor: f32,
+    capacitor: (f32, f32),
+    samples: VecDeque<(f32, f32)>,
+}
+
+impl MemoryTrait for APU {
+    fn get(&self, position: u16) -> u8 {
+        match position {
+            0xFF10..=0xFF14 => self.square_channel_1.read_register(position - 0xFF10),
+            0xFF15..=0xFF19 => self.square_channel_2.read_register(position - 0xFF15),
+            0xFF1A..=0xFF1E => self.wave_channel.read_register(position - 0xFF1A),
+            0xFF1F..=0xFF23 => self.noise_channel.read_register(position - 0xFF1F),
+            0xFF24 => self.master_volume,
+            0xFF25 => self.panning,
+            0xFF26 => self.get_status(),
+            0xFF30..=0xFF3F => self.wave_ram[(position - 0xFF30) as usize],
+            _ => 0xFF
+        }
+    }
+
+    fn set(&mut self, position: u16, value: u8) -> u8 {
+        let old_value = self.get(position);
+
+        match position {
+            0xFF26 => self.set_power(value & 0x80 != 0),
+            0xFF30..=0xFF3F => self.wave_ram[(position - 0xFF30) as usize] = value, //wave RAM can be written while powered off
+            _ if !self.powered => {}
+            0xFF10..=0xFF14 => self.square_channel_1.write_register(position - 0xFF10, value),
+            0xFF15..=0xFF19 => self.square_channel_2.write_register(position - 0xFF15, value),
+            0xFF1A..=0xFF1E => self.wave_channel.write_register(position - 0xFF1A, value),
+            0xFF1F..=0xFF23 => self.noise_channel.write_register(position - 0xFF1F, value),
+            0xFF24 => self.master_volume = value,
+            0xFF25 => self.panning = value,
+            _ => {}
+        }
+
+        old_value
+    }
+
+    fn has_address(&self, position: u16) -> bool {
+        position >= 0xFF10 && position <= 0xFF3F
+    }
+}
+
+impl APU {
+
+    pub const DEFAULT_SAMPLE_RATE: u32 = 48000;
+    const CLOCK_RATE: u32 = 1048576;           //M-cycles per second
+    const MAX_BUFFERED_SAMPLES: usize = 16384; //oldest samples are dropped if nobody takes them
+    const FRAME_SEQUENCER_BIT: u16 = 1 << 12;  //bit 4 of DIV
+
+    pub fn new() -> Self {
+        let mut apu = Self {
+            powered: false,
+
+            square_channel_1: SquareChannel::new(true),
+            square_channel_2: SquareChannel::new(false),
+            wave_channel: WaveChannel::new(),
+            noise_channel: NoiseChannel::new(),
+            wave_ram: [0; 16],
+
+            master_volume: 0,
+            panning: 0,
+
+            frame_sequencer_step: 0,
+            last_divider_bit: false,
+
+            sample_rate: Self::DEFAULT_SAMPLE_RATE,
+            sample_counter: 0,
+            high_pass_charge_factor: 0.0,
+            capacitor: (0.0, 0.0),
+            samples: VecDeque::new(),
+        };
+
+        apu.set_sample_rate(Self::DEFAULT_SAMPLE_RATE);
+        apu
+    }
+
+    /**
+        Advances the APU by one M-cycle. divider_counter is the timer's internal 16 bit counter.
+    */
+    pub fn clock(&mut self, divider_counter: u16) {
+        let divider_bit = divider_counter & Self::FRAME_SEQUENCER_BIT != 0;
+
+        if self.powered {
+            if self.last_divider_bit && !divider_bit {
+                self.step_frame_sequencer();
+            }
+
+            self.square_channel_1.clock();
+            self.square_channel_2.clock();
+            self.wave_channel.clock(&self.wave_ram);
+            self.noise_channel.clock();
+        }
+
+        self.last_divider_bit = divider_bit;
+
+        self.sample_counter += self.sample_rate;
+        if self.sample_counter >= Self::CLOCK_RATE {
+            self.sample_counter -= Self::CLOCK_RATE;
+
+            let sample = self.mix();
+            self.push_sample(sample);
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate.clamp(1, Self::CLOCK_RATE);
+        self.sample_counter = 0;
+        self.high_pass_charge_factor = 0.999958f32.powf(4194304.0 / self.sample_rate as f32);
+    }
+
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /**
+        Drains the buffered stereo samples, left then right, in the range -1.0 to 1.0
+    */
+    pub fn take_samples(&mut self) -> Vec<(f32, f32)> {
+        self.samples.drain(..).collect()
+    }
+
+    fn set_power(&mut self, powered: bool) {
+        if self.powered && !powered {
+            self.square_channel_1 = SquareChannel::new(true);
+            self.square_channel_2 = SquareChannel::new(false);
+            self.wave_channel = WaveChannel::new();
+            self.noise_channel = NoiseChannel::new();
+
+            self.master_volume = 0;
+            self.panning = 0;
+        } else if !self.powered && powered {
+            self.frame_sequencer_step = 0;
+        }
+
+        self.powered = powered;
+    }
+
+    fn get_status(&self) -> u8 {
+        0x70 | if self.powered { 0x80 } else { 0x00 }
+            | if self.square_channel_1.is_enabled() { 0x01 } else { 0x00 }
+            | if self.square_channel_2.is_enabled() { 0x02 } else { 0x00 }
+            | if self.wave_channel.is_enabled() { 0x04 } else { 0x00 }
+            | if self.noise_channel.is_enabled() { 0x08 } else { 0x00 }
+    }
+
+    fn step_frame_sequencer(&mut self) {
+        if self.frame_sequencer_step % 2 == 0 { //256Hz
+            self.square_channel_1.clock_length();
+            self.square_channel_2.clock_length();
+            self.wave_channel.clock_length();
+            self.noise_channel.clock_length();
+        }
+
+        if self.frame_sequencer_step == 2 || self.frame_sequencer_step == 6 { //128Hz
+            self.square_channel_1.clock_sweep();
+        }
+
+        if self.frame_sequencer_step == 7 { //64Hz
+            self.square_channel_1.clock_envelope();
+            self.square_channel_2.clock_envelope();
+            self.noise_channel.clock_envelope();
+        }
+
+        self.frame_sequencer_step = (self.frame_sequencer_step + 1) % 8;
+    }
+
+    fn mix(&mut self) -> (f32, f32) {
+        if !self.powered {
+            return (0.0, 0.0);
+        }
+
+        let outputs = [
+            Self::convert_dac(self.square_channel_1.get_output(), self.square_channel_1.is_dac_enabled()),
+            Self::convert_dac(self.square_channel_2.get_output(), self.square_channel_2.is_dac_enabled()),
+            Self::convert_dac(self.wave_channel.get_output(), self.wave_channel.is_dac_enabled()),
+            Self::convert_dac(self.noise_channel.get_output(), self.noise_channel.is_dac_enabled()),
+        ];
+
+        let mut left = 0.0;
+        let mut right = 0.0;
+
+        for (channel, output) in outputs.iter().enumerate() {
+            if self.panning & (0x10 << channel) != 0 {
+                left += output;
+            }
+            if self.panning & (0x01 << channel) != 0 {
+                right += output;
+            }
+        }
+
+        let left_volume = (((self.master_volume >> 4) & 0x07) + 1) as f32 / 8.0;
+        let right_volume = ((self.master_volume & 0x07) + 1) as f32 / 8.0;
+
+        (self.high_pass(left / 4.0 * left_volume, true), self.high_pass(right / 4.0 * right_volume, false))
+    }
+
+    fn convert_dac(output: u8, dac_enabled: bool) -> f32 {
+        if dac_enabled { output as f32 / 7.5 - 1.0 } else { 0.0 }
+    }
+
+    /**
+        Removes the DC offset left by the DACs, like the capacitor on the real output
+    */
+    fn high_pass(&mut self, input: f32, left: bool) -> f32 {
+        let capacitor = if left { &mut self.capacitor.0 } else { &mut self.capacitor.1 };
+        let output = input - *capacitor;
+
+        *capacitor = input - output * self.high_pass_charge_factor;
+
+        output
+    }
+
+    fn push_sample(&mut self, sample: (f32, f32)) {
+        if self.samples.len() >= Self::MAX_BUFFERED_SAMPLES {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back(sample);
+    }
+
+    /**
+        The sample rate belongs to the audio sink rather than the game, so it is kept, and samples not yet taken are dropped
+    */
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.powered);
+
+        self.square_channel_1.save_state(writer);
+        self.square_channel_2.save_state(writer);
+        self.wave_channel.save_state(writer);
+        self.noise_channel.save_state(writer);
+        writer.write_bytes(&self.wave_ram);
+
+        writer.write_u8(self.master_volume);
+        writer.write_u8(self.panning);
+        writer.write_u8(self.frame_sequencer_step);
+        writer.write_bool(self.last_divider_bit);
+
+        writer.write_f32(self.capacitor.0);
+        writer.write_f32(self.capacitor.1);
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.powered = reader.read_bool()?;
+
+        self.square_channel_1.load_state(reader)?;
+        self.square_channel_2.load_state(reader)?;
+        self.wave_channel.load_state(reader)?;
+        self.noise_channel.load_state(reader)?;
+        let wave_ram_length = self.wave_ram.len();
+        self.wave_ram.copy_from_slice(reader.read_bytes_of_length(wave_ram_length, "Wave RAM")?);
+
+        self.master_volume = reader.read_u8()?;
+        self.panning = reader.read_u8()?;
+        self.frame_sequencer_step = reader.read_u8()? % 8;
+        self.last_divider_bit = reader.read_bool()?;
+
+        self.capacitor = (reader.read_f32()?, reader.read_f32()?);
+        self.samples.clear();
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_powered_apu() -> APU {
+        let mut apu = APU::new();
+
+        apu.set(0xFF26, 0x80);
+        apu.set(0xFF24, 0x77);
+        apu.set(0xFF25, 0xFF);
+
+        apu
+    }
+
+    fn clock_frame_sequencer(apu: &mut APU, steps: u32) {
+        for _ in 0..steps {
+            apu.clock(APU::FRAME_SEQUENCER_BIT);
+            apu.clock(0);
+        }
+    }
+
+    #[test]
+    fn has_addresses_0xff10_to_0xff3f() {
+        let apu = APU::new();
+
+        assert!(!apu.has_address(0xFF0F));
+        assert!(apu.has_address(0xFF10));
+        assert!(apu.has_address(0xFF3F));
+        assert!(!apu.has_address(0xFF40));
+    }
+
+    #[test]
+    fn unused_registers_read_0xff() {
+        let apu = get_powered_apu();
+
+        assert_eq!(0xFF, apu.get(0xFF15));
+        assert_eq!(0xFF, apu.get(0xFF1F));
+        assert_eq!(0xFF, apu.get(0xFF27));
+        assert_eq!(0xFF, apu.get(0xFF2F));
+    }
+
+    #[test]
+    fn nr52_reports_power_and_channel_status() {
+        let mut apu = get_powered_apu();
+
+        assert_eq!(0xF0, apu.get(0xFF26));
+
+        apu.set(0xFF17, 0xF0);
+        apu.set(0xFF19, 0x80);
+
+        assert_eq!(0xF2, apu.get(0xFF26));
+    }
+
+    #[test]
+    fn powered_off_apu_ignores_register_writes() {
+        let mut apu = APU::new();
+
+        apu.set(0xFF24, 0x77);
+        apu.set(0xFF12, 0xF0);
+
+        assert_eq!(0x00, apu.get(0xFF24));
+        assert_eq!(0x00, apu.get(0xFF12));
+        assert_eq!(0x70, apu.get(0xFF26));
+    }
+
+    #[test]
+    fn powering_off_clears_registers_but_keeps_wave_ram() {
+        let mut apu = get_powered_apu();
+
+        apu.set(0xFF12, 0xF0);
+        apu.set(0xFF14, 0x80);
+        apu.set(0xFF30, 0x12);
+        apu.set(0xFF26, 0x00);
+
+        assert_eq!(0x00, apu.get(0xFF12));
+        assert_eq!(0x00, apu.get(0xFF25));
+        assert_eq!(0x70, apu.get(0xFF26));
+        assert_eq!(0x12, apu.get(0xFF30));
+    }
+
+    #[test]
+    fn frame_sequencer_clocks_length_on_div_falling_edge() {
+        let mut apu = get_powered_apu();
+
+        apu.set(0xFF12, 0xF0);
+        apu.set(0xFF11, 0x3E); //2 ticks remaining
+        apu.set(0xFF14, 0xC0);
+
+        clock_frame_sequencer(&mut apu, 2); //steps 0 and 1, only step 0 clocks length
+        assert_eq!(0xF1, apu.get(0xFF26));
+
+        clock_frame_sequencer(&mut apu, 1);
+        assert_eq!(0xF0, apu.get(0xFF26));
+    }
+
+    #[test]
+    fn frame_sequencer_does_not_step_without_falling_edge() {
+        let mut apu = get_powered_apu();
+
+        apu.set(0xFF12, 0xF0);
+        apu.set(0xFF11, 0x3F);
+        apu.set(0xFF14, 0xC0);
+
+        for _ in 0..10 {
+            apu.clock(APU::FRAME_SEQUENCER_BIT);
+        }
+
+        assert_eq!(0xF1, apu.get(0xFF26));
+    }
+
+    #[test]
+    fn produces_samples_at_sample_rate() {
+        let mut apu = get_powered_apu();
+        apu.set_sample_rate(32768);
+
+        for _ in 0..APU::CLOCK_RATE / 8 {
+            apu.clock(0);
+        }
+
+        assert_eq!(4096, apu.take_samples().len());
+        assert!(apu.take_samples().is_empty());
+    }
+
+    #[test]
+    fn drops_oldest_samples_when_full() {
+        let mut apu = APU::new();
+        apu.set_sample_rate(APU::CLOCK_RATE);
+
+        for _ in 0..APU::MAX_BUFFERED_SAMPLES + 10 {
+            apu.clock(0);
+        }
+
+        assert_eq!(APU::MAX_BUFFERED_SAMPLES, apu.take_samples().len());
+    }
+
+    #[test]
+    fn panning_routes_channels_to_each_side() {
+        let mut apu = get_powered_apu();
+        apu.set_sample_rate(APU::CLOCK_RATE);
+        apu.set(0xFF25, 0x01); //channel 1 right only
+
+        apu.set(0xFF11, 0xC0);
+        apu.set(0xFF12, 0xF0);
+        apu.set(0xFF13, 0xFF);
+        apu.set(0xFF14, 0x87);
+
+        apu.clock(0);
+        let (left, right) = apu.take_samples()[0];
+
+        assert_eq!(0.0, left);
+        assert!(right > 0.0);
+    }
+
+    #[test]
+    fn silent_when_powered_off() {
+        let mut apu = APU::new();
+        apu.set_sample_rate(APU::CLOCK_RATE);
+
+        apu.clock(0);
+
+        assert_eq!(vec![(0.0, 0.0)], apu.take_samples());
+    }
+}
@@ -0,0 +1,11 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum AudioError {
+    #[error("Could not write audio: {error}")]
+    WriteError { error: std::io::Error },
+    #[error("No audio output device available")]
+    NoOutputDevice,
+    #[error("Could not open audio stream: {message}")]
+    StreamError { message: String },
+}
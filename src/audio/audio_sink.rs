@@ -0,0 +1,27 @@
+use crate::audio::audio_error::AudioError;
+
+/**
+    Destination for the stereo samples produced by the APU. The main board pushes samples as they are produced
+    and the app drains the sink once per frame, which is when file output or playback actually happens.
+*/
+pub trait AudioSink {
+    fn get_sample_rate(&self) -> u32;
+
+    fn push_samples(&mut self, samples: &[(f32, f32)]);
+
+    fn drain(&mut self) -> Result<(), AudioError>;
+
+    /**
+        Called once the app stops using the sink, so anything it still holds can be written out
+    */
+    fn finish(&mut self) -> Result<(), AudioError> {
+        self.drain()
+    }
+
+    /**
+        Samples still waiting to be played, or None when the sink does not play in real time and so can't be used to pace emulation
+    */
+    fn get_queued_samples(&self) -> Option<usize> {
+        None
+    }
+}
@@ -0,0 +1,164 @@
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
+/**
+    Volume envelope shared by the square and noise channels (NR12, NR22, NR42).
+*/
+pub struct Envelope {
+    initial_volume: u8,
+    increasing: bool,
+    pace: u8,
+
+    volume: u8,
+    timer: u8,
+}
+
+impl Envelope {
+    pub fn new() -> Self {
+        Self {
+            initial_volume: 0,
+            increasing: false,
+            pace: 0,
+
+            volume: 0,
+            timer: 0,
+        }
+    }
+
+    pub fn read(&self) -> u8 {
+        (self.initial_volume << 4) | if self.increasing { 0x08 } else { 0x00 } | self.pace
+    }
+
+    pub fn write(&mut self, value: u8) {
+        self.initial_volume = value >> 4;
+        self.increasing = value & 0x08 != 0;
+        self.pace = value & 0x07;
+    }
+
+    /**
+        The channel's DAC is only powered while the upper 5 bits of the envelope register are not all 0
+    */
+    pub fn is_dac_enabled(&self) -> bool {
+        self.read() & 0xF8 != 0
+    }
+
+    pub fn trigger(&mut self) {
+        self.volume = self.initial_volume;
+        self.timer = self.pace;
+    }
+
+    pub fn clock(&mut self) {
+        if self.pace == 0 {
+            return;
+        }
+
+        if self.timer > 0 {
+            self.timer -= 1;
+        }
+
+        if self.timer == 0 {
+            self.timer = self.pace;
+
+            if self.increasing && self.volume < 0x0F {
+                self.volume += 1;
+            } else if !self.increasing && self.volume > 0 {
+                self.volume -= 1;
+            }
+        }
+    }
+
+    pub fn get_volume(&self) -> u8 {
+        self.volume
+    }
+
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u8(self.initial_volume);
+        writer.write_bool(self.increasing);
+        writer.write_u8(self.pace);
+        writer.write_u8(self.volume);
+        writer.write_u8(self.timer);
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.initial_volume = reader.read_u8()?;
+        self.increasing = reader.read_bool()?;
+        self.pace = reader.read_u8()?;
+        self.volume = reader.read_u8()?;
+        self.timer = reader.read_u8()?;
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_back_written_value() {
+        let mut envelope = Envelope::new();
+
+        envelope.write(0xAB);
+
+        assert_eq!(0xAB, envelope.read());
+    }
+
+    #[test]
+    fn trigger_loads_initial_volume() {
+        let mut envelope = Envelope::new();
+
+        envelope.write(0xA0);
+        envelope.trigger();
+
+        assert_eq!(0x0A, envelope.get_volume());
+    }
+
+    #[test]
+    fn dac_is_disabled_by_zero_volume_and_decreasing() {
+        let mut envelope = Envelope::new();
+
+        envelope.write(0x07);
+        assert!(!envelope.is_dac_enabled());
+
+        envelope.write(0x08);
+        assert!(envelope.is_dac_enabled());
+    }
+
+    #[test]
+    fn decreases_volume_every_pace_ticks() {
+        let mut envelope = Envelope::new();
+
+        envelope.write(0x52);
+        envelope.trigger();
+
+        envelope.clock();
+        assert_eq!(0x05, envelope.get_volume());
+
+        envelope.clock();
+        assert_eq!(0x04, envelope.get_volume());
+    }
+
+    #[test]
+    fn increases_volume_up_to_15() {
+        let mut envelope = Envelope::new();
+
+        envelope.write(0xE9);
+        envelope.trigger();
+
+        for _ in 0..5 {
+            envelope.clock();
+        }
+
+        assert_eq!(0x0F, envelope.get_volume());
+    }
+
+    #[test]
+    fn pace_0_stops_envelope() {
+        let mut envelope = Envelope::new();
+
+        envelope.write(0x50);
+        envelope.trigger();
+        envelope.clock();
+
+        assert_eq!(0x05, envelope.get_volume());
+    }
+}
@@ -0,0 +1,110 @@
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
+/**
+    Switches a channel off after a set number of 256Hz frame sequencer ticks when enabled through NRx4.
+*/
+pub struct LengthCounter {
+    counter: u16,
+    maximum: u16,
+    enabled: bool,
+}
+
+impl LengthCounter {
+    pub fn new(maximum: u16) -> Self {
+        Self {
+            counter: 0,
+            maximum,
+            enabled: false,
+        }
+    }
+
+    pub fn load(&mut self, length: u16) {
+        self.counter = self.maximum - (length % self.maximum);
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn trigger(&mut self) {
+        if self.counter == 0 {
+            self.counter = self.maximum;
+        }
+    }
+
+    /**
+        Returns true when the counter runs out and the channel should be switched off
+    */
+    pub fn clock(&mut self) -> bool {
+        if self.enabled && self.counter > 0 {
+            self.counter -= 1;
+            self.counter == 0
+        } else {
+            false
+        }
+    }
+
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u16(self.counter);
+        writer.write_bool(self.enabled);
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.counter = reader.read_u16()?.min(self.maximum);
+        self.enabled = reader.read_bool()?;
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn does_not_count_when_disabled() {
+        let mut length_counter = LengthCounter::new(64);
+        length_counter.load(63);
+
+        assert!(!length_counter.clock());
+    }
+
+    #[test]
+    fn expires_after_remaining_length() {
+        let mut length_counter = LengthCounter::new(64);
+        length_counter.load(62);
+        length_counter.set_enabled(true);
+
+        assert!(!length_counter.clock());
+        assert!(length_counter.clock());
+        assert!(!length_counter.clock());
+    }
+
+    #[test]
+    fn trigger_reloads_expired_counter_to_maximum() {
+        let mut length_counter = LengthCounter::new(256);
+        length_counter.set_enabled(true);
+
+        length_counter.trigger();
+
+        for _ in 0..255 {
+            assert!(!length_counter.clock());
+        }
+        assert!(length_counter.clock());
+    }
+
+    #[test]
+    fn trigger_keeps_running_counter() {
+        let mut length_counter = LengthCounter::new(64);
+        length_counter.load(63);
+        length_counter.set_enabled(true);
+
+        length_counter.trigger();
+
+        assert!(length_counter.clock());
+    }
+}
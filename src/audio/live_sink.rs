@@ -0,0 +1,97 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use parking_lot::Mutex;
+use crate::audio::audio_error::AudioError;
+use crate::audio::audio_sink::AudioSink;
+
+/**
+    Plays samples through the default output device. Samples are handed to the device's callback thread when drained;
+    if the callback runs dry it repeats the last sample rather than clicking back to 0.
+*/
+pub struct LiveSink {
+    _stream: cpal::Stream,
+    sample_rate: u32,
+
+    pending: Vec<(f32, f32)>,
+    queue: Arc<Mutex<VecDeque<(f32, f32)>>>,
+}
+
+impl LiveSink {
+
+    const MAX_QUEUED_SECONDS: u32 = 1; //stops latency building up forever if emulation runs fast
+
+    pub fn new() -> Result<Self, AudioError> {
+        let host = cpal::default_host();
+        let device = host.default_output_device().ok_or(AudioError::NoOutputDevice)?;
+
+        let sample_rate = device.default_output_config()
+            .map_err(|error| AudioError::StreamError { message: error.to_string() })?
+            .sample_rate().0;
+
+        let config = cpal::StreamConfig {
+            channels: 2,
+            sample_rate: cpal::SampleRate(sample_rate),
+            buffer_size: cpal::BufferSize::Default,
+        };
+
+        let queue = Arc::new(Mutex::new(VecDeque::new()));
+        let callback_queue = queue.clone();
+        let mut last_sample = (0.0, 0.0);
+
+        let stream = device.build_output_stream(
+            &config,
+            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                let mut queue = callback_queue.lock();
+
+                for frame in data.chunks_mut(2) {
+                    last_sample = queue.pop_front().unwrap_or(last_sample);
+
+                    frame[0] = last_sample.0;
+                    if frame.len() > 1 {
+                        frame[1] = last_sample.1;
+                    }
+                }
+            },
+            |error| eprintln!("Audio stream error: {}", error),
+            None
+        ).map_err(|error| AudioError::StreamError { message: error.to_string() })?;
+
+        stream.play().map_err(|error| AudioError::StreamError { message: error.to_string() })?;
+
+        Ok(Self {
+            _stream: stream,
+            sample_rate,
+
+            pending: vec![],
+            queue,
+        })
+    }
+}
+
+impl AudioSink for LiveSink {
+    fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn push_samples(&mut self, samples: &[(f32, f32)]) {
+        self.pending.extend_from_slice(samples);
+    }
+
+    fn drain(&mut self) -> Result<(), AudioError> {
+        let mut queue = self.queue.lock();
+        let max_queued = (self.sample_rate * Self::MAX_QUEUED_SECONDS) as usize;
+
+        queue.extend(self.pending.drain(..));
+
+        while queue.len() > max_queued {
+            queue.pop_front();
+        }
+
+        Ok(())
+    }
+
+    fn get_queued_samples(&self) -> Option<usize> {
+        Some(self.queue.lock().len() + self.pending.len())
+    }
+}
@@ -0,0 +1,19 @@
+mod apu;
+mod square_channel;
+mod wave_channel;
+mod noise_channel;
+mod envelope;
+mod length_counter;
+mod audio_error;
+mod audio_sink;
+mod wav_sink;
+#[cfg(test)]
+mod ring_buffer_sink;
+mod live_sink;
+
+pub use apu::APU;
+pub use audio_sink::AudioSink;
+pub use wav_sink::WavSink;
+#[cfg(test)]
+pub use ring_buffer_sink::RingBufferSink;
+pub use live_sink::LiveSink;
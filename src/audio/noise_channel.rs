@@ -0,0 +1,234 @@
+use crate::audio::envelope::Envelope;
+use crate::audio::length_counter::LengthCounter;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
+
+/**
+    Channel 4 outputs the inverted lowest bit of a 15-bit LFSR, which can be shortened to 7 bits through NR43.
+*/
+pub struct NoiseChannel {
+    enabled: bool,
+
+    clock_shift: u8,
+    short_mode: bool,
+    divisor_code: u8,
+    frequency_timer: i32,
+    lfsr: u16,
+
+    length_counter: LengthCounter,
+    envelope: Envelope,
+}
+
+impl NoiseChannel {
+
+    const DIVISORS: [i32; 8] = [8, 16, 32, 48, 64, 80, 96, 112];
+
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+
+            clock_shift: 0,
+            short_mode: false,
+            divisor_code: 0,
+            frequency_timer: 0,
+            lfsr: 0,
+
+            length_counter: LengthCounter::new(64),
+            envelope: Envelope::new(),
+        }
+    }
+
+    /**
+        Reads NR40-NR44, with write-only bits reading as 1
+    */
+    pub fn read_register(&self, index: u16) -> u8 {
+        match index {
+            2 => self.envelope.read(),
+            3 => (self.clock_shift << 4) | if self.short_mode { 0x08 } else { 0x00 } | self.divisor_code,
+            4 => if self.length_counter.is_enabled() { 0xFF } else { 0xBF },
+            _ => 0xFF
+        }
+    }
+
+    pub fn write_register(&mut self, index: u16, value: u8) {
+        match index {
+            1 => self.length_counter.load((value & 0x3F) as u16),
+            2 => {
+                self.envelope.write(value);
+
+                if !self.envelope.is_dac_enabled() {
+                    self.enabled = false;
+                }
+            }
+            3 => {
+                self.clock_shift = value >> 4;
+                self.short_mode = value & 0x08 != 0;
+                self.divisor_code = value & 0x07;
+            }
+            4 => {
+                self.length_counter.set_enabled(value & 0x40 != 0);
+
+                if value & 0x80 != 0 {
+                    self.trigger();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /**
+        Advances the channel by one M-cycle
+    */
+    pub fn clock(&mut self) {
+        self.frequency_timer -= 4;
+
+        while self.frequency_timer <= 0 {
+            self.frequency_timer += self.get_period();
+            self.step_lfsr();
+        }
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length_counter.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock();
+    }
+
+    /**
+        The 4 bit value sent to the DAC
+    */
+    pub fn get_output(&self) -> u8 {
+        if self.enabled && self.lfsr & 0x01 == 0 {
+            self.envelope.get_volume()
+        } else {
+            0
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn is_dac_enabled(&self) -> bool {
+        self.envelope.is_dac_enabled()
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.envelope.is_dac_enabled();
+        self.frequency_timer = self.get_period();
+        self.lfsr = 0x7FFF;
+
+        self.length_counter.trigger();
+        self.envelope.trigger();
+    }
+
+    fn step_lfsr(&mut self) {
+        let feedback = (self.lfsr ^ (self.lfsr >> 1)) & 0x01;
+
+        self.lfsr = (self.lfsr >> 1) | (feedback << 14);
+
+        if self.short_mode {
+            self.lfsr = (self.lfsr & !0x40) | (feedback << 6);
+        }
+    }
+
+    fn get_period(&self) -> i32 {
+        Self::DIVISORS[self.divisor_code as usize] << self.clock_shift
+    }
+
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.enabled);
+        writer.write_u8(self.clock_shift);
+        writer.write_bool(self.short_mode);
+        writer.write_u8(self.divisor_code);
+        writer.write_i32(self.frequency_timer);
+        writer.write_u16(self.lfsr);
+
+        self.length_counter.save_state(writer);
+        self.envelope.save_state(writer);
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.enabled = reader.read_bool()?;
+        self.clock_shift = reader.read_u8()?;
+        self.short_mode = reader.read_bool()?;
+        self.divisor_code = reader.read_u8()? & 0x07;
+        self.frequency_timer = reader.read_i32()?;
+        self.lfsr = reader.read_u16()?;
+
+        self.length_counter.load_state(reader)?;
+        self.envelope.load_state(reader)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_triggered_channel(nr43: u8) -> NoiseChannel {
+        let mut channel = NoiseChannel::new();
+
+        channel.write_register(2, 0xF0);
+        channel.write_register(3, nr43);
+        channel.write_register(4, 0x80);
+
+        channel
+    }
+
+    #[test]
+    fn trigger_resets_lfsr_to_silence() {
+        let channel = get_triggered_channel(0x00);
+
+        assert!(channel.is_enabled());
+        assert_eq!(0x7FFF, channel.lfsr);
+        assert_eq!(0, channel.get_output());
+    }
+
+    #[test]
+    fn lfsr_shifts_in_xor_of_low_bits() {
+        let mut channel = get_triggered_channel(0x00);
+        channel.lfsr = 0x0001;
+
+        channel.step_lfsr();
+
+        assert_eq!(0x4000, channel.lfsr);
+        assert_eq!(15, channel.get_output());
+    }
+
+    #[test]
+    fn short_mode_also_sets_bit_6() {
+        let mut channel = get_triggered_channel(0x08);
+        channel.lfsr = 0x0001;
+
+        channel.step_lfsr();
+
+        assert_eq!(0x4040, channel.lfsr);
+    }
+
+    #[test]
+    fn steps_lfsr_at_divisor_and_shift() {
+        let mut channel = get_triggered_channel(0x11); //divisor 16 << 1 = 32 clocks
+        channel.lfsr = 0x0001;
+
+        for _ in 0..7 {
+            channel.clock();
+        }
+        assert_eq!(0x0001, channel.lfsr);
+
+        channel.clock();
+        assert_eq!(0x4000, channel.lfsr);
+    }
+
+    #[test]
+    fn reads_back_polynomial_register() {
+        let channel = get_triggered_channel(0xAB);
+
+        assert_eq!(0xAB, channel.read_register(3));
+        assert_eq!(0xFF, channel.read_register(1));
+        assert_eq!(0xBF, channel.read_register(4));
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::VecDeque;
+use crate::audio::audio_error::AudioError;
+use crate::audio::audio_sink::AudioSink;
+
+/**
+    Keeps the most recent samples in memory, overwriting the oldest once full.
+*/
+pub struct RingBufferSink {
+    sample_rate: u32,
+    capacity: usize,
+    samples: VecDeque<(f32, f32)>,
+}
+
+impl RingBufferSink {
+    pub fn new(sample_rate: u32, capacity: usize) -> Self {
+        Self {
+            sample_rate,
+            capacity,
+            samples: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn take_samples(&mut self) -> Vec<(f32, f32)> {
+        self.samples.drain(..).collect()
+    }
+}
+
+impl AudioSink for RingBufferSink {
+    fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn push_samples(&mut self, samples: &[(f32, f32)]) {
+        for sample in samples {
+            if self.capacity == 0 {
+                return;
+            }
+            if self.samples.len() >= self.capacity {
+                self.samples.pop_front();
+            }
+
+            self.samples.push_back(*sample);
+        }
+    }
+
+    fn drain(&mut self) -> Result<(), AudioError> {
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_pushed_samples_in_order() {
+        let mut sink = RingBufferSink::new(48000, 4);
+
+        sink.push_samples(&[(0.1, 0.2), (0.3, 0.4)]);
+
+        assert_eq!(vec![(0.1, 0.2), (0.3, 0.4)], sink.take_samples());
+        assert_eq!(0, sink.len());
+    }
+
+    #[test]
+    fn overwrites_oldest_samples_when_full() {
+        let mut sink = RingBufferSink::new(48000, 2);
+
+        sink.push_samples(&[(0.1, 0.1), (0.2, 0.2), (0.3, 0.3)]);
+
+        assert_eq!(vec![(0.2, 0.2), (0.3, 0.3)], sink.take_samples());
+    }
+
+    #[test]
+    fn zero_capacity_keeps_nothing() {
+        let mut sink = RingBufferSink::new(48000, 0);
+
+        sink.push_samples(&[(0.1, 0.1)]);
+
+        assert_eq!(0, sink.len());
+    }
+
+    #[test]
+    fn is_not_real_time() {
+        let sink = RingBufferSink::new(44100, 1);
+
+        assert_eq!(44100, sink.get_sample_rate());
+        assert_eq!(None, sink.get_queued_samples());
+    }
+}
@@ -0,0 +1,411 @@
+use crate::audio::envelope::Envelope;
+use crate::audio::length_counter::LengthCounter;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
+
+/**
+    Channels 1 and 2. Only channel 1 has the frequency sweep unit, so writes to NR20 (0xFF15) are ignored.
+*/
+pub struct SquareChannel {
+    has_sweep: bool,
+    enabled: bool,
+
+    duty: u8,
+    duty_position: usize,
+    frequency: u16,
+    frequency_timer: i32,
+
+    length_counter: LengthCounter,
+    envelope: Envelope,
+
+    sweep_pace: u8,
+    sweep_decreasing: bool,
+    sweep_step: u8,
+    sweep_timer: u8,
+    sweep_enabled: bool,
+    shadow_frequency: u16,
+}
+
+impl SquareChannel {
+
+    const DUTY_PATTERNS: [[u8; 8]; 4] = [
+        [0, 0, 0, 0, 0, 0, 0, 1], //12.5%
+        [1, 0, 0, 0, 0, 0, 0, 1], //25%
+        [1, 0, 0, 0, 0, 1, 1, 1], //50%
+        [0, 1, 1, 1, 1, 1, 1, 0], //75%
+    ];
+    const MAX_FREQUENCY: u16 = 0x07FF;
+
+    pub fn new(has_sweep: bool) -> Self {
+        Self {
+            has_sweep,
+            enabled: false,
+
+            duty: 0,
+            duty_position: 0,
+            frequency: 0,
+            frequency_timer: 0,
+
+            length_counter: LengthCounter::new(64),
+            envelope: Envelope::new(),
+
+            sweep_pace: 0,
+            sweep_decreasing: false,
+            sweep_step: 0,
+            sweep_timer: 0,
+            sweep_enabled: false,
+            shadow_frequency: 0,
+        }
+    }
+
+    /**
+        Reads NRx0-NRx4, with write-only bits reading as 1
+    */
+    pub fn read_register(&self, index: u16) -> u8 {
+        match index {
+            0 if self.has_sweep => 0x80 | (self.sweep_pace << 4) | if self.sweep_decreasing { 0x08 } else { 0x00 } | self.sweep_step,
+            1 => (self.duty << 6) | 0x3F,
+            2 => self.envelope.read(),
+            4 => if self.length_counter.is_enabled() { 0xFF } else { 0xBF },
+            _ => 0xFF
+        }
+    }
+
+    pub fn write_register(&mut self, index: u16, value: u8) {
+        match index {
+            0 if self.has_sweep => {
+                self.sweep_pace = (value >> 4) & 0x07;
+                self.sweep_decreasing = value & 0x08 != 0;
+                self.sweep_step = value & 0x07;
+            }
+            1 => {
+                self.duty = value >> 6;
+                self.length_counter.load((value & 0x3F) as u16);
+            }
+            2 => {
+                self.envelope.write(value);
+
+                if !self.envelope.is_dac_enabled() {
+                    self.enabled = false;
+                }
+            }
+            3 => self.frequency = (self.frequency & 0x0700) | value as u16,
+            4 => {
+                self.frequency = (self.frequency & 0x00FF) | ((value as u16 & 0x07) << 8);
+                self.length_counter.set_enabled(value & 0x40 != 0);
+
+                if value & 0x80 != 0 {
+                    self.trigger();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /**
+        Advances the channel by one M-cycle
+    */
+    pub fn clock(&mut self) {
+        self.frequency_timer -= 4;
+
+        while self.frequency_timer <= 0 {
+            self.frequency_timer += self.get_period();
+            self.duty_position = (self.duty_position + 1) % 8;
+        }
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length_counter.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock();
+    }
+
+    pub fn clock_sweep(&mut self) {
+        if self.sweep_timer > 0 {
+            self.sweep_timer -= 1;
+        }
+
+        if self.sweep_timer == 0 {
+            self.sweep_timer = self.get_sweep_timer_period();
+
+            if self.sweep_enabled && self.sweep_pace != 0 {
+                let new_frequency = self.calculate_sweep();
+
+                if new_frequency > Self::MAX_FREQUENCY {
+                    self.enabled = false;
+                } else if self.sweep_step != 0 {
+                    self.frequency = new_frequency;
+                    self.shadow_frequency = new_frequency;
+
+                    if self.calculate_sweep() > Self::MAX_FREQUENCY {
+                        self.enabled = false;
+                    }
+                }
+            }
+        }
+    }
+
+    /**
+        The 4 bit value sent to the DAC
+    */
+    pub fn get_output(&self) -> u8 {
+        if self.enabled {
+            Self::DUTY_PATTERNS[self.duty as usize][self.duty_position] * self.envelope.get_volume()
+        } else {
+            0
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn is_dac_enabled(&self) -> bool {
+        self.envelope.is_dac_enabled()
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.envelope.is_dac_enabled();
+        self.frequency_timer = self.get_period();
+
+        self.length_counter.trigger();
+        self.envelope.trigger();
+
+        if self.has_sweep {
+            self.shadow_frequency = self.frequency;
+            self.sweep_timer = self.get_sweep_timer_period();
+            self.sweep_enabled = self.sweep_pace != 0 || self.sweep_step != 0;
+
+            if self.sweep_step != 0 && self.calculate_sweep() > Self::MAX_FREQUENCY {
+                self.enabled = false;
+            }
+        }
+    }
+
+    fn calculate_sweep(&self) -> u16 {
+        let delta = self.shadow_frequency >> self.sweep_step;
+
+        if self.sweep_decreasing {
+            self.shadow_frequency.wrapping_sub(delta)
+        } else {
+            self.shadow_frequency + delta
+        }
+    }
+
+    fn get_sweep_timer_period(&self) -> u8 {
+        if self.sweep_pace == 0 { 8 } else { self.sweep_pace }
+    }
+
+    fn get_period(&self) -> i32 {
+        (2048 - self.frequency as i32) * 4
+    }
+
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.enabled);
+        writer.write_u8(self.duty);
+        writer.write_u8(self.duty_position as u8);
+        writer.write_u16(self.frequency);
+        writer.write_i32(self.frequency_timer);
+
+        self.length_counter.save_state(writer);
+        self.envelope.save_state(writer);
+
+        writer.write_u8(self.sweep_pace);
+        writer.write_bool(self.sweep_decreasing);
+        writer.write_u8(self.sweep_step);
+        writer.write_u8(self.sweep_timer);
+        writer.write_bool(self.sweep_enabled);
+        writer.write_u16(self.shadow_frequency);
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.enabled = reader.read_bool()?;
+        self.duty = reader.read_u8()? & 0x03;
+        self.duty_position = reader.read_u8()? as usize % 8;
+        self.frequency = reader.read_u16()?;
+        self.frequency_timer = reader.read_i32()?;
+
+        self.length_counter.load_state(reader)?;
+        self.envelope.load_state(reader)?;
+
+        self.sweep_pace = reader.read_u8()?;
+        self.sweep_decreasing = reader.read_bool()?;
+        self.sweep_step = reader.read_u8()?;
+        self.sweep_timer = reader.read_u8()?;
+        self.sweep_enabled = reader.read_bool()?;
+        self.shadow_frequency = reader.read_u16()?;
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_triggered_channel(has_sweep: bool, frequency: u16) -> SquareChannel {
+        let mut channel = SquareChannel::new(has_sweep);
+
+        channel.write_register(1, 0x80); //50% duty
+        channel.write_register(2, 0xF0);
+        channel.write_register(3, (frequency & 0xFF) as u8);
+        channel.write_register(4, 0x80 | (frequency >> 8) as u8);
+
+        channel
+    }
+
+    #[test]
+    fn is_disabled_until_triggered() {
+        let channel = SquareChannel::new(false);
+
+        assert!(!channel.is_enabled());
+        assert_eq!(0, channel.get_output());
+    }
+
+    #[test]
+    fn trigger_enables_channel() {
+        let channel = get_triggered_channel(false, 0x700);
+
+        assert!(channel.is_enabled());
+    }
+
+    #[test]
+    fn trigger_with_dac_off_does_not_enable_channel() {
+        let mut channel = SquareChannel::new(false);
+
+        channel.write_register(2, 0x00);
+        channel.write_register(4, 0x80);
+
+        assert!(!channel.is_enabled());
+    }
+
+    #[test]
+    fn disabling_dac_disables_channel() {
+        let mut channel = get_triggered_channel(false, 0x700);
+
+        channel.write_register(2, 0x07);
+
+        assert!(!channel.is_enabled());
+    }
+
+    #[test]
+    fn steps_through_duty_pattern_at_frequency() {
+        let mut channel = get_triggered_channel(false, 0x7FF); //period of 4 clocks, one duty step per M-cycle
+        let mut outputs = vec![];
+
+        for _ in 0..8 {
+            channel.clock();
+            outputs.push(channel.get_output());
+        }
+
+        assert_eq!(vec![0, 0, 0, 0, 15, 15, 15, 15], outputs);
+    }
+
+    #[test]
+    fn unreadable_bits_read_as_1() {
+        let mut channel = SquareChannel::new(true);
+
+        channel.write_register(0, 0x00);
+        channel.write_register(1, 0x40);
+        channel.write_register(3, 0x12);
+        channel.write_register(4, 0x07);
+
+        assert_eq!(0x80, channel.read_register(0));
+        assert_eq!(0x7F, channel.read_register(1));
+        assert_eq!(0xFF, channel.read_register(3));
+        assert_eq!(0xBF, channel.read_register(4));
+    }
+
+    #[test]
+    fn channel_2_has_no_sweep_register() {
+        let mut channel = SquareChannel::new(false);
+
+        channel.write_register(0, 0x12);
+
+        assert_eq!(0xFF, channel.read_register(0));
+    }
+
+    #[test]
+    fn length_disables_channel() {
+        let mut channel = get_triggered_channel(false, 0x700);
+
+        channel.write_register(1, 0x3F);
+        channel.write_register(4, 0x47);
+        channel.clock_length();
+
+        assert!(!channel.is_enabled());
+    }
+
+    #[test]
+    fn sweep_increases_frequency() {
+        let mut channel = SquareChannel::new(true);
+
+        channel.write_register(0, 0x11); //pace 1, increasing, step 1
+        channel.write_register(2, 0xF0);
+        channel.write_register(3, 0x00);
+        channel.write_register(4, 0x81);
+
+        channel.clock_sweep();
+
+        assert_eq!(0x180, channel.frequency);
+    }
+
+    #[test]
+    fn sweep_decreases_frequency() {
+        let mut channel = SquareChannel::new(true);
+
+        channel.write_register(0, 0x19); //pace 1, decreasing, step 1
+        channel.write_register(2, 0xF0);
+        channel.write_register(3, 0x00);
+        channel.write_register(4, 0x81);
+
+        channel.clock_sweep();
+
+        assert_eq!(0x080, channel.frequency);
+    }
+
+    #[test]
+    fn sweep_overflow_on_trigger_disables_channel() {
+        let mut channel = SquareChannel::new(true);
+
+        channel.write_register(0, 0x01);
+        channel.write_register(2, 0xF0);
+        channel.write_register(3, 0xFF);
+        channel.write_register(4, 0x87);
+
+        assert!(!channel.is_enabled());
+    }
+
+    #[test]
+    fn sweep_overflow_disables_channel() {
+        let mut channel = SquareChannel::new(true);
+
+        channel.write_register(0, 0x11);
+        channel.write_register(2, 0xF0);
+        channel.write_register(3, 0x00);
+        channel.write_register(4, 0x85);
+
+        channel.clock_sweep();
+
+        assert!(!channel.is_enabled());
+    }
+
+    #[test]
+    fn envelope_changes_output_volume() {
+        let mut channel = SquareChannel::new(false);
+
+        channel.write_register(1, 0xC0); //75% duty, high on the first step
+        channel.write_register(2, 0xF1);
+        channel.write_register(3, 0xFF);
+        channel.write_register(4, 0x87);
+
+        channel.clock_envelope();
+        channel.clock();
+
+        assert_eq!(14, channel.get_output());
+    }
+}
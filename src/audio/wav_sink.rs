@@ -0,0 +1,190 @@
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+use crate::audio::audio_error::AudioError;
+use crate::audio::audio_sink::AudioSink;
+
+/**
+    Records samples to a 16 bit stereo PCM WAV file. The RIFF and data sizes are filled in when the sink is finished or dropped.
+*/
+pub struct WavSink<W: Write + Seek> {
+    writer: Option<W>,
+    sample_rate: u32,
+    pending: Vec<(f32, f32)>,
+    data_size: u32,
+}
+
+impl WavSink<BufWriter<File>> {
+    pub fn create(path: &Path, sample_rate: u32) -> Result<Self, AudioError> {
+        let file = File::create(path).map_err(|error| AudioError::WriteError { error })?;
+
+        Self::new(BufWriter::new(file), sample_rate)
+    }
+}
+
+impl<W: Write + Seek> WavSink<W> {
+
+    const CHANNELS: u16 = 2;
+    const BITS_PER_SAMPLE: u16 = 16;
+    const HEADER_SIZE: u32 = 44;
+
+    pub fn new(mut writer: W, sample_rate: u32) -> Result<Self, AudioError> {
+        Self::write_header(&mut writer, sample_rate, 0).map_err(|error| AudioError::WriteError { error })?;
+
+        Ok(Self {
+            writer: Some(writer),
+            sample_rate,
+            pending: vec![],
+            data_size: 0,
+        })
+    }
+
+    fn finish_writer(&mut self) -> Result<(), AudioError> {
+        self.drain()?;
+
+        match &mut self.writer {
+            Some(writer) => {
+                let result = writer.seek(SeekFrom::Start(0))
+                    .and_then(|_| Self::write_header(writer, self.sample_rate, self.data_size))
+                    .and_then(|_| writer.seek(SeekFrom::End(0)))
+                    .and_then(|_| writer.flush());
+
+                result.map_err(|error| AudioError::WriteError { error })
+            }
+            None => Ok(())
+        }
+    }
+
+    fn write_header(writer: &mut W, sample_rate: u32, data_size: u32) -> std::io::Result<()> {
+        let block_align = Self::CHANNELS * Self::BITS_PER_SAMPLE / 8;
+
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&(Self::HEADER_SIZE - 8 + data_size).to_le_bytes())?;
+        writer.write_all(b"WAVE")?;
+
+        writer.write_all(b"fmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        writer.write_all(&1u16.to_le_bytes())?; //PCM
+        writer.write_all(&Self::CHANNELS.to_le_bytes())?;
+        writer.write_all(&sample_rate.to_le_bytes())?;
+        writer.write_all(&(sample_rate * block_align as u32).to_le_bytes())?;
+        writer.write_all(&block_align.to_le_bytes())?;
+        writer.write_all(&Self::BITS_PER_SAMPLE.to_le_bytes())?;
+
+        writer.write_all(b"data")?;
+        writer.write_all(&data_size.to_le_bytes())
+    }
+
+    fn convert_sample(sample: f32) -> [u8; 2] {
+        ((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes()
+    }
+}
+
+impl<W: Write + Seek> AudioSink for WavSink<W> {
+    fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn push_samples(&mut self, samples: &[(f32, f32)]) {
+        self.pending.extend_from_slice(samples);
+    }
+
+    fn drain(&mut self) -> Result<(), AudioError> {
+        let writer = match &mut self.writer {
+            Some(writer) => writer,
+            None => return Ok(())
+        };
+
+        let mut data = Vec::with_capacity(self.pending.len() * 4);
+
+        for (left, right) in self.pending.drain(..) {
+            data.extend_from_slice(&Self::convert_sample(left));
+            data.extend_from_slice(&Self::convert_sample(right));
+        }
+
+        writer.write_all(&data).map_err(|error| AudioError::WriteError { error })?;
+        self.data_size += data.len() as u32;
+
+        Ok(())
+    }
+
+    /**
+        Writes any pending samples and the final header
+    */
+    fn finish(&mut self) -> Result<(), AudioError> {
+        self.finish_writer()
+    }
+}
+
+impl<W: Write + Seek> Drop for WavSink<W> {
+    fn drop(&mut self) {
+        let _ = self.finish_writer();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use super::*;
+
+    fn get_u32(data: &[u8], position: usize) -> u32 {
+        u32::from_le_bytes([data[position], data[position + 1], data[position + 2], data[position + 3]])
+    }
+
+    #[test]
+    fn writes_pcm_header() {
+        let mut sink = WavSink::new(Cursor::new(vec![]), 48000).unwrap();
+        sink.finish().unwrap();
+        let data = sink.writer.take().unwrap().into_inner();
+
+        assert_eq!(44, data.len());
+        assert_eq!(b"RIFF", &data[0..4]);
+        assert_eq!(36, get_u32(&data, 4));
+        assert_eq!(b"WAVEfmt ", &data[8..16]);
+        assert_eq!(2, data[22]);
+        assert_eq!(48000, get_u32(&data, 24));
+        assert_eq!(192000, get_u32(&data, 28));
+        assert_eq!(b"data", &data[36..40]);
+    }
+
+    #[test]
+    fn writes_samples_as_16_bit_stereo() {
+        let mut sink = WavSink::new(Cursor::new(vec![]), 48000).unwrap();
+
+        sink.push_samples(&[(1.0, -1.0), (0.0, 2.0)]);
+        sink.finish().unwrap();
+        let data = sink.writer.take().unwrap().into_inner();
+
+        assert_eq!(8, get_u32(&data, 40));
+        assert_eq!(44, get_u32(&data, 4));
+        assert_eq!(vec![0xFF, 0x7F, 0x01, 0x80, 0x00, 0x00, 0xFF, 0x7F], data[44..].to_vec());
+    }
+
+    #[test]
+    fn drain_writes_pending_samples() {
+        let mut sink = WavSink::new(Cursor::new(vec![]), 48000).unwrap();
+
+        sink.push_samples(&[(0.0, 0.0)]);
+        sink.drain().unwrap();
+
+        assert_eq!(48, sink.writer.as_ref().unwrap().get_ref().len());
+        assert!(sink.pending.is_empty());
+    }
+
+    #[test]
+    fn create_writes_file_on_drop() {
+        let path = std::env::temp_dir().join("gameboy_emulator_wav_sink_test.wav");
+
+        {
+            let mut sink = WavSink::create(&path, 22050).unwrap();
+            sink.push_samples(&[(0.5, 0.5); 10]);
+        }
+
+        let data = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(84, data.len());
+        assert_eq!(40, get_u32(&data, 40));
+    }
+}
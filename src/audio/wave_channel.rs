@@ -0,0 +1,236 @@
+use crate::audio::length_counter::LengthCounter;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
+
+/**
+    Channel 3 plays back the 32 4-bit samples in wave RAM. Wave RAM itself lives in the APU since it
+    keeps its contents when the APU is powered off.
+*/
+pub struct WaveChannel {
+    enabled: bool,
+    dac_enabled: bool,
+
+    output_level: u8,
+    frequency: u16,
+    frequency_timer: i32,
+
+    position: usize,
+    sample: u8,
+
+    length_counter: LengthCounter,
+}
+
+impl WaveChannel {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            dac_enabled: false,
+
+            output_level: 0,
+            frequency: 0,
+            frequency_timer: 0,
+
+            position: 0,
+            sample: 0,
+
+            length_counter: LengthCounter::new(256),
+        }
+    }
+
+    /**
+        Reads NR30-NR34, with write-only bits reading as 1
+    */
+    pub fn read_register(&self, index: u16) -> u8 {
+        match index {
+            0 => if self.dac_enabled { 0xFF } else { 0x7F },
+            2 => (self.output_level << 5) | 0x9F,
+            4 => if self.length_counter.is_enabled() { 0xFF } else { 0xBF },
+            _ => 0xFF
+        }
+    }
+
+    pub fn write_register(&mut self, index: u16, value: u8) {
+        match index {
+            0 => {
+                self.dac_enabled = value & 0x80 != 0;
+
+                if !self.dac_enabled {
+                    self.enabled = false;
+                }
+            }
+            1 => self.length_counter.load(value as u16),
+            2 => self.output_level = (value >> 5) & 0x03,
+            3 => self.frequency = (self.frequency & 0x0700) | value as u16,
+            4 => {
+                self.frequency = (self.frequency & 0x00FF) | ((value as u16 & 0x07) << 8);
+                self.length_counter.set_enabled(value & 0x40 != 0);
+
+                if value & 0x80 != 0 {
+                    self.trigger();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /**
+        Advances the channel by one M-cycle
+    */
+    pub fn clock(&mut self, wave_ram: &[u8; 16]) {
+        self.frequency_timer -= 4;
+
+        while self.frequency_timer <= 0 {
+            self.frequency_timer += self.get_period();
+            self.position = (self.position + 1) % 32;
+
+            let byte = wave_ram[self.position / 2];
+            self.sample = if self.position % 2 == 0 { byte >> 4 } else { byte & 0x0F }; //high nibble is played first
+        }
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length_counter.clock() {
+            self.enabled = false;
+        }
+    }
+
+    /**
+        The 4 bit value sent to the DAC
+    */
+    pub fn get_output(&self) -> u8 {
+        if !self.enabled {
+            return 0;
+        }
+
+        match self.output_level {
+            0 => 0,
+            1 => self.sample,
+            2 => self.sample >> 1,
+            _ => self.sample >> 2
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn is_dac_enabled(&self) -> bool {
+        self.dac_enabled
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.dac_enabled;
+        self.frequency_timer = self.get_period();
+        self.position = 0;
+
+        self.length_counter.trigger();
+    }
+
+    fn get_period(&self) -> i32 {
+        (2048 - self.frequency as i32) * 2
+    }
+
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.enabled);
+        writer.write_bool(self.dac_enabled);
+        writer.write_u8(self.output_level);
+        writer.write_u16(self.frequency);
+        writer.write_i32(self.frequency_timer);
+        writer.write_u8(self.position as u8);
+        writer.write_u8(self.sample);
+
+        self.length_counter.save_state(writer);
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.enabled = reader.read_bool()?;
+        self.dac_enabled = reader.read_bool()?;
+        self.output_level = reader.read_u8()?;
+        self.frequency = reader.read_u16()?;
+        self.frequency_timer = reader.read_i32()?;
+        self.position = reader.read_u8()? as usize % 32;
+        self.sample = reader.read_u8()?;
+
+        self.length_counter.load_state(reader)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WAVE_RAM: [u8; 16] = [0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF, 0xFE, 0xDC, 0xBA, 0x98, 0x76, 0x54, 0x32, 0x10];
+
+    fn get_triggered_channel(output_level: u8) -> WaveChannel {
+        let mut channel = WaveChannel::new();
+
+        channel.write_register(0, 0x80);
+        channel.write_register(2, output_level << 5);
+        channel.write_register(3, 0xFE); //period of 4 clocks, one sample per M-cycle
+        channel.write_register(4, 0x87);
+
+        channel
+    }
+
+    #[test]
+    fn trigger_needs_dac() {
+        let mut channel = WaveChannel::new();
+
+        channel.write_register(4, 0x80);
+        assert!(!channel.is_enabled());
+
+        channel.write_register(0, 0x80);
+        channel.write_register(4, 0x80);
+        assert!(channel.is_enabled());
+    }
+
+    #[test]
+    fn plays_wave_ram_nibbles_in_order() {
+        let mut channel = get_triggered_channel(1);
+        let mut outputs = vec![];
+
+        for _ in 0..4 {
+            channel.clock(&WAVE_RAM);
+            outputs.push(channel.get_output());
+        }
+
+        assert_eq!(vec![1, 2, 3, 4], outputs);
+    }
+
+    #[test]
+    fn output_level_shifts_sample() {
+        for (output_level, expected) in [(0, 0), (1, 0x0E), (2, 0x07), (3, 0x03)] {
+            let mut channel = get_triggered_channel(output_level);
+
+            for _ in 0..14 {
+                channel.clock(&WAVE_RAM);
+            }
+
+            assert_eq!(expected, channel.get_output());
+        }
+    }
+
+    #[test]
+    fn unreadable_bits_read_as_1() {
+        let mut channel = WaveChannel::new();
+
+        channel.write_register(1, 0x12);
+        channel.write_register(2, 0x40);
+
+        assert_eq!(0x7F, channel.read_register(0));
+        assert_eq!(0xFF, channel.read_register(1));
+        assert_eq!(0xDF, channel.read_register(2));
+    }
+
+    #[test]
+    fn length_of_256_disables_channel() {
+        let mut channel = WaveChannel::new();
+
+        channel.write_register(0, 0x80);
+        channel.write_register(1, 0xFF);
+        channel.write_register(4, 0xC0);
+        channel.clock_length();
+
+        assert!(!channel.is_enabled());
+    }
+}
@@ -2,10 +2,24 @@ use std::sync::Arc;
 use parking_lot::Mutex;
 use crate::cpu::interrupt::Interrupt;
 use crate::memory::MemoryController;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
 
 pub trait CPU {
 
     fn clock (&mut self, memory: Arc<Mutex<MemoryController>>);
     fn try_interrupt(&mut self, memory: Arc<Mutex<MemoryController>>, interrupt: Interrupt);
     fn reset(&mut self);
+
+    /**
+        Address of the instruction being executed
+    */
+    fn get_pc(&self) -> u16;
+
+    /**
+        BC, DE and HL, where test ROMs leave their result signature
+    */
+    fn get_bc_de_hl(&self) -> (u16, u16, u16);
+
+    fn save_state(&self, writer: &mut StateWriter);
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError>;
 }
@@ -9,6 +9,7 @@ use crate::cpu::interrupt::Interrupt;
 use crate::cpu::register::Register;
 use crate::cpu::registers::Registers;
 use crate::memory::{MemoryController, MemoryTrait};
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
 
 pub struct GameBoyCPU {
     registers: Registers,
@@ -16,6 +17,7 @@ pub struct GameBoyCPU {
     enable_interrupts: bool,
     is_halted: bool,
     current_instruction: Box<dyn Instruction>,
+    instruction_address: u16,
     interrupt: Option<Interrupt>,
 
     callstack: VecDeque<String>,
@@ -73,6 +75,46 @@ impl CPU for GameBoyCPU {
     fn reset(&mut self) {
         *self = GameBoyCPU::new_with_nop();
     }
+
+    fn get_pc(&self) -> u16 {
+        self.instruction_address
+    }
+
+    fn get_bc_de_hl(&self) -> (u16, u16, u16) {
+        (self.registers.bc.get_value(), self.registers.de.get_value(), self.registers.hl.get_value())
+    }
+
+    /**
+        Flags are stored in F, so the ALU has nothing of its own to save. The current instruction is saved as its
+        opcode followed by its own progress, so it can be decoded again and resumed from the same M-cycle.
+    */
+    fn save_state(&self, writer: &mut StateWriter) {
+        for register in [&self.registers.af, &self.registers.bc, &self.registers.de, &self.registers.hl, &self.registers.pc, &self.registers.sp] {
+            writer.write_u16(register.get_value());
+        }
+
+        writer.write_bool(self.enable_interrupts);
+        writer.write_bool(self.is_halted);
+        writer.write_u8(self.current_instruction.get_opcode());
+        self.current_instruction.save_state(writer);
+        writer.write_u16(self.instruction_address);
+        writer.write_u8(self.interrupt.map_or(0, |interrupt| interrupt.get_bit_mask()));
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        for register in [&mut self.registers.af, &mut self.registers.bc, &mut self.registers.de, &mut self.registers.hl, &mut self.registers.pc, &mut self.registers.sp] {
+            register.set_value(reader.read_u16()?);
+        }
+
+        self.enable_interrupts = reader.read_bool()?;
+        self.is_halted = reader.read_bool()?;
+        self.current_instruction = decode_instruction(&reader.read_u8()?);
+        self.current_instruction.load_state(reader)?;
+        self.instruction_address = reader.read_u16()?;
+        self.interrupt = Interrupt::from_bit_mask(reader.read_u8()?);
+
+        Ok(())
+    }
 }
 
 impl GameBoyCPU {
@@ -98,6 +140,7 @@ impl GameBoyCPU {
             enable_interrupts: false,
             is_halted: false,
             current_instruction: first_instruction,
+            instruction_address: 0,
             interrupt: None,
 
             callstack: VecDeque::new()
@@ -133,7 +176,8 @@ impl GameBoyCPU {
         //     panic!("");
         // }
 
-        let opcode = memory.lock().get(self.registers.pc.get_value());
+        self.instruction_address = self.registers.pc.get_value();
+        let opcode = memory.lock().get(self.instruction_address);
 
         self.current_instruction = decode_instruction(&opcode);
 
@@ -216,6 +260,85 @@ mod tests {
         assert_eq!(0x101, cpu.registers.pc.get_value()); //next instruction will be RST 38 in uninitialised ROM space
     }
 
+    #[test]
+    fn get_pc_returns_address_of_current_instruction() {
+        let nullable_internal = Rc::new(RefCell::new(NullableInstructionInternal::new()));
+        let nullable_instruction = Box::new(NullableInstruction::new(nullable_internal.clone(), 0xDD, true));
+        let memory = Arc::new(Mutex::new(MemoryController::new()));
+
+        let mut cpu = GameBoyCPU::new(nullable_instruction);
+
+        cpu.clock(memory.clone());
+
+        assert_eq!(0x100, cpu.get_pc());
+    }
+
+    #[test]
+    fn get_bc_de_hl_returns_register_pairs() {
+        let mut cpu = GameBoyCPU::new_with_nop();
+        cpu.registers.bc.set_value(0x0305);
+        cpu.registers.de.set_value(0x080D);
+        cpu.registers.hl.set_value(0x1522);
+
+        assert_eq!((0x0305, 0x080D, 0x1522), cpu.get_bc_de_hl());
+    }
+
+    #[test]
+    fn load_state_restores_saved_state() {
+        let memory = Arc::new(Mutex::new(MemoryController::new()));
+        memory.lock().set(0xC000, 0x3C); //INC A
+        let mut cpu = GameBoyCPU::new_with_nop();
+        cpu.registers.af.set_value(0x12B0);
+        cpu.registers.bc.set_value(0x3456);
+        cpu.registers.pc.set_value(0xC000);
+        cpu.registers.sp.set_value(0xDFF0);
+        cpu.clock(memory.clone());
+        cpu.enable_interrupts = true;
+        cpu.interrupt = Some(Interrupt::Timer);
+
+        let mut writer = StateWriter::new();
+        cpu.save_state(&mut writer);
+        let state = writer.into_data();
+
+        let mut loaded_cpu = GameBoyCPU::new_with_nop();
+        let mut reader = StateReader::new(&state);
+        loaded_cpu.load_state(&mut reader).unwrap();
+
+        assert!(reader.is_finished());
+        assert_eq!(cpu.registers.to_string(), loaded_cpu.registers.to_string());
+        assert_eq!(cpu.current_instruction.get_opcode(), loaded_cpu.current_instruction.get_opcode());
+        assert_eq!(cpu.get_pc(), loaded_cpu.get_pc());
+        assert_eq!(true, loaded_cpu.enable_interrupts);
+        assert_eq!(cpu.interrupt, loaded_cpu.interrupt);
+    }
+
+    #[test]
+    fn load_state_resumes_instruction_part_way_through() {
+        let memory = Arc::new(Mutex::new(MemoryController::new()));
+        memory.lock().set(0xC000, 0xFA); //LD A, (0xD000)
+        memory.lock().set(0xC001, 0x00);
+        memory.lock().set(0xC002, 0xD0);
+        memory.lock().set(0xD000, 0x77);
+        let mut cpu = GameBoyCPU::new_with_nop();
+        cpu.registers.pc.set_value(0xC000);
+        cpu.clock(memory.clone());
+        cpu.clock(memory.clone()); //reads the low byte of the address
+
+        let mut writer = StateWriter::new();
+        cpu.save_state(&mut writer);
+        let state = writer.into_data();
+
+        let mut loaded_cpu = GameBoyCPU::new_with_nop();
+        loaded_cpu.load_state(&mut StateReader::new(&state)).unwrap();
+        memory.lock().set(0xC001, 0x55);
+        for _ in 0..3 {
+            loaded_cpu.clock(memory.clone());
+        }
+
+        assert_eq!(0x77, loaded_cpu.registers.a.borrow().get_value());
+        assert_eq!(0xC003, loaded_cpu.get_pc());
+    }
+
     #[test]
     fn interrupt_sets_address_properly() {
         let memory = Arc::new(Mutex::new(MemoryController::new()));
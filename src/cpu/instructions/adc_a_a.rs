@@ -25,6 +25,8 @@ impl Instruction for AdcAA {
         0x8F
     }
 
+    instruction_state!();
+
     fn act(&mut self, registers: &mut Registers, alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         let fake_register = Register8::new(registers.a.borrow().get_value());
         alu.adc(&mut *registers.a.clone().borrow_mut(), &fake_register);
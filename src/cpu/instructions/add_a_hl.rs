@@ -31,6 +31,8 @@ impl Instruction for AddAHl {
         0x86
     }
 
+    instruction_state!(counter: u8, value: u8);
+
     fn act(&mut self, registers: &mut Registers, alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         if self.counter == 1 {
             self.value = memory_controller.lock().get(registers.hl.get_value());
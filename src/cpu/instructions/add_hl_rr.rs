@@ -32,6 +32,8 @@ macro_rules! add_hl_rr {
                     $opcode
                 }
 
+                instruction_state!(counter: u8);
+
                 fn act(&mut self, registers: &mut Registers, alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
                     if self.counter == 1 {
                         let temp_register = Register16::new(registers.$register.get_value());
@@ -26,6 +26,8 @@ impl Instruction for AddSpN {
         0xE8
     }
 
+    instruction_state!(counter: u8, relative_address: u8);
+
     fn act(&mut self, registers: &mut Registers, alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         if self.counter == 3 {
             self.relative_address = memory_controller.lock().get(registers.pc.get_value());
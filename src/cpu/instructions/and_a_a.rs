@@ -25,6 +25,8 @@ impl Instruction for AndAA {
         0xA7
     }
 
+    instruction_state!();
+
     fn act(&mut self, registers: &mut Registers, alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         let fake_register = Register8::new(registers.a.borrow().get_value());
         alu.and(&mut *registers.a.clone().borrow_mut(), &fake_register);
@@ -22,6 +22,8 @@ impl Instruction for BadInstruction {
         self.opcode
     }
 
+    instruction_state!();
+
     fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         println!("Bad instruction '{:#X}' at address '{:#X}'", self.opcode, registers.pc.get_value().wrapping_sub(1));
 
@@ -30,6 +30,8 @@ macro_rules! bit_b_hl {
                     $opcode
                 }
 
+                instruction_state!(counter: u8, value_register: Register8);
+
                 fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
                     if self.counter == 3 {
                         self.value_register.set_value(memory_controller.lock().get(registers.hl.get_value()));
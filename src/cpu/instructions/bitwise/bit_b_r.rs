@@ -26,6 +26,8 @@ macro_rules! bit_b_r {
                     $opcode
                 }
 
+                instruction_state!(counter: u8);
+
                 fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
                     if self.counter == 1 {
                         let mut flags = registers.f.borrow_mut();
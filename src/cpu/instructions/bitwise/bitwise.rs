@@ -7,6 +7,7 @@ use crate::cpu::instructions::Instruction;
 use crate::cpu::register::Register;
 use crate::cpu::registers::Registers;
 use crate::memory::{MemoryController, MemoryTrait};
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
 pub use super::*;
 
 pub struct Bitwise {
@@ -60,6 +61,32 @@ impl Instruction for Bitwise {
         0xCB
     }
 
+    /**
+        The second opcode is only known once it has been read, after which the bitwise instruction's own progress follows it
+    */
+    fn save_state(&self, writer: &mut StateWriter) {
+        match &self.instruction {
+            Some(instruction) => {
+                writer.write_bool(true);
+                writer.write_u8(instruction.get_opcode());
+                instruction.save_state(writer);
+            }
+            None => writer.write_bool(false)
+        }
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.instruction = if reader.read_bool()? {
+            let mut instruction = Bitwise::decode_instruction(&reader.read_u8()?);
+            instruction.load_state(reader)?;
+            Some(instruction)
+        } else {
+            None
+        };
+
+        Ok(())
+    }
+
     fn act(&mut self, registers: &mut Registers, alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, enable_interrupts: &mut bool, is_halted: &mut bool) -> bool {
         match self.instruction {
             Some(ref mut instruction) => {
@@ -117,4 +144,31 @@ mod tests {
         assert_eq!(true, nullable_instruction_internal.borrow().was_executed);
     }
 
+    #[test]
+    fn load_state_restores_bitwise_instruction_part_way_through() {
+        let mut registers = Registers::new(0, 0, 0, 0xD000, 0xC000, 0);
+        let memory = Arc::new(Mutex::new(MemoryController::new()));
+        let mut alu = ALU::new(registers.f.clone());
+
+        memory.lock().set(0xC000, 0x36); // SWAP (HL)
+        memory.lock().set(0xD000, 0x12);
+
+        let mut instruction = Bitwise { instruction: None };
+        instruction.act(&mut registers, &mut alu, memory.clone(), &mut false, &mut false); //reads 0x12 from (HL)
+
+        let mut writer = StateWriter::new();
+        instruction.save_state(&mut writer);
+        let state = writer.into_data();
+
+        let mut loaded = Bitwise { instruction: None };
+        let mut reader = StateReader::new(&state);
+        loaded.load_state(&mut reader).unwrap();
+        assert!(reader.is_finished());
+
+        memory.lock().set(0xD000, 0x00);
+        loaded.act(&mut registers, &mut alu, memory.clone(), &mut false, &mut false);
+        loaded.act(&mut registers, &mut alu, memory.clone(), &mut false, &mut false);
+
+        assert_eq!(0x21, memory.lock().get(0xD000));
+    }
 }
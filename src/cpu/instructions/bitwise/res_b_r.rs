@@ -28,6 +28,8 @@ macro_rules! res_b_r {
                     $opcode
                 }
 
+                instruction_state!(counter: u8);
+
                 fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
                     if self.counter == 1 {
                         registers.$reg.borrow_mut().set_bit($bit, false);
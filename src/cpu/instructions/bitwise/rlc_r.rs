@@ -26,6 +26,8 @@ macro_rules! rlc_r {
                     $opcode
                 }
 
+                instruction_state!(counter: u8);
+
                 fn act(&mut self, registers: &mut Registers, alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interlcupts: &mut bool, _is_halted: &mut bool) -> bool {
                     if self.counter == 1 {
                         alu.rlc(&mut *registers.$reg.borrow_mut());
@@ -26,6 +26,8 @@ macro_rules! swap_r {
                     $opcode
                 }
 
+                instruction_state!();
+
                 fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
                     let mut flags = registers.f.borrow_mut();
 
@@ -25,6 +25,8 @@ impl Instruction for CallNn {
         0xCD
     }
 
+    instruction_state!(counter: u8, address: u16);
+
     //maybe wrong ordering, shouldn't have too much bearing.
     fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         if self.counter == 2 {
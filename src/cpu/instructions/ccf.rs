@@ -22,6 +22,8 @@ impl Instruction for Ccf {
         0x3F
     }
 
+    instruction_state!();
+
     fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         let carry_flag = registers.f.borrow().get_bit(ALU::CARRY_FLAG);
 
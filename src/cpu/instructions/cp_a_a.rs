@@ -23,6 +23,8 @@ impl Instruction for CpAA {
         0xBF
     }
 
+    instruction_state!();
+
     fn act(&mut self, registers: &mut Registers, alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         alu.sub(&mut Register8::new(registers.a.borrow().get_value()), &registers.a.borrow());
 
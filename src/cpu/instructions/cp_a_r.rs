@@ -30,6 +30,8 @@ macro_rules! cp_a_r {
                     $opcode
                 }
 
+                instruction_state!();
+
                 fn act(&mut self, registers: &mut Registers, alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
                     alu.sub(&mut Register8::new(registers.a.borrow().get_value()), &registers.$register.borrow());
 
@@ -22,6 +22,8 @@ impl Instruction for CplA {
         0x2F
     }
 
+    instruction_state!();
+
     fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         let new_value = !registers.a.borrow().get_value();
         registers.a.borrow_mut().set_value(new_value);
@@ -25,6 +25,8 @@ impl Instruction for DAA {
         0x27
     }
 
+    instruction_state!();
+
     fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         let mut offset = 0;
 
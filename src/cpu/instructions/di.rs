@@ -21,6 +21,8 @@ impl Instruction for Di {
         0xF3
     }
 
+    instruction_state!();
+
     fn act(&mut self, _registers: &mut Registers, _alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         *enable_interrupts = false;
 
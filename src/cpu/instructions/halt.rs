@@ -21,6 +21,8 @@ impl Instruction for Halt {
         0x76
     }
 
+    instruction_state!();
+
     fn act(&mut self, _registers: &mut Registers, _alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, is_halted: &mut bool) -> bool {
         *is_halted = true;
 
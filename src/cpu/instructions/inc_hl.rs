@@ -26,6 +26,8 @@ impl Instruction for IncHl {
         0x23
     }
 
+    instruction_state!(counter: u8);
+
     fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         if self.counter == 1 {
             registers.hl.increment();
@@ -27,6 +27,8 @@ impl Instruction for IncHlAddr {
         0x34
     }
 
+    instruction_state!(counter: u8, value_register: Register8);
+
     fn act(&mut self, registers: &mut Registers, alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         if self.counter == 2 {
             self.value_register.set_value(memory_controller.lock().get(registers.hl.get_value()));
@@ -26,6 +26,8 @@ macro_rules! inc_r {
                     $opcode
                 }
 
+                instruction_state!();
+
                 fn act(&mut self, registers: &mut Registers, alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
                     alu.add_no_carry(&mut *registers.$register.clone().borrow_mut(), &Register8::one());
 
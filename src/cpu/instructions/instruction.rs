@@ -2,6 +2,7 @@ use crate::cpu::alu::ALU;
 use crate::cpu::instructions::*;
 use crate::cpu::registers::Registers;
 use crate::memory::MemoryController;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
 use crate::{adc_a_r_decode_instruction, add_a_r_decode_instruction, add_hl_rr_decode_instruction, and_a_r_decode_instruction, call_cc_nn_decode_instruction, cp_a_r_decode_instruction, dec_r_decode_instruction, dec_rr_decode_instruction, inc_r_decode_instruction, inc_rr_decode_instruction, jp_cc_nn_decode_instruction, jr_cc_n_decode_instruction, ld_a_rr_decode_instruction, ld_hl_r_decode_instruction, ld_r_hl_decode_instruction, ld_r_n_decode_instruction, ld_r_r_decode_instruction, ld_rr_a_decode_instruction, ld_rr_nn_decode_instruction, or_a_r_decode_instruction, pop_rr_decode_instruction, push_rr_decode_instruction, ret_with_condition_decode_instruction, rst_nn_decode_instruction, sbc_a_r_decode_instruction, sub_a_r_decode_instruction, xor_a_r_decode_instruction};
 use parking_lot::Mutex;
 use std::sync::Arc;
@@ -46,6 +47,30 @@ macro_rules! return_if_is_instruction {
 }
 
 
+/**
+Implements save_state and load_state for the fields an instruction keeps between M-cycles,
+written in the same form as the struct, e.g. instruction_state!(counter: u8, value_register: Register8)
+*/
+macro_rules! instruction_state {
+    ($($field:ident: $type:ident),*) => {
+        fn save_state(&self, _writer: &mut $crate::save_state::StateWriter) {
+            $( instruction_state!(@write _writer, self.$field, $type); )*
+        }
+
+        fn load_state(&mut self, _reader: &mut $crate::save_state::StateReader) -> Result<(), $crate::save_state::SaveStateError> {
+            $( instruction_state!(@read _reader, self.$field, $type); )*
+            Ok(())
+        }
+    };
+
+    (@write $writer:ident, $value:expr, Register8) => { $writer.write_u8($crate::cpu::register::Register::get_value(&$value)) };
+    (@write $writer:ident, $value:expr, $type:ident) => { ::paste::paste! { $writer.[<write_ $type>]($value) } };
+
+    (@read $reader:ident, $value:expr, Register8) => { $crate::cpu::register::Register::set_value(&mut $value, $reader.read_u8()?) };
+    (@read $reader:ident, $value:expr, $type:ident) => { ::paste::paste! { $value = $reader.[<read_ $type>]()? } };
+}
+
+
 pub trait Instruction {
 
     fn from_opcode(opcode: &u8) -> Option<Box<dyn Instruction>> where Self: Sized;
@@ -55,6 +80,13 @@ pub trait Instruction {
 
     fn act(&mut self, registers: &mut Registers, alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, enable_interrupts: &mut bool, is_halted: &mut bool) -> bool; //returns whether the CPU should return the next instruction
 
+    /**
+        Writes whatever the instruction has worked out so far, so it can carry on from the same M-cycle after loading.
+        The opcode is saved by the CPU, which decodes the instruction again before calling load_state.
+    */
+    fn save_state(&self, writer: &mut StateWriter);
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError>;
+
 }
 
 
@@ -92,6 +124,7 @@ pub fn decode_instruction(opcode: &u8) -> Box<dyn Instruction> {
     return_if_is_instruction!(RlcA, opcode);    //0x07
     return_if_is_instruction!(LdNnSp, opcode);  //0x08
     return_if_is_instruction!(RrcA, opcode);    //0x0F
+    return_if_is_instruction!(Stop, opcode);    //0x10
     return_if_is_instruction!(RlA, opcode);     //0x17
     return_if_is_instruction!(JrN, opcode);     //0x18
     return_if_is_instruction!(RrA, opcode);     //0x1F
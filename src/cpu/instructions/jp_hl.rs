@@ -22,6 +22,8 @@ impl Instruction for JpHl {
         0xE9
     }
 
+    instruction_state!();
+
     fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         registers.pc.set_value(registers.hl.get_value());
 
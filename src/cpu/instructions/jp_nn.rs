@@ -25,6 +25,8 @@ impl Instruction for JpNn {
         0xC3
     }
 
+    instruction_state!(counter: u8, address: u16);
+
     fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         if self.counter == 2 {
             self.address = memory_controller.lock().get(registers.pc.get_value()) as u16;
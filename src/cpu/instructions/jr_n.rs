@@ -25,6 +25,8 @@ impl Instruction for JrN {
         0x18
     }
 
+    instruction_state!(counter: u8, address: u16);
+
     fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         if self.counter == 1 {
             let relative_address = memory_controller.lock().get(registers.pc.get_value());
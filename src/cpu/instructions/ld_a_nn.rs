@@ -27,6 +27,8 @@ impl Instruction for LdANN {
         0xFA
     }
 
+    instruction_state!(counter: u8, address: u16);
+
     fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         if self.counter == 3 {
             self.address = memory_controller.lock().get(registers.pc.get_value()) as u16;
@@ -55,6 +57,7 @@ mod tests {
     use super::*;
     use crate::cpu::register::Register;
     use crate::memory::MemoryTrait;
+    use crate::save_state::{StateReader, StateWriter};
 
     reusable_testing_macro!(0xFA, LdANN);
 
@@ -128,4 +131,19 @@ mod tests {
         assert_eq!(true, result);
         assert_eq!(0xC002, registers.pc.get_value());
     }
+
+    #[test]
+    fn load_state_restores_address_part_way_through() {
+        let instruction = LdANN { counter: 2, address: 0x0012 };
+
+        let mut writer = StateWriter::new();
+        instruction.save_state(&mut writer);
+        let state = writer.into_data();
+
+        let mut loaded = LdANN { counter: 3, address: 0 };
+        loaded.load_state(&mut StateReader::new(&state)).unwrap();
+
+        assert_eq!(2, loaded.counter);
+        assert_eq!(0x0012, loaded.address);
+    }
 }
@@ -30,6 +30,8 @@ macro_rules! ld_hl_r {
                     $opcode
                 }
 
+                instruction_state!(counter: u8, address: u16);
+
                 fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
                     if self.counter == 1 {
                         self.address = registers.hl.get_value();
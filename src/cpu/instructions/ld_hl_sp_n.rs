@@ -26,6 +26,8 @@ impl Instruction for LdHlSpN {
         0xF8
     }
 
+    instruction_state!(counter: u8, value: u8);
+
     fn act(&mut self, registers: &mut Registers, alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         if self.counter == 2 {
             self.value = memory_controller.lock().get(registers.pc.get_value()) as u8;
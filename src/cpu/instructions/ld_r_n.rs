@@ -28,6 +28,8 @@ macro_rules! ld_r_n {
                     $opcode
                 }
 
+                instruction_state!(counter: u8);
+
                 fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
                     if self.counter == 1 {
                         registers.$register.borrow_mut().set_value(memory_controller.lock().get(registers.pc.get_value()));
@@ -26,6 +26,8 @@ macro_rules! ld_r_r {
                     $opcode
                 }
 
+                instruction_state!();
+
                 fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
                     let value = registers.$reg_2.borrow().get_value();
                     registers.$reg_1.borrow_mut().set_value(value);
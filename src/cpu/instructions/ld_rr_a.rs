@@ -28,6 +28,8 @@ macro_rules! ld_rr_a {
                     $opcode
                 }
 
+                instruction_state!(counter: u8);
+
                 fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
                     if self.counter == 1 {
                         memory_controller.lock().set(registers.$register.get_value(), registers.a.borrow().get_value());
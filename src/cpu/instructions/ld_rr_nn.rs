@@ -29,6 +29,8 @@ macro_rules! ld_rr_nn {
                     $opcode
                 }
 
+                instruction_state!(counter: u8, value: u16);
+
                 fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
                     if self.counter == 2 {
                         self.value = memory_controller.lock().get(registers.pc.get_value()) as u16;
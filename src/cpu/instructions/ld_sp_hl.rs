@@ -24,6 +24,8 @@ impl Instruction for LdSpHl {
         0xF9
     }
 
+    instruction_state!(counter: u8);
+
     fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         if self.counter == 1 {
             let hl_value = registers.hl.get_value();
@@ -25,6 +25,8 @@ impl Instruction for LdSpNn {
         0x31
     }
 
+    instruction_state!(counter: u8, value: u16);
+
     fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         if self.counter == 2 {
             self.value = memory_controller.lock().get(registers.pc.get_value()) as u16;
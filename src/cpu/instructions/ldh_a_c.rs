@@ -26,6 +26,8 @@ impl Instruction for LdhAC {
         0xF2
     }
 
+    instruction_state!(counter: u8);
+
     fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         if self.counter == 1 {
             registers.a.borrow_mut().set_value(memory_controller.lock().get(0xFF00 | (registers.c.borrow().get_value() as u16)));
@@ -26,6 +26,8 @@ impl Instruction for LdhCA {
         0xE2
     }
 
+    instruction_state!(counter: u8);
+
     fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         if self.counter == 1 {
             memory_controller.lock().set(0xFF00 | (registers.c.borrow().get_value() as u16), registers.a.borrow().get_value());
@@ -27,6 +27,8 @@ impl Instruction for LdhNA {
         0xE0
     }
 
+    instruction_state!(counter: u8, address: u16);
+
     fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         if self.counter == 2 {
             self.address = 0xFF00 + memory_controller.lock().get(registers.pc.get_value()) as u16;
@@ -24,6 +24,8 @@ impl Instruction for LdiAHl {
         0x2A
     }
 
+    instruction_state!(counter: u8);
+
     fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         if self.counter == 1 {
             registers.a.borrow_mut().set_value(memory_controller.lock().get(registers.hl.get_value()));
@@ -24,6 +24,8 @@ impl Instruction for LdiHlA {
         0x22
     }
 
+    instruction_state!(counter: u8);
+
     fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         if self.counter == 1 {
             memory_controller.lock().set(registers.hl.get_value(), registers.a.borrow().get_value());
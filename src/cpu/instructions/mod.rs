@@ -1,10 +1,10 @@
+#[macro_use]
+mod instruction;
 #[cfg(test)]
 mod nullable_instruction;
 #[cfg(test)]
 pub use nullable_instruction::*;
 
-#[macro_use]
-mod instruction;
 mod bad_instruction;
 
 mod nop; //0x00
@@ -40,6 +40,7 @@ mod rst_nn;
 mod rlca; //0x07
 mod ld_nn_sp; //0x08
 mod rrca; //0x0F
+mod stop; //0x10
 mod rla; //0x17
 mod jr_n; //0x18
 mod rra; //0x1F
@@ -139,6 +140,7 @@ use rst_nn::*;
 use rlca::RlcA;         //0x07
 use ld_nn_sp::LdNnSp;   //0x08
 use rrca::RrcA;         //0x0F
+use stop::Stop;         //0x10
 use rla::RlA;           //0x17
 use jr_n::JrN;          //0x18
 use rra::RrA;           //0x1F
@@ -21,6 +21,8 @@ impl Instruction for Nop {
         0x00
     }
 
+    instruction_state!();
+
     fn act(&mut self, _registers: &mut Registers, _alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         true
     }
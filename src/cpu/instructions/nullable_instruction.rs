@@ -49,6 +49,8 @@ impl Instruction for NullableInstruction {
         self.opcode
     }
 
+    instruction_state!();
+
     fn act(&mut self, _registers: &mut Registers, _alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         self.internal.borrow_mut().was_executed = true;
 
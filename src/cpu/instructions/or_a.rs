@@ -21,6 +21,8 @@ impl Instruction for OrA {
         0xB7
     }
 
+    instruction_state!();
+
     fn act(&mut self, registers: &mut Registers, alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         alu.or_internal(registers.a.clone(), registers.a.clone());
 
@@ -29,6 +29,8 @@ macro_rules! push_rr {
                     $opcode
                 }
 
+                instruction_state!(counter: u8);
+
                 //maybe wrong ordering, shouldn't have too much bearing.
                 fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
                     if self.counter == 3 {
@@ -25,6 +25,8 @@ impl Instruction for Ret {
         0xC9
     }
 
+    instruction_state!(counter: u8, address_low_byte: u8);
+
     fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         if self.counter == 1 {
             self.address_low_byte = memory_controller.lock().get(registers.sp.get_value());
@@ -30,6 +30,8 @@ macro_rules! ret_with_condition {
                     $opcode
                 }
 
+                instruction_state!(counter: u8, address_low_byte: u8);
+
                 fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
                     if registers.f.borrow().get_bit(ALU::$flag) == $wants_set {
                         if self.counter == 1 {
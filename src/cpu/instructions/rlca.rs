@@ -22,6 +22,8 @@ impl Instruction for RlcA {
         0x07
     }
 
+    instruction_state!();
+
     fn act(&mut self, registers: &mut Registers, alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         alu.rlc(&mut registers.a.borrow_mut());
 
@@ -28,6 +28,8 @@ macro_rules! sbc_a_r {
                     $opcode
                 }
 
+                instruction_state!();
+
                 fn act(&mut self, registers: &mut Registers, alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
                     alu.sbc(&mut *registers.a.clone().borrow_mut(), &registers.$register.clone().borrow());
 
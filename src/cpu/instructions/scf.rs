@@ -22,6 +22,8 @@ impl Instruction for Scf {
         0x37
     }
 
+    instruction_state!();
+
     fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         registers.f.borrow_mut().set_bit(ALU::SUB_FLAG, false);
         registers.f.borrow_mut().set_bit(ALU::HALF_CARRY_FLAG, false);
@@ -0,0 +1,98 @@
+use crate::cpu::alu::ALU;
+use crate::cpu::instructions::Instruction;
+use crate::cpu::register::Register;
+use crate::cpu::registers::Registers;
+use crate::memory::MemoryController;
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+/**
+    STOP is followed by a byte that is skipped. The instruction doesn't finish until joypad input wakes the
+    memory controller back up, so interrupts are only taken afterwards.
+*/
+pub struct Stop {
+    counter: u8
+}
+
+impl Instruction for Stop {
+
+    #[inline]
+    fn from_opcode(opcode: &u8) -> Option<Box<dyn Instruction>> {
+        if *opcode == 0x10 {
+            return Some(Box::new(Stop { counter: 1 }))
+        }
+        None
+    }
+
+    fn get_opcode(&self) -> u8 {
+        0x10
+    }
+
+    instruction_state!(counter: u8);
+
+    fn act(&mut self, registers: &mut Registers, _alu: &mut ALU, memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
+        if self.counter == 1 {
+            registers.pc.increment();
+            memory_controller.lock().stop();
+
+            self.counter -= 1;
+            return false;
+        }
+
+        !memory_controller.lock().is_stopped()
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryTrait;
+
+    reusable_testing_macro!(0x10, Stop);
+
+    #[test]
+    fn skips_second_byte_and_resets_div_on_tick_1() {
+        let mut registers = Registers::new(0, 0, 0, 0, 0xC001, 0);
+        let mut alu = ALU::new(registers.f.clone());
+        let memory = Arc::new(Mutex::new(MemoryController::new()));
+
+        let mut instruction = Stop { counter: 1 };
+
+        let result = instruction.act(&mut registers, &mut alu, memory.clone(), &mut false, &mut false);
+
+        assert_eq!(false, result);
+        assert_eq!(0xC002, registers.pc.get_value());
+        assert_eq!(0x00, memory.lock().get(0xFF04));
+        assert!(memory.lock().is_stopped());
+    }
+
+    #[test]
+    fn waits_while_stopped() {
+        let mut registers = Registers::new(0, 0, 0, 0, 0xC002, 0);
+        let mut alu = ALU::new(registers.f.clone());
+        let memory = Arc::new(Mutex::new(MemoryController::new()));
+        memory.lock().stop();
+
+        let mut instruction = Stop { counter: 0 };
+
+        let result = instruction.act(&mut registers, &mut alu, memory.clone(), &mut false, &mut false);
+
+        assert_eq!(false, result);
+        assert_eq!(0xC002, registers.pc.get_value());
+    }
+
+    #[test]
+    fn finishes_once_woken() {
+        let mut registers = Registers::new(0, 0, 0, 0, 0xC002, 0);
+        let mut alu = ALU::new(registers.f.clone());
+        let memory = Arc::new(Mutex::new(MemoryController::new()));
+
+        let mut instruction = Stop { counter: 0 };
+
+        let result = instruction.act(&mut registers, &mut alu, memory.clone(), &mut false, &mut false);
+
+        assert_eq!(true, result);
+    }
+}
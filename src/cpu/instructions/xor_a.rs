@@ -21,6 +21,8 @@ impl Instruction for XorA {
         0xAF
     }
 
+    instruction_state!();
+
     fn act(&mut self, registers: &mut Registers, alu: &mut ALU, _memory_controller: Arc<Mutex<MemoryController>>, _enable_interrupts: &mut bool, _is_halted: &mut bool) -> bool {
         alu.xor_internal(registers.a.clone(), registers.a.clone());
 
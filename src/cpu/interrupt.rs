@@ -18,6 +18,12 @@ impl Interrupt {
         }
     }
     
+    pub fn from_bit_mask(mask: u8) -> Option<Interrupt> {
+        [Interrupt::VBlank, Interrupt::LCD, Interrupt::Timer, Interrupt::Serial, Interrupt::Joypad]
+            .into_iter()
+            .find(|interrupt| interrupt.get_bit_mask() == mask)
+    }
+
     pub fn get_address(&self) -> u16 {
         match self {
             Interrupt::VBlank => 0x0040,
@@ -8,6 +8,7 @@ use parking_lot::Mutex;
 use crate::cpu::CPU;
 use crate::cpu::interrupt::Interrupt;
 use crate::memory::MemoryController;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
 
 
 
@@ -31,6 +32,23 @@ impl CPU for NullableCPU {
         self.num_times_clocked.replace(0);
         self.interrupt_requested.replace(None);
     }
+
+    fn get_pc(&self) -> u16 {
+        0
+    }
+
+    fn get_bc_de_hl(&self) -> (u16, u16, u16) {
+        (0, 0, 0)
+    }
+
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u32(*self.num_times_clocked.borrow());
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.num_times_clocked.replace(reader.read_u32()?);
+        Ok(())
+    }
 }
 
 impl NullableCPU {
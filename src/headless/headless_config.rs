@@ -0,0 +1,214 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::headless::headless_error::HeadlessError;
+use crate::headless::stop_condition::StopCondition;
+
+/**
+    --headless <rom> runs without a window until one of:
+        --frames <count>            that many frames have been sent
+        --timeout <seconds>         that much real time has passed
+        --until-pc <address>        an instruction at the address starts
+        --until-memory <address>=<value>
+        --until-serial <text>       the text has been sent over the link port
+    --movie <path.gbm> plays a recorded movie from its start state, stopping when it ends if --frames isn't given.
+    --link-rom <rom> connects a second Game Boy running that ROM by link cable, stepped in lock-step with the first.
+    The stop conditions, movie and dumps all apply to the first, and serial output isn't recorded.
+    --dump-screen <path.png> and --dump-memory <path> write the final state, --print-serial echoes serial output.
+    Numbers can be decimal or 0x prefixed hex.
+*/
+pub struct HeadlessConfig {
+    pub rom_path: String,
+    pub frames: Option<u64>,
+    pub timeout: Option<Duration>,
+    pub stop_conditions: Vec<StopCondition>,
+    pub movie_path: Option<PathBuf>,
+    pub link_rom_path: Option<String>,
+
+    pub screen_dump_path: Option<PathBuf>,
+    pub memory_dump_path: Option<PathBuf>,
+    pub print_serial: bool,
+}
+
+impl HeadlessConfig {
+
+    pub const USAGE: &'static str = "Usage: --headless <rom> [--frames <count>] [--timeout <seconds>] [--until-pc <address>] \
+        [--until-memory <address>=<value>] [--until-serial <text>] [--movie <path.gbm>] [--link-rom <rom>] [--dump-screen <path.png>] [--dump-memory <path>] [--print-serial]";
+
+    pub fn from_args(args: &[String]) -> Result<Self, HeadlessError> {
+        let rom_path = Self::get_argument_value(args, "--headless")
+            .ok_or_else(|| Self::usage_error("--headless needs a ROM path"))?;
+
+        let frames = match Self::get_argument_value(args, "--frames") {
+            Some(value) => Some(value.parse::<u64>().map_err(|_| Self::usage_error(&format!("Invalid frame count {}", value)))?),
+            None => None
+        };
+
+        let timeout = match Self::get_argument_value(args, "--timeout") {
+            Some(value) => match value.parse::<f64>() {
+                Ok(seconds) if seconds >= 0.0 => Some(Duration::from_secs_f64(seconds)),
+                _ => return Err(Self::usage_error(&format!("Invalid timeout {}", value)))
+            }
+            None => None
+        };
+
+        let mut stop_conditions = vec![];
+
+        match Self::get_argument_value(args, "--until-pc") {
+            Some(value) => stop_conditions.push(StopCondition::ProgramCounter { address: Self::parse_address(&value)? }),
+            None => {}
+        }
+
+        match Self::get_argument_value(args, "--until-memory") {
+            Some(value) => {
+                let (address, expected) = value.split_once('=')
+                    .ok_or_else(|| Self::usage_error(&format!("--until-memory needs <address>=<value>, got {}", value)))?;
+
+                let expected = Self::parse_number(expected)
+                    .filter(|&expected| expected <= 0xFF)
+                    .ok_or_else(|| Self::usage_error(&format!("Invalid byte {}", expected)))?;
+
+                stop_conditions.push(StopCondition::MemoryValue { address: Self::parse_address(address)?, value: expected as u8 });
+            }
+            None => {}
+        }
+
+        match Self::get_argument_value(args, "--until-serial") {
+            Some(text) => stop_conditions.push(StopCondition::SerialOutput { text }),
+            None => {}
+        }
+
+        let movie_path = Self::get_argument_value(args, "--movie").map(PathBuf::from);
+
+        if frames.is_none() && timeout.is_none() && stop_conditions.is_empty() && movie_path.is_none() {
+            return Err(Self::usage_error("Nothing would stop the emulator, give --frames, --timeout, --movie or an --until condition"));
+        }
+
+        Ok(Self {
+            rom_path,
+            frames,
+            timeout,
+            stop_conditions,
+            movie_path,
+            link_rom_path: Self::get_argument_value(args, "--link-rom"),
+
+            screen_dump_path: Self::get_argument_value(args, "--dump-screen").map(PathBuf::from),
+            memory_dump_path: Self::get_argument_value(args, "--dump-memory").map(PathBuf::from),
+            print_serial: args.iter().any(|arg| arg == "--print-serial"),
+        })
+    }
+
+    fn get_argument_value(args: &[String], name: &str) -> Option<String> {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|index| args.get(index + 1))
+            .cloned()
+    }
+
+    fn parse_number(text: &str) -> Option<u32> {
+        let text = text.trim();
+
+        match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => text.parse::<u32>().ok()
+        }
+    }
+
+    fn parse_address(text: &str) -> Result<u16, HeadlessError> {
+        Self::parse_number(text)
+            .filter(|&address| address <= 0xFFFF)
+            .map(|address| address as u16)
+            .ok_or_else(|| Self::usage_error(&format!("Invalid address {}", text)))
+    }
+
+    fn usage_error(message: &str) -> HeadlessError {
+        HeadlessError::UsageError { message: message.to_string() }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_args(args: &[&str]) -> Vec<String> {
+        let mut all_args = vec!["gameboy_emulator".to_string()];
+        all_args.extend(args.iter().map(|arg| arg.to_string()));
+
+        all_args
+    }
+
+    #[test]
+    fn parses_limits_and_dumps() {
+        let config = HeadlessConfig::from_args(&get_args(&[
+            "--headless", "test.gb", "--frames", "120", "--timeout", "2.5",
+            "--dump-screen", "screen.png", "--dump-memory", "memory.bin", "--print-serial"
+        ])).unwrap();
+
+        assert_eq!("test.gb", config.rom_path);
+        assert_eq!(Some(120), config.frames);
+        assert_eq!(Some(Duration::from_millis(2500)), config.timeout);
+        assert_eq!(Some(PathBuf::from("screen.png")), config.screen_dump_path);
+        assert_eq!(Some(PathBuf::from("memory.bin")), config.memory_dump_path);
+        assert!(config.print_serial);
+        assert!(config.stop_conditions.is_empty());
+    }
+
+    #[test]
+    fn parses_link_rom() {
+        let config = HeadlessConfig::from_args(&get_args(&["--headless", "test.gb", "--link-rom", "other.gb", "--frames", "1"])).unwrap();
+
+        assert_eq!(Some("other.gb".to_string()), config.link_rom_path);
+    }
+
+    #[test]
+    fn parses_stop_conditions() {
+        let config = HeadlessConfig::from_args(&get_args(&[
+            "--headless", "test.gb", "--until-pc", "0x0150", "--until-memory", "0xA000=128", "--until-serial", "Passed"
+        ])).unwrap();
+
+        assert_eq!(vec![
+            StopCondition::ProgramCounter { address: 0x0150 },
+            StopCondition::MemoryValue { address: 0xA000, value: 0x80 },
+            StopCondition::SerialOutput { text: "Passed".to_string() },
+        ], config.stop_conditions);
+    }
+
+    #[test]
+    fn needs_something_to_stop() {
+        let result = HeadlessConfig::from_args(&get_args(&["--headless", "test.gb"]));
+
+        assert!(matches!(result, Err(HeadlessError::UsageError { .. })));
+    }
+
+    #[test]
+    fn movie_is_enough_to_stop() {
+        let config = HeadlessConfig::from_args(&get_args(&["--headless", "test.gb", "--movie", "test.gbm"])).unwrap();
+
+        assert_eq!(Some(PathBuf::from("test.gbm")), config.movie_path);
+        assert_eq!(None, config.frames);
+    }
+
+    #[test]
+    fn needs_rom_path() {
+        let result = HeadlessConfig::from_args(&get_args(&["--headless"]));
+
+        assert!(matches!(result, Err(HeadlessError::UsageError { .. })));
+    }
+
+    #[test]
+    fn rejects_out_of_range_values() {
+        for args in [
+            ["--until-pc", "0x10000"],
+            ["--until-memory", "0xC000=0x100"],
+            ["--until-memory", "0xC000"],
+            ["--frames", "-1"],
+        ] {
+            let mut all_args = vec!["--headless", "test.gb"];
+            all_args.extend_from_slice(&args);
+
+            let result = HeadlessConfig::from_args(&get_args(&all_args));
+
+            assert!(matches!(result, Err(HeadlessError::UsageError { .. })));
+        }
+    }
+}
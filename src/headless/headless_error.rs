@@ -0,0 +1,37 @@
+use thiserror::Error;
+use crate::memory::CartridgeError;
+use crate::movie::MovieError;
+use crate::renderer::RendererError;
+use crate::system::SystemError;
+
+#[derive(Error, Debug)]
+pub enum HeadlessError {
+    #[error("{message}")]
+    UsageError { message: String },
+    #[error("Could not load ROM: {error}")]
+    RomError { error: CartridgeError },
+    #[error("Emulation error: {error}")]
+    SystemError { error: SystemError },
+    #[error("Could not dump memory: {error}")]
+    MemoryDumpError { error: std::io::Error },
+    #[error("Could not dump screen: {error}")]
+    ScreenDumpError { error: RendererError },
+    #[error("Could not read test ROM directory: {error}")]
+    TestRomDirectoryError { error: std::io::Error },
+    #[error("Could not play movie: {error}")]
+    MovieError { error: MovieError },
+}
+
+impl HeadlessError {
+    pub fn get_exit_code(&self) -> i32 {
+        match self {
+            HeadlessError::UsageError { .. } => exitcode::USAGE,
+            HeadlessError::RomError { .. } => exitcode::NOINPUT,
+            HeadlessError::SystemError { .. } => exitcode::SOFTWARE,
+            HeadlessError::MemoryDumpError { .. } => exitcode::IOERR,
+            HeadlessError::ScreenDumpError { .. } => exitcode::IOERR,
+            HeadlessError::TestRomDirectoryError { .. } => exitcode::NOINPUT,
+            HeadlessError::MovieError { .. } => exitcode::DATAERR,
+        }
+    }
+}
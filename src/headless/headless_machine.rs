@@ -0,0 +1,131 @@
+use std::sync::Arc;
+use dec_gl::shader::ShaderManager;
+use parking_lot::Mutex;
+use crate::app::PerformanceTimer;
+use crate::cpu::GameBoyCPU;
+use crate::headless::headless_error::HeadlessError;
+use crate::memory::io_map::{JoypadIO, SerialRecorder};
+use crate::memory::MemoryController;
+use crate::renderer::{ScreenBuffer, SoftwareRenderer};
+use crate::system::{LinkedMainBoards, MainBoard};
+
+enum HeadlessBoards {
+    Single(MainBoard),
+    Linked(LinkedMainBoards), //the first board is the one being watched
+}
+
+/**
+    A main board drawing with the software renderer and recording its serial output, with nothing attached
+    that needs a window. It can instead be linked to a second board, in which case nothing is recorded from the link port.
+*/
+pub struct HeadlessMachine {
+    main_boards: HeadlessBoards,
+    memory: Arc<Mutex<MemoryController>>,
+    serial_output: Arc<Mutex<Vec<u8>>>,
+    screen_buffer: Arc<Mutex<ScreenBuffer>>,
+
+    shader_manager: ShaderManager,
+    performance_timer: PerformanceTimer,
+}
+
+impl HeadlessMachine {
+
+    pub fn new(rom_path: &String, echo_serial: bool) -> Result<Self, HeadlessError> {
+        let (main_board, memory, screen_buffer) = Self::create_main_board(rom_path)?;
+
+        let serial_recorder = SerialRecorder::new(echo_serial);
+        let serial_output = serial_recorder.get_output();
+        memory.lock().get_io_map().lock().set_serial_peer(Some(Box::new(serial_recorder)));
+
+        Ok(Self {
+            main_boards: HeadlessBoards::Single(main_board),
+            memory,
+            serial_output,
+            screen_buffer,
+
+            shader_manager: ShaderManager::new(),
+            performance_timer: PerformanceTimer::new_fake(),
+        })
+    }
+
+    /**
+        Links the ROM to a second Game Boy running link_rom_path, the memory, screen and joypad are the first one's
+    */
+    pub fn new_linked(rom_path: &String, link_rom_path: &String) -> Result<Self, HeadlessError> {
+        let (main_board, memory, screen_buffer) = Self::create_main_board(rom_path)?;
+        let (link_main_board, _, _) = Self::create_main_board(link_rom_path)?;
+
+        Ok(Self {
+            main_boards: HeadlessBoards::Linked(LinkedMainBoards::new(main_board, link_main_board)),
+            memory,
+            serial_output: Arc::new(Mutex::new(vec![])),
+            screen_buffer,
+
+            shader_manager: ShaderManager::new(),
+            performance_timer: PerformanceTimer::new_fake(),
+        })
+    }
+
+    fn create_main_board(rom_path: &String) -> Result<(MainBoard, Arc<Mutex<MemoryController>>, Arc<Mutex<ScreenBuffer>>), HeadlessError> {
+        let memory = Arc::new(Mutex::new(MemoryController::new()));
+
+        let load_result = memory.lock().load_rom(rom_path, false); //battery saves would make runs depend on files left on disk
+        load_result.map_err(|error| HeadlessError::RomError { error })?;
+
+        let vram = memory.lock().get_vram_arc();
+        let oam = memory.lock().get_oam_arc();
+        let video_io = memory.lock().get_io_map().lock().get_video_io();
+
+        let software_renderer = SoftwareRenderer::new(vram, oam, video_io);
+        let screen_buffer = software_renderer.get_screen_buffer();
+
+        let main_board = MainBoard::new(Box::new(GameBoyCPU::new_with_nop()), memory.clone(), Box::new(software_renderer));
+
+        Ok((main_board, memory, screen_buffer))
+    }
+
+    /**
+        Performs up to the given number of VDU ticks, returning whether a frame was finished. Linked boards take
+        at most a scan line per step.
+    */
+    pub fn step(&mut self, ticks: u32) -> Result<bool, HeadlessError> {
+        let result = match &mut self.main_boards {
+            HeadlessBoards::Single(main_board) => main_board.perform_ticks(ticks, &mut self.shader_manager, &mut self.performance_timer),
+            HeadlessBoards::Linked(linked_main_boards) => linked_main_boards.perform_ticks(ticks, &mut self.shader_manager, &mut self.performance_timer),
+        };
+
+        let (_, frame_sent) = result.map_err(|error| HeadlessError::SystemError { error })?;
+
+        Ok(frame_sent)
+    }
+
+    pub fn get_main_board(&self) -> &MainBoard {
+        match &self.main_boards {
+            HeadlessBoards::Single(main_board) => main_board,
+            HeadlessBoards::Linked(linked_main_boards) => linked_main_boards.get_main_board(0),
+        }
+    }
+
+    pub fn get_main_board_mut(&mut self) -> &mut MainBoard {
+        match &mut self.main_boards {
+            HeadlessBoards::Single(main_board) => main_board,
+            HeadlessBoards::Linked(linked_main_boards) => linked_main_boards.get_main_board_mut(0),
+        }
+    }
+
+    pub fn get_joypad(&self) -> Arc<Mutex<JoypadIO>> {
+        self.memory.lock().get_io_map().lock().get_joypad_io()
+    }
+
+    pub fn get_memory(&self) -> Arc<Mutex<MemoryController>> {
+        self.memory.clone()
+    }
+
+    pub fn get_serial_output(&self) -> Arc<Mutex<Vec<u8>>> {
+        self.serial_output.clone()
+    }
+
+    pub fn get_screen_buffer(&self) -> Arc<Mutex<ScreenBuffer>> {
+        self.screen_buffer.clone()
+    }
+}
@@ -0,0 +1,425 @@
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+use crate::headless::headless_config::HeadlessConfig;
+use crate::headless::headless_error::HeadlessError;
+use crate::headless::headless_machine::HeadlessMachine;
+use crate::memory::MemoryTrait;
+use crate::movie::{Movie, MoviePlayer};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HeadlessOutcome {
+    ConditionMet { frames: u64 },
+    FramesCompleted { frames: u64 },
+    ConditionNotMet { frames: u64 },
+    TimedOut { frames: u64 },
+}
+
+impl HeadlessOutcome {
+    pub fn get_exit_code(&self) -> i32 {
+        match self {
+            HeadlessOutcome::ConditionMet { .. } => exitcode::OK,
+            HeadlessOutcome::FramesCompleted { .. } => exitcode::OK,
+            HeadlessOutcome::ConditionNotMet { .. } => exitcode::SOFTWARE,
+            HeadlessOutcome::TimedOut { .. } => exitcode::TEMPFAIL,
+        }
+    }
+}
+
+/**
+    Runs a ROM without a window or OpenGL, drawing with the software renderer. Battery saves are neither read
+    nor written, so every run starts from the same state.
+*/
+pub struct HeadlessRunner {
+    config: HeadlessConfig,
+}
+
+impl HeadlessRunner {
+
+    pub fn new(config: HeadlessConfig) -> Self {
+        Self {
+            config,
+        }
+    }
+
+    /**
+        Returns the process exit code
+    */
+    pub fn run_from_args(args: &[String]) -> i32 {
+        let result = HeadlessConfig::from_args(args)
+            .and_then(|config| HeadlessRunner::new(config).run());
+
+        match result {
+            Ok(outcome) => {
+                println!("{:?}", outcome);
+                outcome.get_exit_code()
+            }
+            Err(error) => {
+                eprintln!("{}", error);
+                if let HeadlessError::UsageError { .. } = error {
+                    eprintln!("{}", HeadlessConfig::USAGE);
+                }
+
+                error.get_exit_code()
+            }
+        }
+    }
+
+    pub fn run(&mut self) -> Result<HeadlessOutcome, HeadlessError> {
+        let mut machine = match &self.config.link_rom_path {
+            Some(link_rom_path) => HeadlessMachine::new_linked(&self.config.rom_path, link_rom_path)?,
+            None => HeadlessMachine::new(&self.config.rom_path, self.config.print_serial)?
+        };
+        let mut movie_player = self.start_movie(&mut machine)?;
+
+        let outcome = self.run_machine(&mut machine, movie_player.as_mut())?;
+
+        self.dump(&machine)?;
+
+        Ok(outcome)
+    }
+
+    fn start_movie(&self, machine: &mut HeadlessMachine) -> Result<Option<MoviePlayer>, HeadlessError> {
+        let movie_path = match &self.config.movie_path {
+            Some(movie_path) => movie_path,
+            None => return Ok(None)
+        };
+
+        let movie_error = |error| HeadlessError::MovieError { error };
+
+        let movie = Movie::load(movie_path).map_err(movie_error)?;
+        let rom_hash = Movie::hash_rom_file(Path::new(&self.config.rom_path)).map_err(movie_error)?;
+
+        let mut movie_player = MoviePlayer::new(movie);
+        movie_player.start(machine.get_main_board_mut(), rom_hash).map_err(movie_error)?;
+
+        movie_player.apply_next_input(&mut machine.get_joypad().lock());
+
+        Ok(Some(movie_player))
+    }
+
+    /**
+        A movie's input for each frame is set as the previous frame is sent, and it stops the run when it ends
+        unless a frame count was given
+    */
+    fn run_machine(&self, machine: &mut HeadlessMachine, mut movie_player: Option<&mut MoviePlayer>) -> Result<HeadlessOutcome, HeadlessError> {
+        let ticks_per_step = if self.config.stop_conditions.iter().any(|condition| condition.needs_every_cycle()) {
+            2 //one M-cycle
+        } else {
+            u32::MAX
+        };
+
+        let started = Instant::now();
+        let mut frames = 0;
+
+        loop {
+            let frame_sent = machine.step(ticks_per_step)?;
+
+            if frame_sent {
+                frames += 1;
+            }
+
+            if self.is_condition_met(machine) {
+                return Ok(HeadlessOutcome::ConditionMet { frames });
+            }
+
+            if !frame_sent {
+                continue;
+            }
+
+            let movie_finished = match &mut movie_player {
+                Some(movie_player) if movie_player.is_finished() => true,
+                Some(movie_player) => {
+                    movie_player.apply_next_input(&mut machine.get_joypad().lock());
+                    false
+                }
+                None => false
+            };
+
+            if self.config.frames == Some(frames) || (self.config.frames.is_none() && movie_finished) {
+                return Ok(if self.config.stop_conditions.is_empty() {
+                    HeadlessOutcome::FramesCompleted { frames }
+                } else {
+                    HeadlessOutcome::ConditionNotMet { frames }
+                });
+            }
+
+            match self.config.timeout {
+                Some(timeout) if started.elapsed() >= timeout => return Ok(HeadlessOutcome::TimedOut { frames }),
+                _ => {}
+            }
+        }
+    }
+
+    fn is_condition_met(&self, machine: &HeadlessMachine) -> bool {
+        if self.config.stop_conditions.is_empty() {
+            return false;
+        }
+
+        let pc = machine.get_main_board().get_pc();
+        let memory_mutex = machine.get_memory();
+        let memory = memory_mutex.lock();
+        let serial_output_mutex = machine.get_serial_output();
+        let serial_output = serial_output_mutex.lock();
+
+        self.config.stop_conditions.iter().any(|condition| condition.is_met(pc, &memory, &serial_output))
+    }
+
+    fn dump(&self, machine: &HeadlessMachine) -> Result<(), HeadlessError> {
+        match &self.config.memory_dump_path {
+            Some(path) => {
+                let data: Vec<u8> = {
+                    let memory_mutex = machine.get_memory();
+                    let memory = memory_mutex.lock();
+                    (0..=0xFFFF).map(|address| memory.get(address)).collect()
+                };
+
+                fs::write(path, data).map_err(|error| HeadlessError::MemoryDumpError { error })?;
+            }
+            None => {}
+        }
+
+        match &self.config.screen_dump_path {
+            Some(path) => machine.get_screen_buffer().lock().save_png(path).map_err(|error| HeadlessError::ScreenDumpError { error })?,
+            None => {}
+        }
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use crate::headless::stop_condition::StopCondition;
+    use crate::headless::test_rom_builder::write_test_rom;
+    use crate::movie::MovieStart;
+    use super::*;
+
+    /**
+        Writes 'O' to SB, starts a transfer, stores 0x42 at 0xC000 and loops forever
+    */
+    fn write_runner_test_rom(name: &str) -> PathBuf {
+        write_test_rom(name, 0x00, 0x00, &[
+            0x3E, b'O',         //LD A, 'O'
+            0xE0, 0x01,         //LDH (SB), A
+            0x3E, 0x81,         //LD A, 0x81
+            0xE0, 0x02,         //LDH (SC), A
+            0x3E, 0x42,         //LD A, 0x42
+            0xEA, 0x00, 0xC0,   //LD (0xC000), A
+            0x18, 0xFE,         //JR -2
+        ])
+    }
+
+    fn get_config(rom_path: &PathBuf) -> HeadlessConfig {
+        HeadlessConfig {
+            rom_path: rom_path.to_string_lossy().to_string(),
+            frames: Some(10),
+            timeout: None,
+            stop_conditions: vec![],
+            movie_path: None,
+            link_rom_path: None,
+
+            screen_dump_path: None,
+            memory_dump_path: None,
+            print_serial: false,
+        }
+    }
+
+    fn run(name: &str, configure: impl FnOnce(&mut HeadlessConfig)) -> Result<HeadlessOutcome, HeadlessError> {
+        let rom_path = write_runner_test_rom(name);
+        let mut config = get_config(&rom_path);
+        configure(&mut config);
+
+        let outcome = HeadlessRunner::new(config).run();
+        fs::remove_file(&rom_path).unwrap();
+
+        outcome
+    }
+
+    #[test]
+    fn runs_requested_number_of_frames() {
+        let outcome = run("frames", |config| config.frames = Some(3)).unwrap();
+
+        assert_eq!(HeadlessOutcome::FramesCompleted { frames: 3 }, outcome);
+        assert_eq!(exitcode::OK, outcome.get_exit_code());
+    }
+
+    #[test]
+    fn stops_when_pc_is_reached() {
+        let outcome = run("pc", |config| config.stop_conditions = vec![StopCondition::ProgramCounter { address: 0x0150 }]).unwrap();
+
+        assert_eq!(HeadlessOutcome::ConditionMet { frames: 0 }, outcome);
+    }
+
+    #[test]
+    fn stops_on_serial_output() {
+        let outcome = run("serial", |config| config.stop_conditions = vec![StopCondition::SerialOutput { text: "O".to_string() }]).unwrap();
+
+        assert_eq!(HeadlessOutcome::ConditionMet { frames: 1 }, outcome);
+    }
+
+    #[test]
+    fn stops_on_memory_value() {
+        let outcome = run("memory", |config| config.stop_conditions = vec![StopCondition::MemoryValue { address: 0xC000, value: 0x42 }]).unwrap();
+
+        assert_eq!(exitcode::OK, outcome.get_exit_code());
+    }
+
+    #[test]
+    fn fails_when_condition_is_not_met_in_time() {
+        let outcome = run("not_met", |config| {
+            config.frames = Some(2);
+            config.stop_conditions = vec![StopCondition::MemoryValue { address: 0xC000, value: 0x99 }];
+        }).unwrap();
+
+        assert_eq!(HeadlessOutcome::ConditionNotMet { frames: 2 }, outcome);
+        assert_eq!(exitcode::SOFTWARE, outcome.get_exit_code());
+    }
+
+    #[test]
+    fn times_out() {
+        let outcome = run("timeout", |config| {
+            config.frames = None;
+            config.timeout = Some(std::time::Duration::ZERO);
+        }).unwrap();
+
+        assert_eq!(HeadlessOutcome::TimedOut { frames: 1 }, outcome);
+        assert_eq!(exitcode::TEMPFAIL, outcome.get_exit_code());
+    }
+
+    #[test]
+    fn dumps_memory_and_screen() {
+        let memory_dump_path = std::env::temp_dir().join(format!("headless_memory_{}.bin", std::process::id()));
+        let screen_dump_path = std::env::temp_dir().join(format!("headless_screen_{}.png", std::process::id()));
+
+        run("dump", |config| {
+            config.frames = Some(1);
+            config.memory_dump_path = Some(memory_dump_path.clone());
+            config.screen_dump_path = Some(screen_dump_path.clone());
+        }).unwrap();
+
+        let memory_dump = fs::read(&memory_dump_path).unwrap();
+        assert_eq!(0x10000, memory_dump.len());
+        assert_eq!(0x42, memory_dump[0xC000]);
+        assert!(screen_dump_path.exists());
+
+        fs::remove_file(&memory_dump_path).unwrap();
+        fs::remove_file(&screen_dump_path).unwrap();
+    }
+
+    /**
+        Keeps selecting the action buttons and copying P1 to 0xC000
+    */
+    fn write_movie_test_rom(name: &str) -> PathBuf {
+        write_test_rom(name, 0x00, 0x00, &[
+            0x3E, 0x10,         //LD A, 0x10
+            0xE0, 0x00,         //LDH (P1), A
+            0xF0, 0x00,         //LDH A, (P1)
+            0xEA, 0x00, 0xC0,   //LD (0xC000), A
+            0x18, 0xF5,         //JR -11
+        ])
+    }
+
+    fn run_movie(name: &str, inputs: &[u8], stop_conditions: Vec<StopCondition>) -> HeadlessOutcome {
+        let rom_path = write_movie_test_rom(name);
+        let movie_path = std::env::temp_dir().join(format!("headless_movie_{}_{}.gbm", name, std::process::id()));
+
+        let machine = HeadlessMachine::new(&rom_path.to_string_lossy().to_string(), false).unwrap();
+        let mut movie = Movie::new(Movie::hash_rom_file(&rom_path).unwrap(), MovieStart::PowerOn, machine.get_main_board().save_state());
+        for buttons in inputs {
+            movie.push_input(*buttons);
+        }
+        movie.save(&movie_path).unwrap();
+
+        let mut config = get_config(&rom_path);
+        config.frames = None;
+        config.movie_path = Some(movie_path.clone());
+        config.stop_conditions = stop_conditions;
+
+        let outcome = HeadlessRunner::new(config).run();
+        fs::remove_file(&rom_path).unwrap();
+        fs::remove_file(&movie_path).unwrap();
+
+        outcome.unwrap()
+    }
+
+    #[test]
+    fn plays_movie_input() {
+        let outcome = run_movie("pressed", &[0x00, 0x00, 0x10, 0x10], vec![StopCondition::MemoryValue { address: 0xC000, value: 0xDE }]);
+
+        assert_eq!(HeadlessOutcome::ConditionMet { frames: 3 }, outcome);
+    }
+
+    #[test]
+    fn stops_when_movie_ends() {
+        let outcome = run_movie("ends", &[0x00, 0x00, 0x00], vec![StopCondition::MemoryValue { address: 0xC000, value: 0xDE }]);
+
+        assert_eq!(HeadlessOutcome::ConditionNotMet { frames: 3 }, outcome);
+    }
+
+    #[test]
+    fn exchanges_bytes_with_linked_rom() {
+        let rom_path = write_test_rom("link_internal", 0x00, 0x00, &[
+            0x3E, 0x12,         //LD A, 0x12
+            0xE0, 0x01,         //LDH (SB), A
+            0x3E, 0x81,         //LD A, 0x81
+            0xE0, 0x02,         //LDH (SC), A
+            0x18, 0xFE,         //JR -2
+        ]);
+        let link_rom_path = write_test_rom("link_external", 0x00, 0x00, &[
+            0x3E, 0x34,         //LD A, 0x34
+            0xE0, 0x01,         //LDH (SB), A
+            0x3E, 0x80,         //LD A, 0x80
+            0xE0, 0x02,         //LDH (SC), A
+            0x18, 0xFE,         //JR -2
+        ]);
+
+        let mut config = get_config(&rom_path);
+        config.link_rom_path = Some(link_rom_path.to_string_lossy().to_string());
+        config.stop_conditions = vec![StopCondition::MemoryValue { address: 0xFF01, value: 0x34 }];
+
+        let outcome = HeadlessRunner::new(config).run();
+        fs::remove_file(&rom_path).unwrap();
+        fs::remove_file(&link_rom_path).unwrap();
+
+        assert_eq!(HeadlessOutcome::ConditionMet { frames: 0 }, outcome.unwrap());
+    }
+
+    #[test]
+    fn ignores_battery_save_next_to_rom() {
+        let rom_path = write_test_rom("battery", 0x03, 0x02, &[ //MBC1+RAM+BATTERY, 8KB
+            0x3E, 0x0A,         //LD A, 0x0A
+            0xEA, 0x00, 0x00,   //LD (0x0000), A
+            0xFA, 0x00, 0xA0,   //LD A, (0xA000)
+            0xEA, 0x00, 0xC0,   //LD (0xC000), A
+            0x18, 0xFE,         //JR -2
+        ]);
+        let save_path = rom_path.with_extension("sav");
+        fs::write(&save_path, vec![0x42; 0x2000]).unwrap();
+
+        let mut config = get_config(&rom_path);
+        config.frames = Some(2);
+        config.stop_conditions = vec![StopCondition::MemoryValue { address: 0xC000, value: 0x42 }];
+
+        let outcome = HeadlessRunner::new(config).run();
+        fs::remove_file(&rom_path).unwrap();
+        let save_data = fs::read(&save_path).unwrap();
+        fs::remove_file(&save_path).unwrap();
+
+        assert_eq!(HeadlessOutcome::ConditionNotMet { frames: 2 }, outcome.unwrap());
+        assert_eq!(vec![0x42; 0x2000], save_data);
+    }
+
+    #[test]
+    fn missing_rom_is_an_error() {
+        let mut config = get_config(&PathBuf::from("./does_not_exist.gb"));
+        config.frames = Some(1);
+
+        let result = HeadlessRunner::new(config).run();
+
+        assert!(matches!(result, Err(HeadlessError::RomError { .. })));
+        assert_eq!(exitcode::NOINPUT, result.unwrap_err().get_exit_code());
+    }
+}
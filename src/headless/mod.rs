@@ -0,0 +1,11 @@
+mod headless_config;
+mod headless_error;
+mod headless_machine;
+mod headless_runner;
+mod stop_condition;
+mod test_rom_harness;
+#[cfg(test)]
+mod test_rom_builder;
+
+pub use headless_runner::HeadlessRunner;
+pub use test_rom_harness::TestRomHarness;
@@ -0,0 +1,60 @@
+use crate::memory::{MemoryController, MemoryTrait};
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum StopCondition {
+    ProgramCounter { address: u16 },
+    MemoryValue { address: u16, value: u8 },
+    SerialOutput { text: String },
+}
+
+impl StopCondition {
+
+    /**
+        The program counter can pass an address within a single M-cycle, so it has to be checked after every one
+    */
+    pub fn needs_every_cycle(&self) -> bool {
+        matches!(self, StopCondition::ProgramCounter { .. })
+    }
+
+    pub fn is_met(&self, pc: u16, memory: &MemoryController, serial_output: &[u8]) -> bool {
+        match self {
+            StopCondition::ProgramCounter { address } => pc == *address,
+            StopCondition::MemoryValue { address, value } => memory.get(*address) == *value,
+            StopCondition::SerialOutput { text } => serial_output.windows(text.len()).any(|window| window == text.as_bytes()),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn program_counter_matches_address() {
+        let memory = MemoryController::new();
+        let condition = StopCondition::ProgramCounter { address: 0x0150 };
+
+        assert!(condition.is_met(0x0150, &memory, &[]));
+        assert!(!condition.is_met(0x0151, &memory, &[]));
+    }
+
+    #[test]
+    fn memory_value_reads_memory() {
+        let mut memory = MemoryController::new();
+        let condition = StopCondition::MemoryValue { address: 0xC000, value: 0x42 };
+
+        assert!(!condition.is_met(0, &memory, &[]));
+        memory.set(0xC000, 0x42);
+        assert!(condition.is_met(0, &memory, &[]));
+    }
+
+    #[test]
+    fn serial_output_finds_text_anywhere() {
+        let memory = MemoryController::new();
+        let condition = StopCondition::SerialOutput { text: "Passed".to_string() };
+
+        assert!(condition.is_met(0, &memory, b"cpu_instrs\n\nPassed all tests"));
+        assert!(!condition.is_met(0, &memory, b"Pass"));
+    }
+}
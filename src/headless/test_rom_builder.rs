@@ -0,0 +1,21 @@
+use std::fs;
+use std::path::PathBuf;
+
+/**
+    Writes a 32KB ROM with a valid header that jumps from 0x0100 to the program at 0x0150
+*/
+pub fn write_test_rom(name: &str, cartridge_type: u8, ram_size: u8, program: &[u8]) -> PathBuf {
+    let mut data = vec![0u8; 0x8000];
+
+    data[0x0100..0x0103].copy_from_slice(&[0xC3, 0x50, 0x01]); //JP 0x0150
+    data[0x0147] = cartridge_type;
+    data[0x0149] = ram_size;
+    data[0x0150..0x0150 + program.len()].copy_from_slice(program);
+
+    data[0x014D] = data[0x0134..0x014D].iter().fold(0u8, |checksum, &byte| checksum.wrapping_sub(byte).wrapping_sub(1));
+
+    let path = std::env::temp_dir().join(format!("headless_{}_{}.gb", name, std::process::id()));
+    fs::write(&path, data).unwrap();
+
+    path
+}
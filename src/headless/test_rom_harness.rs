@@ -0,0 +1,449 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::headless::headless_error::HeadlessError;
+use crate::headless::headless_machine::HeadlessMachine;
+use crate::memory::MemoryTrait;
+use crate::renderer::ScreenBuffer;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum TestRomResult {
+    Passed,
+    Failed { reason: String },
+    TimedOut,
+    Error { message: String },
+}
+
+impl TestRomResult {
+    fn get_name(&self) -> &'static str {
+        match self {
+            TestRomResult::Passed => "PASS",
+            TestRomResult::Failed { .. } => "FAIL",
+            TestRomResult::TimedOut => "TIMEOUT",
+            TestRomResult::Error { .. } => "ERROR",
+        }
+    }
+
+    fn get_detail(&self) -> &str {
+        match self {
+            TestRomResult::Failed { reason } => reason,
+            TestRomResult::Error { message } => message,
+            _ => ""
+        }
+    }
+}
+
+pub struct TestRomReport {
+    pub name: String,
+    pub result: TestRomResult,
+    pub frames: u64,
+}
+
+/**
+    --test-roms <directory> [--frames <limit>] runs every .gb and .gbc file under the directory and prints a results table.
+    Results are read the way each suite reports them:
+        Blargg      "Passed" or "Failed" over serial, or the status byte at 0xA000 once 0xA001-0xA003 hold DE B0 61
+        Mooneye     LD B,B with B, C, D, E, H, L holding 3, 5, 8, 13, 21, 34 to pass or all 0x42 to fail,
+                    or the same six bytes sent over serial
+        Screenshots a ROM with a PNG of the same name beside it (dmg-acid2.gb and dmg-acid2.png) is compared to it
+                    at LD B,B or once the frame limit is reached
+*/
+pub struct TestRomHarness {
+    directory: PathBuf,
+    frame_limit: u64,
+}
+
+impl TestRomHarness {
+
+    pub const USAGE: &'static str = "Usage: --test-roms <directory> [--frames <limit>]";
+    pub const DEFAULT_FRAME_LIMIT: u64 = 7200; //two minutes of Game Boy time
+
+    const BREAKPOINT_OPCODE: u8 = 0x40; //LD B,B
+    const MOONEYE_PASS: [u8; 6] = [3, 5, 8, 13, 21, 34];
+    const MOONEYE_FAIL: [u8; 6] = [0x42; 6];
+    const BLARGG_SIGNATURE: [u8; 3] = [0xDE, 0xB0, 0x61];
+    const BLARGG_RUNNING: u8 = 0x80;
+
+    pub fn new(directory: PathBuf, frame_limit: u64) -> Self {
+        Self {
+            directory,
+            frame_limit,
+        }
+    }
+
+    /**
+        Returns the process exit code, which is only OK when every ROM passed
+    */
+    pub fn run_from_args(args: &[String]) -> i32 {
+        let get_argument_value = |name: &str| args.iter().position(|arg| arg == name).and_then(|index| args.get(index + 1));
+
+        let directory = match get_argument_value("--test-roms") {
+            Some(directory) => PathBuf::from(directory),
+            None => {
+                eprintln!("{}", Self::USAGE);
+                return exitcode::USAGE;
+            }
+        };
+
+        let frame_limit = match get_argument_value("--frames").map(|value| value.parse::<u64>()) {
+            Some(Ok(frame_limit)) => frame_limit,
+            Some(Err(_)) => {
+                eprintln!("{}", Self::USAGE);
+                return exitcode::USAGE;
+            }
+            None => Self::DEFAULT_FRAME_LIMIT
+        };
+
+        let harness = TestRomHarness::new(directory, frame_limit);
+
+        match harness.run_all() {
+            Ok(reports) => {
+                print!("{}", Self::format_table(&reports));
+
+                if reports.iter().all(|report| report.result == TestRomResult::Passed) {
+                    exitcode::OK
+                } else {
+                    exitcode::SOFTWARE
+                }
+            }
+            Err(error) => {
+                eprintln!("{}", error);
+                error.get_exit_code()
+            }
+        }
+    }
+
+    pub fn run_all(&self) -> Result<Vec<TestRomReport>, HeadlessError> {
+        let mut rom_paths = vec![];
+        Self::find_roms(&self.directory, &mut rom_paths)?;
+        rom_paths.sort();
+
+        Ok(rom_paths.iter().map(|rom_path| self.run_rom(rom_path)).collect())
+    }
+
+    fn find_roms(directory: &Path, rom_paths: &mut Vec<PathBuf>) -> Result<(), HeadlessError> {
+        let entries = fs::read_dir(directory).map_err(|error| HeadlessError::TestRomDirectoryError { error })?;
+
+        for entry in entries {
+            let path = entry.map_err(|error| HeadlessError::TestRomDirectoryError { error })?.path();
+
+            if path.is_dir() {
+                Self::find_roms(&path, rom_paths)?;
+            } else if matches!(path.extension().and_then(|extension| extension.to_str()), Some("gb") | Some("gbc")) {
+                rom_paths.push(path);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn run_rom(&self, rom_path: &Path) -> TestRomReport {
+        let name = rom_path.strip_prefix(&self.directory).unwrap_or(rom_path).to_string_lossy().to_string();
+        let reference_path = rom_path.with_extension("png");
+
+        let reference = if reference_path.exists() {
+            match ScreenBuffer::load_png(&reference_path) {
+                Ok(reference) => Some(reference),
+                Err(error) => return TestRomReport { name, result: TestRomResult::Error { message: error.to_string() }, frames: 0 }
+            }
+        } else {
+            None
+        };
+
+        let (result, frames) = match self.run_machine(rom_path, reference.as_ref()) {
+            Ok(result_and_frames) => result_and_frames,
+            Err(error) => (TestRomResult::Error { message: error.to_string() }, 0)
+        };
+
+        TestRomReport {
+            name,
+            result,
+            frames,
+        }
+    }
+
+    fn run_machine(&self, rom_path: &Path, reference: Option<&ScreenBuffer>) -> Result<(TestRomResult, u64), HeadlessError> {
+        let mut machine = HeadlessMachine::new(&rom_path.to_string_lossy().to_string(), false)?;
+        let mut frames = 0;
+        let mut last_pc = None;
+        let mut serial_length = 0;
+
+        while frames < self.frame_limit {
+            if machine.step(2)? { //one M-cycle, so no LD B,B is missed
+                frames += 1;
+
+                match Self::check_blargg_memory(&*machine.get_memory().lock()) {
+                    Some(result) => return Ok((result, frames)),
+                    None => {}
+                }
+            }
+
+            let serial_output_mutex = machine.get_serial_output();
+            let serial_output = serial_output_mutex.lock();
+            if serial_output.len() != serial_length {
+                serial_length = serial_output.len();
+
+                match Self::check_serial(&serial_output) {
+                    Some(result) => return Ok((result, frames)),
+                    None => {}
+                }
+            }
+
+            let pc = machine.get_main_board().get_pc();
+            if last_pc == Some(pc) {
+                continue;
+            }
+            last_pc = Some(pc);
+
+            let opcode = machine.get_memory().lock().get(pc);
+            if opcode == Self::BREAKPOINT_OPCODE {
+                match Self::check_breakpoint(&machine, reference) {
+                    Some(result) => return Ok((result, frames)),
+                    None => {}
+                }
+            }
+        }
+
+        let result = match reference {
+            Some(reference) => Self::compare_screen(&machine, reference),
+            None => TestRomResult::TimedOut
+        };
+
+        Ok((result, frames))
+    }
+
+    fn check_serial(serial_output: &[u8]) -> Option<TestRomResult> {
+        let contains = |text: &[u8]| serial_output.windows(text.len()).any(|window| window == text);
+
+        if contains(b"Passed") || serial_output.ends_with(&Self::MOONEYE_PASS) {
+            Some(TestRomResult::Passed)
+        } else if serial_output.ends_with(&Self::MOONEYE_FAIL) {
+            Some(TestRomResult::Failed { reason: "Mooneye failure sent over serial".to_string() })
+        } else if contains(b"Failed") {
+            Some(TestRomResult::Failed { reason: Self::get_last_line(&String::from_utf8_lossy(serial_output)) })
+        } else {
+            None
+        }
+    }
+
+    fn check_blargg_memory(memory: &impl MemoryTrait) -> Option<TestRomResult> {
+        let signature = [memory.get(0xA001), memory.get(0xA002), memory.get(0xA003)];
+        let status = memory.get(0xA000);
+
+        if signature != Self::BLARGG_SIGNATURE || status == Self::BLARGG_RUNNING {
+            return None;
+        }
+
+        if status == 0 {
+            return Some(TestRomResult::Passed);
+        }
+
+        let text: Vec<u8> = (0xA004..0xC000u16)
+            .map(|address| memory.get(address))
+            .take_while(|&character| character != 0)
+            .collect();
+
+        Some(TestRomResult::Failed { reason: format!("Status {:02X}: {}", status, Self::get_last_line(&String::from_utf8_lossy(&text))) })
+    }
+
+    fn check_breakpoint(machine: &HeadlessMachine, reference: Option<&ScreenBuffer>) -> Option<TestRomResult> {
+        let (bc, de, hl) = machine.get_main_board().get_bc_de_hl();
+        let registers = [(bc >> 8) as u8, bc as u8, (de >> 8) as u8, de as u8, (hl >> 8) as u8, hl as u8];
+
+        if registers == Self::MOONEYE_PASS {
+            return Some(TestRomResult::Passed);
+        }
+
+        if registers == Self::MOONEYE_FAIL {
+            return Some(TestRomResult::Failed { reason: "Mooneye failure registers".to_string() });
+        }
+
+        reference.map(|reference| Self::compare_screen(machine, reference)) //other ROMs use LD B,B as an ordinary instruction
+    }
+
+    fn compare_screen(machine: &HeadlessMachine, reference: &ScreenBuffer) -> TestRomResult {
+        let differences = machine.get_screen_buffer().lock().count_differences(reference);
+
+        if differences == 0 {
+            TestRomResult::Passed
+        } else {
+            TestRomResult::Failed { reason: format!("{} pixels differ from the reference", differences) }
+        }
+    }
+
+    fn get_last_line(text: &str) -> String {
+        text.lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .last()
+            .unwrap_or("")
+            .to_string()
+    }
+
+    pub fn format_table(reports: &[TestRomReport]) -> String {
+        let name_width = reports.iter().map(|report| report.name.len()).max().unwrap_or(0).max("ROM".len());
+        let passed = reports.iter().filter(|report| report.result == TestRomResult::Passed).count();
+
+        let mut table = format!("{:<name_width$}  {:<7}  {:>6}  {}\n", "ROM", "Result", "Frames", "Detail");
+
+        for report in reports {
+            table.push_str(&format!("{:<name_width$}  {:<7}  {:>6}  {}\n", report.name, report.result.get_name(), report.frames, report.result.get_detail()));
+        }
+
+        table.push_str(&format!("{}/{} passed\n", passed, reports.len()));
+
+        table
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::headless::test_rom_builder::write_test_rom;
+    use super::*;
+
+    const SET_MOONEYE_PASS_REGISTERS: [u8; 12] = [
+        0x06, 3,    //LD B, 3
+        0x0E, 5,    //LD C, 5
+        0x16, 8,    //LD D, 8
+        0x1E, 13,   //LD E, 13
+        0x26, 21,   //LD H, 21
+        0x2E, 34,   //LD L, 34
+    ];
+
+    const CLEAR_SCREEN: [u8; 5] = [
+        0xAF,       //XOR A
+        0xE0, 0x47, //LDH (BGP), A, so every background colour is white
+        0x18, 0xFE,
+    ];
+
+    fn get_test_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("test_roms_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+
+        directory
+    }
+
+    fn run_single_rom(name: &str, cartridge_type: u8, ram_size: u8, program: &[u8], reference: Option<&ScreenBuffer>) -> TestRomReport {
+        let directory = get_test_directory(name);
+        let rom_path = directory.join("test.gb");
+        fs::rename(write_test_rom(name, cartridge_type, ram_size, program), &rom_path).unwrap();
+
+        match reference {
+            Some(reference) => reference.save_png(&rom_path.with_extension("png")).unwrap(),
+            None => {}
+        }
+
+        let report = TestRomHarness::new(directory.clone(), 5).run_rom(&rom_path);
+        fs::remove_dir_all(&directory).unwrap();
+
+        report
+    }
+
+    #[test]
+    fn serial_passed_and_failed() {
+        assert_eq!(Some(TestRomResult::Passed), TestRomHarness::check_serial(b"cpu_instrs\n\nPassed all tests\n"));
+        assert_eq!(
+            Some(TestRomResult::Failed { reason: "Failed 2 tests.".to_string() }),
+            TestRomHarness::check_serial(b"cpu_instrs\n\n01:ok  02:01\n\nFailed 2 tests.\n")
+        );
+        assert_eq!(None, TestRomHarness::check_serial(b"cpu_instrs\n\n01:ok"));
+    }
+
+    #[test]
+    fn serial_mooneye_signatures() {
+        assert_eq!(Some(TestRomResult::Passed), TestRomHarness::check_serial(&[3, 5, 8, 13, 21, 34]));
+        assert!(matches!(TestRomHarness::check_serial(&[0x42; 6]), Some(TestRomResult::Failed { .. })));
+    }
+
+    #[test]
+    fn blargg_memory_failure_reports_text() {
+        let report = run_single_rom("blargg_memory", 0x02, 0x02, &[
+            0x3E, 0x0A, 0xEA, 0x00, 0x00,   //enable cartridge RAM
+            0x3E, 0xDE, 0xEA, 0x01, 0xA0,
+            0x3E, 0xB0, 0xEA, 0x02, 0xA0,
+            0x3E, 0x61, 0xEA, 0x03, 0xA0,
+            0x3E, b'X', 0xEA, 0x04, 0xA0,
+            0xAF, 0xEA, 0x05, 0xA0,         //text terminator
+            0x3E, 0x01, 0xEA, 0x00, 0xA0,   //status 1
+            0x18, 0xFE,
+        ], None);
+
+        assert_eq!(TestRomResult::Failed { reason: "Status 01: X".to_string() }, report.result);
+    }
+
+    #[test]
+    fn mooneye_registers_pass() {
+        let mut program = SET_MOONEYE_PASS_REGISTERS.to_vec();
+        program.extend_from_slice(&[0x40, 0x18, 0xFE]); //LD B,B then loop
+
+        let report = run_single_rom("mooneye_pass", 0x00, 0x00, &program, None);
+
+        assert_eq!(TestRomResult::Passed, report.result);
+        assert_eq!(0, report.frames);
+    }
+
+    #[test]
+    fn mooneye_registers_fail() {
+        let report = run_single_rom("mooneye_fail", 0x00, 0x00, &[
+            0x3E, 0x42, 0x47, 0x4F, 0x57, 0x5F, 0x67, 0x6F, //LD A, 0x42 then copy it to B, C, D, E, H and L
+            0x40, 0x18, 0xFE,
+        ], None);
+
+        assert_eq!(TestRomResult::Failed { reason: "Mooneye failure registers".to_string() }, report.result);
+    }
+
+    #[test]
+    fn ordinary_ld_b_b_does_not_end_the_test() {
+        let report = run_single_rom("ordinary_ld_b_b", 0x00, 0x00, &[0x40, 0x18, 0xFD], None);
+
+        assert_eq!(TestRomResult::TimedOut, report.result);
+        assert_eq!(5, report.frames);
+    }
+
+    #[test]
+    fn screen_matching_reference_passes() {
+        let report = run_single_rom("screenshot_pass", 0x00, 0x00, &CLEAR_SCREEN, Some(&ScreenBuffer::new()));
+
+        assert_eq!(TestRomResult::Passed, report.result);
+    }
+
+    #[test]
+    fn screen_differing_from_reference_fails() {
+        let mut reference = ScreenBuffer::new();
+        reference.get_line_mut(0)[0] = 3;
+
+        let report = run_single_rom("screenshot_fail", 0x00, 0x00, &CLEAR_SCREEN, Some(&reference));
+
+        assert_eq!(TestRomResult::Failed { reason: "1 pixels differ from the reference".to_string() }, report.result);
+    }
+
+    #[test]
+    fn finds_roms_in_subdirectories_and_formats_table() {
+        let directory = get_test_directory("table");
+        fs::create_dir_all(directory.join("mooneye")).unwrap();
+        let mut program = SET_MOONEYE_PASS_REGISTERS.to_vec();
+        program.extend_from_slice(&[0x40, 0x18, 0xFE]);
+        fs::rename(write_test_rom("table_pass", 0x00, 0x00, &program), directory.join("mooneye/pass.gb")).unwrap();
+        fs::rename(write_test_rom("table_loop", 0x00, 0x00, &[0x18, 0xFE]), directory.join("loop.gb")).unwrap();
+        fs::write(directory.join("notes.txt"), "not a ROM").unwrap();
+
+        let reports = TestRomHarness::new(directory.clone(), 2).run_all().unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(vec!["loop.gb", "mooneye/pass.gb"], reports.iter().map(|report| report.name.as_str()).collect::<Vec<_>>());
+        assert_eq!(
+            "ROM              Result   Frames  Detail\n\
+             loop.gb          TIMEOUT       2  \n\
+             mooneye/pass.gb  PASS          0  \n\
+             1/2 passed\n",
+            TestRomHarness::format_table(&reports)
+        );
+    }
+
+    #[test]
+    fn missing_directory_is_an_error() {
+        let result = TestRomHarness::new(PathBuf::from("./does_not_exist"), 1).run_all();
+
+        assert!(matches!(result, Err(HeadlessError::TestRomDirectoryError { .. })));
+    }
+}
@@ -0,0 +1,87 @@
+use glfw::{Action, GamepadAxis, GamepadButton, GamepadState};
+
+/**
+    A button, or one direction of a stick or trigger, on GLFW's standard gamepad layout
+*/
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GamepadInput {
+    Button(GamepadButton),
+    Axis(GamepadAxis, bool), //true for the positive direction, which is right and down for the sticks
+}
+
+impl GamepadInput {
+
+    const NAMES: [(&'static str, GamepadInput); 25] = [
+        ("A", GamepadInput::Button(GamepadButton::ButtonA)),
+        ("B", GamepadInput::Button(GamepadButton::ButtonB)),
+        ("X", GamepadInput::Button(GamepadButton::ButtonX)),
+        ("Y", GamepadInput::Button(GamepadButton::ButtonY)),
+        ("LeftBumper", GamepadInput::Button(GamepadButton::ButtonLeftBumper)),
+        ("RightBumper", GamepadInput::Button(GamepadButton::ButtonRightBumper)),
+        ("Back", GamepadInput::Button(GamepadButton::ButtonBack)),
+        ("Start", GamepadInput::Button(GamepadButton::ButtonStart)),
+        ("Guide", GamepadInput::Button(GamepadButton::ButtonGuide)),
+        ("LeftThumb", GamepadInput::Button(GamepadButton::ButtonLeftThumb)),
+        ("RightThumb", GamepadInput::Button(GamepadButton::ButtonRightThumb)),
+        ("DpadUp", GamepadInput::Button(GamepadButton::ButtonDpadUp)),
+        ("DpadRight", GamepadInput::Button(GamepadButton::ButtonDpadRight)),
+        ("DpadDown", GamepadInput::Button(GamepadButton::ButtonDpadDown)),
+        ("DpadLeft", GamepadInput::Button(GamepadButton::ButtonDpadLeft)),
+        ("LeftStickUp", GamepadInput::Axis(GamepadAxis::AxisLeftY, false)),
+        ("LeftStickRight", GamepadInput::Axis(GamepadAxis::AxisLeftX, true)),
+        ("LeftStickDown", GamepadInput::Axis(GamepadAxis::AxisLeftY, true)),
+        ("LeftStickLeft", GamepadInput::Axis(GamepadAxis::AxisLeftX, false)),
+        ("RightStickUp", GamepadInput::Axis(GamepadAxis::AxisRightY, false)),
+        ("RightStickRight", GamepadInput::Axis(GamepadAxis::AxisRightX, true)),
+        ("RightStickDown", GamepadInput::Axis(GamepadAxis::AxisRightY, true)),
+        ("RightStickLeft", GamepadInput::Axis(GamepadAxis::AxisRightX, false)),
+        ("LeftTrigger", GamepadInput::Axis(GamepadAxis::AxisLeftTrigger, true)),
+        ("RightTrigger", GamepadInput::Axis(GamepadAxis::AxisRightTrigger, true)),
+    ];
+
+    pub fn from_name(name: &str) -> Option<GamepadInput> {
+        Self::NAMES.into_iter()
+            .find(|(input_name, _)| input_name.eq_ignore_ascii_case(name))
+            .map(|(_, input)| input)
+    }
+
+    /**
+        Axes count as pressed once they are pushed further than dead_zone, triggers rest at -1.0
+    */
+    pub fn is_pressed(&self, state: &GamepadState, dead_zone: f32) -> bool {
+        match self {
+            GamepadInput::Button(button) => state.get_button_state(*button) == Action::Press,
+            GamepadInput::Axis(axis, true) => state.get_axis(*axis) > dead_zone,
+            GamepadInput::Axis(axis, false) => state.get_axis(*axis) < -dead_zone,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use glfw::ffi::GLFWgamepadstate;
+    use super::*;
+
+    #[test]
+    fn parses_names_case_insensitively() {
+        assert_eq!(Some(GamepadInput::Button(GamepadButton::ButtonDpadUp)), GamepadInput::from_name("dpadup"));
+        assert_eq!(Some(GamepadInput::Axis(GamepadAxis::AxisLeftX, false)), GamepadInput::from_name("LeftStickLeft"));
+        assert_eq!(None, GamepadInput::from_name("Turbo"));
+    }
+
+    #[test]
+    fn axes_are_pressed_past_dead_zone() {
+        let mut state = GLFWgamepadstate { buttons: [0; 15], axes: [0.0; 6] };
+        state.buttons[GamepadButton::ButtonA as usize] = 1;
+        state.axes[GamepadAxis::AxisLeftX as usize] = -0.8;
+        state.axes[GamepadAxis::AxisLeftY as usize] = 0.3;
+        let state = GamepadState::from(state);
+
+        assert!(GamepadInput::Button(GamepadButton::ButtonA).is_pressed(&state, 0.5));
+        assert!(!GamepadInput::Button(GamepadButton::ButtonB).is_pressed(&state, 0.5));
+        assert!(GamepadInput::Axis(GamepadAxis::AxisLeftX, false).is_pressed(&state, 0.5));
+        assert!(!GamepadInput::Axis(GamepadAxis::AxisLeftX, true).is_pressed(&state, 0.5));
+        assert!(!GamepadInput::Axis(GamepadAxis::AxisLeftY, true).is_pressed(&state, 0.5));
+    }
+}
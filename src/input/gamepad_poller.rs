@@ -0,0 +1,45 @@
+use glfw::{GamepadState, Glfw, JoystickId};
+
+/**
+    Checks every joystick slot each frame so gamepads can be plugged in and removed while running.
+    Joysticks without a gamepad mapping in GLFW's database are ignored.
+*/
+pub struct GamepadPoller {
+    glfw: Glfw,
+    connected: Vec<JoystickId>,
+}
+
+impl GamepadPoller {
+
+    pub fn new(glfw: Glfw) -> Self {
+        Self {
+            glfw,
+            connected: vec![],
+        }
+    }
+
+    pub fn poll(&mut self) -> Vec<GamepadState> {
+        let mut states = vec![];
+
+        for id in (0..16).filter_map(JoystickId::from_i32) {
+            let joystick = self.glfw.get_joystick(id);
+            let state = if joystick.is_gamepad() { joystick.get_gamepad_state() } else { None };
+
+            match (state.is_some(), self.connected.contains(&id)) {
+                (true, false) => {
+                    eprintln!("Gamepad connected: {}", joystick.get_gamepad_name().unwrap_or_default());
+                    self.connected.push(id);
+                }
+                (false, true) => {
+                    eprintln!("Gamepad disconnected");
+                    self.connected.retain(|connected_id| *connected_id != id);
+                }
+                _ => {}
+            }
+
+            states.extend(state);
+        }
+
+        states
+    }
+}
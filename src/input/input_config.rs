@@ -0,0 +1,267 @@
+use std::fs;
+use std::path::Path;
+use glfw::{GamepadAxis, GamepadButton, GamepadState, Key};
+use crate::app::App;
+use crate::input::gamepad_input::GamepadInput;
+use crate::input::input_error::InputError;
+use crate::input::joypad_button::JoypadButton;
+use crate::input::opposing_direction_policy::OpposingDirectionPolicy;
+
+/**
+    Which keys press which buttons, read from lines of <button> = <key>, <key>... where keys are named as in GLFW
+    (W, Space, Up, LeftShift, Kp5 and so on). A button's line replaces its default keys, and
+    opposing-directions = allow | last-wins | neutral sets what happens when left and right or up and down are held.
+    Gamepads are bound the same way with gamepad-<button> = <input>, <input>... using the names in GamepadInput,
+    and gamepad-dead-zone = <0.0 to 1.0> sets how far a stick has to be pushed. Anything after # is a comment.
+    The hotkeys in App::HOTKEYS can't be bound.
+*/
+pub struct InputConfig {
+    bindings: Vec<(Key, JoypadButton)>,
+    gamepad_bindings: Vec<(GamepadInput, JoypadButton)>,
+    gamepad_dead_zone: f32,
+    opposing_direction_policy: OpposingDirectionPolicy,
+}
+
+impl InputConfig {
+
+    pub const DEFAULT_PATH: &'static str = "input.cfg";
+    const POLICY_NAME: &'static str = "opposing-directions";
+    const GAMEPAD_PREFIX: &'static str = "gamepad-";
+    const DEAD_ZONE_NAME: &'static str = "gamepad-dead-zone";
+    const DEFAULT_DEAD_ZONE: f32 = 0.5;
+
+    const KEYS: &'static [Key] = &[
+        Key::Space, Key::Apostrophe, Key::Comma, Key::Minus, Key::Period, Key::Slash, Key::Num0, Key::Num1,
+        Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9,
+        Key::Semicolon, Key::Equal, Key::A, Key::B, Key::C, Key::D, Key::E, Key::F,
+        Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M, Key::N,
+        Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V,
+        Key::W, Key::X, Key::Y, Key::Z, Key::LeftBracket, Key::Backslash, Key::RightBracket, Key::GraveAccent,
+        Key::Escape, Key::Enter, Key::Tab, Key::Backspace, Key::Insert, Key::Delete, Key::Right, Key::Left,
+        Key::Down, Key::Up, Key::PageUp, Key::PageDown, Key::Home, Key::End, Key::CapsLock, Key::ScrollLock,
+        Key::NumLock, Key::PrintScreen, Key::Pause, Key::F1, Key::F2, Key::F3, Key::F4, Key::F5,
+        Key::F6, Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12, Key::Kp0,
+        Key::Kp1, Key::Kp2, Key::Kp3, Key::Kp4, Key::Kp5, Key::Kp6, Key::Kp7, Key::Kp8,
+        Key::Kp9, Key::KpDecimal, Key::KpDivide, Key::KpMultiply, Key::KpSubtract, Key::KpAdd, Key::KpEnter, Key::KpEqual,
+        Key::LeftShift, Key::LeftControl, Key::LeftAlt, Key::LeftSuper, Key::RightShift, Key::RightControl, Key::RightAlt, Key::RightSuper,
+        Key::Menu,
+    ];
+
+    pub fn new() -> Self {
+        Self {
+            bindings: vec![
+                (Key::W, JoypadButton::Up),
+                (Key::S, JoypadButton::Down),
+                (Key::A, JoypadButton::Left),
+                (Key::D, JoypadButton::Right),
+                (Key::Space, JoypadButton::A),
+                (Key::Enter, JoypadButton::B),
+                (Key::E, JoypadButton::Select),
+                (Key::Q, JoypadButton::Start),
+            ],
+            gamepad_bindings: vec![
+                (GamepadInput::Button(GamepadButton::ButtonDpadUp), JoypadButton::Up),
+                (GamepadInput::Button(GamepadButton::ButtonDpadDown), JoypadButton::Down),
+                (GamepadInput::Button(GamepadButton::ButtonDpadLeft), JoypadButton::Left),
+                (GamepadInput::Button(GamepadButton::ButtonDpadRight), JoypadButton::Right),
+                (GamepadInput::Axis(GamepadAxis::AxisLeftY, false), JoypadButton::Up),
+                (GamepadInput::Axis(GamepadAxis::AxisLeftY, true), JoypadButton::Down),
+                (GamepadInput::Axis(GamepadAxis::AxisLeftX, false), JoypadButton::Left),
+                (GamepadInput::Axis(GamepadAxis::AxisLeftX, true), JoypadButton::Right),
+                (GamepadInput::Button(GamepadButton::ButtonB), JoypadButton::A), //by position, the Game Boy's A is the right hand button
+                (GamepadInput::Button(GamepadButton::ButtonA), JoypadButton::B),
+                (GamepadInput::Button(GamepadButton::ButtonBack), JoypadButton::Select),
+                (GamepadInput::Button(GamepadButton::ButtonStart), JoypadButton::Start),
+            ],
+            gamepad_dead_zone: Self::DEFAULT_DEAD_ZONE,
+            opposing_direction_policy: OpposingDirectionPolicy::Allow,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, InputError> {
+        let text = fs::read_to_string(path).map_err(|error| InputError::ReadError { error })?;
+
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, InputError> {
+        let mut config = Self::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let parse_error = |message: String| InputError::ParseError { line: index + 1, message };
+
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (name, value) = line.split_once('=')
+                .ok_or_else(|| parse_error(format!("Expected <name> = <value>, got {}", line)))?;
+            let (name, value) = (name.trim(), value.trim());
+
+            if name.eq_ignore_ascii_case(Self::POLICY_NAME) {
+                config.opposing_direction_policy = OpposingDirectionPolicy::from_name(value)
+                    .ok_or_else(|| parse_error(format!("Unknown opposing direction policy {}, expected allow, last-wins or neutral", value)))?;
+                continue;
+            }
+
+            if name.eq_ignore_ascii_case(Self::DEAD_ZONE_NAME) {
+                config.gamepad_dead_zone = value.parse::<f32>().ok()
+                    .filter(|dead_zone| (0.0..=1.0).contains(dead_zone))
+                    .ok_or_else(|| parse_error(format!("Invalid gamepad dead zone {}, expected 0.0 to 1.0", value)))?;
+                continue;
+            }
+
+            let gamepad_button = name.get(..Self::GAMEPAD_PREFIX.len())
+                .filter(|prefix| prefix.eq_ignore_ascii_case(Self::GAMEPAD_PREFIX))
+                .map(|_| &name[Self::GAMEPAD_PREFIX.len()..]);
+
+            if let Some(gamepad_button) = gamepad_button {
+                let button = JoypadButton::from_name(gamepad_button)
+                    .ok_or_else(|| parse_error(format!("Unknown button {}", gamepad_button)))?;
+
+                let inputs = Self::split_list(value)
+                    .map(|input| GamepadInput::from_name(input).ok_or_else(|| parse_error(format!("Unknown gamepad input {}", input))))
+                    .collect::<Result<Vec<GamepadInput>, InputError>>()?;
+
+                config.bind_gamepad(button, &inputs);
+                continue;
+            }
+
+            let button = JoypadButton::from_name(name)
+                .ok_or_else(|| parse_error(format!("Unknown button {}", name)))?;
+
+            let keys = Self::split_list(value)
+                .map(|name| match Self::parse_key(name) {
+                    Some(key) if App::HOTKEYS.contains(&key) => Err(parse_error(format!("{:?} is used by a hotkey and can't be bound", key))),
+                    Some(key) => Ok(key),
+                    None => Err(parse_error(format!("Unknown key {}", name)))
+                })
+                .collect::<Result<Vec<Key>, InputError>>()?;
+
+            config.bind(button, &keys);
+        }
+
+        Ok(config)
+    }
+
+    /**
+        Replaces the button's keys. A key can only press one button, so it is taken from any other.
+    */
+    pub fn bind(&mut self, button: JoypadButton, keys: &[Key]) {
+        self.bindings.retain(|(bound_key, bound_button)| *bound_button != button && !keys.contains(bound_key));
+        self.bindings.extend(keys.iter().map(|key| (*key, button)));
+    }
+
+    pub fn bind_gamepad(&mut self, button: JoypadButton, inputs: &[GamepadInput]) {
+        self.gamepad_bindings.retain(|(bound_input, bound_button)| *bound_button != button && !inputs.contains(bound_input));
+        self.gamepad_bindings.extend(inputs.iter().map(|input| (*input, button)));
+    }
+
+    pub fn get_button(&self, key: Key) -> Option<JoypadButton> {
+        self.bindings.iter()
+            .find(|(bound_key, _)| *bound_key == key)
+            .map(|(_, button)| *button)
+    }
+
+    /**
+        Every button held on the gamepad, in the order they are bound
+    */
+    pub fn get_gamepad_buttons(&self, state: &GamepadState) -> Vec<JoypadButton> {
+        self.gamepad_bindings.iter()
+            .filter(|(input, _)| input.is_pressed(state, self.gamepad_dead_zone))
+            .map(|(_, button)| *button)
+            .collect()
+    }
+
+    pub fn get_opposing_direction_policy(&self) -> OpposingDirectionPolicy {
+        self.opposing_direction_policy
+    }
+
+    fn split_list(value: &str) -> impl Iterator<Item = &str> {
+        value.split(',')
+            .map(|item| item.trim())
+            .filter(|item| !item.is_empty())
+    }
+
+    fn parse_key(name: &str) -> Option<Key> {
+        Self::KEYS.iter().copied().find(|key| format!("{:?}", key).eq_ignore_ascii_case(name))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use glfw::ffi::GLFWgamepadstate;
+    use super::*;
+
+    #[test]
+    fn defaults_to_wasd() {
+        let config = InputConfig::new();
+
+        assert_eq!(Some(JoypadButton::Up), config.get_button(Key::W));
+        assert_eq!(Some(JoypadButton::Start), config.get_button(Key::Q));
+        assert_eq!(None, config.get_button(Key::Up));
+        assert_eq!(OpposingDirectionPolicy::Allow, config.get_opposing_direction_policy());
+    }
+
+    #[test]
+    fn rebinds_buttons_from_config() {
+        let config = InputConfig::parse("
+            # arrow keys instead of WASD
+            up = Up, Kp8
+            down = down
+            a = Z   # lower case names work too
+            opposing-directions = last-wins
+        ").unwrap();
+
+        assert_eq!(Some(JoypadButton::Up), config.get_button(Key::Up));
+        assert_eq!(Some(JoypadButton::Up), config.get_button(Key::Kp8));
+        assert_eq!(Some(JoypadButton::Down), config.get_button(Key::Down));
+        assert_eq!(None, config.get_button(Key::S));
+        assert_eq!(Some(JoypadButton::A), config.get_button(Key::Z));
+        assert_eq!(None, config.get_button(Key::W));
+        assert_eq!(None, config.get_button(Key::Space));
+        assert_eq!(Some(JoypadButton::Left), config.get_button(Key::A));
+        assert_eq!(OpposingDirectionPolicy::LastWins, config.get_opposing_direction_policy());
+    }
+
+    #[test]
+    fn rebinds_gamepad_buttons_from_config() {
+        let config = InputConfig::parse("
+            gamepad-a = A
+            gamepad-up = DpadUp, RightStickUp
+            gamepad-dead-zone = 0.25
+        ").unwrap();
+
+        let mut state = GLFWgamepadstate { buttons: [0; 15], axes: [0.0; 6] };
+        state.buttons[GamepadButton::ButtonA as usize] = 1;
+        state.buttons[GamepadButton::ButtonStart as usize] = 1;
+        state.axes[GamepadAxis::AxisRightY as usize] = -0.3;
+        state.axes[GamepadAxis::AxisLeftY as usize] = -0.3;
+
+        assert_eq!(
+            vec![JoypadButton::Start, JoypadButton::A, JoypadButton::Up],
+            config.get_gamepad_buttons(&GamepadState::from(state))
+        );
+    }
+
+    #[test]
+    fn key_moves_to_latest_button() {
+        let config = InputConfig::parse("start = W").unwrap();
+
+        assert_eq!(Some(JoypadButton::Start), config.get_button(Key::W));
+        assert_eq!(None, config.get_button(Key::Q));
+    }
+
+    #[test]
+    fn reports_line_of_error() {
+        for text in ["up = W\njump = Space", "up = NotAKey", "up W", "opposing-directions = sometimes", "gamepad-up = Paddle", "gamepad-dead-zone = 2", "a = F5", "select = Kp1, r"] {
+            let result = InputConfig::parse(text);
+
+            assert!(matches!(result, Err(InputError::ParseError { .. })), "{}", text);
+        }
+
+        assert!(matches!(InputConfig::parse("up = W\njump = Space"), Err(InputError::ParseError { line: 2, .. })));
+    }
+}
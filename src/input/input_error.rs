@@ -0,0 +1,9 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum InputError {
+    #[error("Could not read input config: {error}")]
+    ReadError { error: std::io::Error },
+    #[error("Input config line {line}: {message}")]
+    ParseError { line: usize, message: String },
+}
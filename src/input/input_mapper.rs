@@ -0,0 +1,199 @@
+use glfw::{Action, GamepadState, Key};
+use crate::input::input_config::InputConfig;
+use crate::input::joypad_button::JoypadButton;
+use crate::input::opposing_direction_policy::OpposingDirectionPolicy;
+
+/**
+    Turns key events and gamepad states into joypad buttons using an InputConfig, applying its opposing direction policy.
+    It only tracks what is held, the caller decides when the buttons reach the JoypadIO.
+*/
+pub struct InputMapper {
+    config: InputConfig,
+    held_keys: Vec<Key>,
+    held_gamepad_buttons: Vec<JoypadButton>,
+    last_pressed: Vec<JoypadButton>, //most recently pressed direction first
+}
+
+impl InputMapper {
+
+    pub fn new(config: InputConfig) -> Self {
+        Self {
+            config,
+            held_keys: vec![],
+            held_gamepad_buttons: vec![],
+            last_pressed: vec![],
+        }
+    }
+
+    pub fn is_bound(&self, key: Key) -> bool {
+        self.config.get_button(key).is_some()
+    }
+
+    /**
+        Returns false if the key isn't bound to a button
+    */
+    pub fn handle_key(&mut self, key: Key, action: Action) -> bool {
+        let button = match self.config.get_button(key) {
+            Some(button) => button,
+            None => return false
+        };
+
+        match action {
+            Action::Press | Action::Repeat => {
+                if !self.held_keys.contains(&key) {
+                    self.held_keys.push(key);
+                }
+                if action == Action::Press {
+                    self.set_last_pressed(button);
+                }
+            }
+            Action::Release => self.held_keys.retain(|held_key| *held_key != key),
+        }
+
+        true
+    }
+
+    /**
+        Takes the state of every connected gamepad
+    */
+    pub fn handle_gamepads(&mut self, states: &[GamepadState]) {
+        let mut held_buttons = vec![];
+        for button in states.iter().flat_map(|state| self.config.get_gamepad_buttons(state)) {
+            if !held_buttons.contains(&button) {
+                held_buttons.push(button);
+            }
+        }
+
+        for button in &held_buttons {
+            if !self.held_gamepad_buttons.contains(button) {
+                self.set_last_pressed(*button);
+            }
+        }
+        self.held_gamepad_buttons = held_buttons;
+    }
+
+    /**
+        Whether the game should see the button as pressed, after the opposing direction policy
+    */
+    pub fn is_pressed(&self, button: JoypadButton) -> bool {
+        if !self.is_held(button) {
+            return false;
+        }
+
+        match button.get_opposite() {
+            Some(opposite) if self.is_held(opposite) => match self.config.get_opposing_direction_policy() {
+                OpposingDirectionPolicy::Allow => true,
+                OpposingDirectionPolicy::LastWins => self.last_pressed.iter()
+                    .find(|pressed| **pressed == button || **pressed == opposite)
+                    .is_some_and(|pressed| *pressed == button),
+                OpposingDirectionPolicy::Neutral => false,
+            },
+            _ => true
+        }
+    }
+
+    fn is_held(&self, button: JoypadButton) -> bool {
+        self.held_gamepad_buttons.contains(&button) ||
+            self.held_keys.iter().any(|key| self.config.get_button(*key) == Some(button))
+    }
+
+    fn set_last_pressed(&mut self, button: JoypadButton) {
+        if button.get_opposite().is_some() {
+            self.last_pressed.retain(|pressed| *pressed != button);
+            self.last_pressed.insert(0, button);
+        }
+    }
+
+    /**
+        Every pressed button as one byte for JoypadIO::set_buttons
+    */
+    pub fn get_buttons(&self) -> u8 {
+        JoypadButton::ALL.iter()
+            .filter(|button| self.is_pressed(**button))
+            .fold(0, |buttons, button| buttons | button.get_mask())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use glfw::ffi::GLFWgamepadstate;
+    use glfw::GamepadButton;
+    use super::*;
+
+    fn hold_left_then_right(policy: OpposingDirectionPolicy) -> InputMapper {
+        let config = InputConfig::parse(&format!("opposing-directions = {}", policy.get_name())).unwrap();
+
+        let mut mapper = InputMapper::new(config);
+
+        mapper.handle_key(Key::A, Action::Press);
+        mapper.handle_key(Key::D, Action::Press);
+
+        mapper
+    }
+
+    #[test]
+    fn sets_bound_buttons() {
+        let mut mapper = InputMapper::new(InputConfig::new());
+
+        assert!(mapper.handle_key(Key::Space, Action::Press));
+        assert!(!mapper.handle_key(Key::Z, Action::Press));
+        assert_eq!(0b0001_0000, mapper.get_buttons());
+
+        mapper.handle_key(Key::Space, Action::Release);
+        assert_eq!(0, mapper.get_buttons());
+    }
+
+    #[test]
+    fn allow_presses_both_directions() {
+        let mapper = hold_left_then_right(OpposingDirectionPolicy::Allow);
+
+        assert_eq!(0b0000_0011, mapper.get_buttons());
+    }
+
+    #[test]
+    fn last_wins_presses_latest_direction() {
+        let mut mapper = hold_left_then_right(OpposingDirectionPolicy::LastWins);
+
+        assert_eq!(0b0000_0001, mapper.get_buttons());
+
+        mapper.handle_key(Key::D, Action::Release);
+        assert_eq!(0b0000_0010, mapper.get_buttons());
+    }
+
+    #[test]
+    fn neutral_presses_neither_direction() {
+        let mut mapper = hold_left_then_right(OpposingDirectionPolicy::Neutral);
+
+        assert_eq!(0, mapper.get_buttons());
+
+        mapper.handle_key(Key::A, Action::Release);
+        assert_eq!(0b0000_0001, mapper.get_buttons());
+    }
+
+    #[test]
+    fn combines_keyboard_and_gamepad() {
+        let mut mapper = InputMapper::new(InputConfig::new());
+        mapper.handle_key(Key::Space, Action::Press);
+
+        let mut state = GLFWgamepadstate { buttons: [0; 15], axes: [0.0; 6] };
+        state.buttons[GamepadButton::ButtonStart as usize] = 1;
+
+        mapper.handle_gamepads(&[GamepadState::from(state)]);
+        assert_eq!(0b1001_0000, mapper.get_buttons());
+
+        mapper.handle_gamepads(&[]);
+        assert_eq!(0b0001_0000, mapper.get_buttons());
+    }
+
+    #[test]
+    fn last_wins_between_keyboard_and_gamepad() {
+        let mut mapper = hold_left_then_right(OpposingDirectionPolicy::LastWins);
+
+        let mut state = GLFWgamepadstate { buttons: [0; 15], axes: [0.0; 6] };
+        state.buttons[GamepadButton::ButtonDpadLeft as usize] = 1;
+
+        mapper.handle_gamepads(&[GamepadState::from(state)]);
+        assert_eq!(0b0000_0010, mapper.get_buttons());
+    }
+}
@@ -0,0 +1,68 @@
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JoypadButton {
+    Up,
+    Down,
+    Left,
+    Right,
+    A,
+    B,
+    Select,
+    Start,
+}
+
+impl JoypadButton {
+
+    pub const ALL: [JoypadButton; 8] = [
+        JoypadButton::Up, JoypadButton::Down, JoypadButton::Left, JoypadButton::Right,
+        JoypadButton::A, JoypadButton::B, JoypadButton::Select, JoypadButton::Start,
+    ];
+
+    /**
+        The name used in the input config file
+    */
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            JoypadButton::Up => "up",
+            JoypadButton::Down => "down",
+            JoypadButton::Left => "left",
+            JoypadButton::Right => "right",
+            JoypadButton::A => "a",
+            JoypadButton::B => "b",
+            JoypadButton::Select => "select",
+            JoypadButton::Start => "start",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<JoypadButton> {
+        Self::ALL.into_iter().find(|button| button.get_name().eq_ignore_ascii_case(name))
+    }
+
+    /**
+        The direction that can't be held at the same time on a real D-pad
+    */
+    pub fn get_opposite(&self) -> Option<JoypadButton> {
+        match self {
+            JoypadButton::Up => Some(JoypadButton::Down),
+            JoypadButton::Down => Some(JoypadButton::Up),
+            JoypadButton::Left => Some(JoypadButton::Right),
+            JoypadButton::Right => Some(JoypadButton::Left),
+            _ => None
+        }
+    }
+
+    /**
+        The button's bit in JoypadIO::set_buttons
+    */
+    pub fn get_mask(&self) -> u8 {
+        match self {
+            JoypadButton::Right => 0x01,
+            JoypadButton::Left => 0x02,
+            JoypadButton::Up => 0x04,
+            JoypadButton::Down => 0x08,
+            JoypadButton::A => 0x10,
+            JoypadButton::B => 0x20,
+            JoypadButton::Select => 0x40,
+            JoypadButton::Start => 0x80,
+        }
+    }
+}
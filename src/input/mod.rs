@@ -0,0 +1,11 @@
+mod gamepad_input;
+mod gamepad_poller;
+mod input_config;
+mod input_error;
+mod input_mapper;
+mod joypad_button;
+mod opposing_direction_policy;
+
+pub use gamepad_poller::GamepadPoller;
+pub use input_config::InputConfig;
+pub use input_mapper::InputMapper;
@@ -0,0 +1,25 @@
+/**
+    What to do when both directions of an axis are held, which can't happen on a real D-pad and confuses some games
+*/
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OpposingDirectionPolicy {
+    Allow,      //both are pressed
+    LastWins,   //only the one pressed most recently
+    Neutral,    //neither
+}
+
+impl OpposingDirectionPolicy {
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            OpposingDirectionPolicy::Allow => "allow",
+            OpposingDirectionPolicy::LastWins => "last-wins",
+            OpposingDirectionPolicy::Neutral => "neutral",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<OpposingDirectionPolicy> {
+        [OpposingDirectionPolicy::Allow, OpposingDirectionPolicy::LastWins, OpposingDirectionPolicy::Neutral].into_iter()
+            .find(|policy| policy.get_name().eq_ignore_ascii_case(name))
+    }
+}
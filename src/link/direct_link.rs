@@ -0,0 +1,191 @@
+use std::sync::Arc;
+use parking_lot::Mutex;
+use crate::memory::io_map::SerialPeer;
+
+struct DirectLink {
+    transfers: [Option<u8>; 2], //byte sent by each end that the other hasn't picked up yet
+    replies: [Option<u8>; 2],   //byte waiting to be shifted into each end
+}
+
+/**
+    One end of a link cable between two Game Boys in the same process. The other end picks up a transfer the next time
+    it is polled, and once the 8 bits have been clocked in it receives the byte and replies with its SB. The end driving
+    the clock waits for that reply, so as long as both are stepped in a fixed order the result is deterministic.
+*/
+pub struct DirectLinkPeer {
+    link: Arc<Mutex<DirectLink>>,
+    side: usize,
+    receiving: Option<(u8, u16)>, //byte from the other end's internal clock and how many clocks it has been shifting
+}
+
+impl DirectLinkPeer {
+
+    const TRANSFER_CLOCKS: u16 = 1024; //8 bits at 8192Hz
+
+    pub fn new_pair() -> (Self, Self) {
+        let link = Arc::new(Mutex::new(DirectLink {
+            transfers: [None; 2],
+            replies: [None; 2],
+        }));
+
+        (Self { link: link.clone(), side: 0, receiving: None }, Self { link, side: 1, receiving: None })
+    }
+}
+
+impl SerialPeer for DirectLinkPeer {
+    fn start_transfer(&mut self, outgoing: u8) {
+        self.link.lock().transfers[self.side] = Some(outgoing);
+    }
+
+    fn poll(&mut self, data: u8) -> Option<u8> {
+        let mut link = self.link.lock();
+        let other_side = 1 - self.side;
+
+        match link.replies[self.side].take() {
+            Some(reply) => return Some(reply),
+            None => {}
+        }
+
+        match link.transfers[other_side].take() {
+            Some(incoming) => {
+                if link.transfers[self.side].take().is_some() { //if both ends started a transfer they swap bytes once
+                    link.replies[other_side] = Some(data);
+                    return Some(incoming);
+                }
+
+                self.receiving = Some((incoming, 0));
+            }
+            None => {}
+        }
+
+        match &mut self.receiving {
+            Some((incoming, clocks)) => {
+                *clocks += 1;
+
+                if *clocks < Self::TRANSFER_CLOCKS {
+                    return None;
+                }
+
+                let incoming = *incoming;
+                self.receiving = None;
+                link.replies[other_side] = Some(data);
+
+                Some(incoming)
+            }
+            None => None
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::cpu::Interrupt;
+    use crate::memory::io_map::IOMap;
+    use crate::memory::MemoryTrait;
+    use super::*;
+
+    fn get_linked_io_maps() -> (IOMap, IOMap) {
+        let (peer_1, peer_2) = DirectLinkPeer::new_pair();
+        let mut io_map_1 = IOMap::new();
+        let mut io_map_2 = IOMap::new();
+
+        io_map_1.set_serial_peer(Some(Box::new(peer_1)));
+        io_map_2.set_serial_peer(Some(Box::new(peer_2)));
+
+        (io_map_1, io_map_2)
+    }
+
+    fn clock_both(io_map_1: &mut IOMap, io_map_2: &mut IOMap, clocks: u32) -> (Vec<u32>, Vec<u32>) {
+        let mut interrupts = (vec![], vec![]);
+
+        for clock in 0..clocks {
+            if io_map_1.clock().contains(&Interrupt::Serial) { interrupts.0.push(clock); }
+            if io_map_2.clock().contains(&Interrupt::Serial) { interrupts.1.push(clock); }
+        }
+
+        interrupts
+    }
+
+    #[test]
+    fn exchanges_bytes_between_internal_and_external_clock() {
+        let (mut io_map_1, mut io_map_2) = get_linked_io_maps();
+
+        io_map_1.set(0xFF01, 0x12);
+        io_map_2.set(0xFF01, 0x34);
+        io_map_2.set(0xFF02, 0x80);
+        io_map_1.set(0xFF02, 0x81);
+
+        let (interrupts_1, interrupts_2) = clock_both(&mut io_map_1, &mut io_map_2, 1025);
+
+        assert_eq!(0x34, io_map_1.get(0xFF01));
+        assert_eq!(0x12, io_map_2.get(0xFF01));
+        assert_eq!(vec![1024], interrupts_1); //the reply is picked up the clock after the external end finishes
+        assert_eq!(vec![1023], interrupts_2);
+    }
+
+    #[test]
+    fn works_in_both_directions() {
+        let (mut io_map_1, mut io_map_2) = get_linked_io_maps();
+
+        io_map_1.set(0xFF01, 0x12);
+        io_map_1.set(0xFF02, 0x80);
+        io_map_2.set(0xFF01, 0x34);
+        io_map_2.set(0xFF02, 0x81);
+
+        let (interrupts_1, interrupts_2) = clock_both(&mut io_map_1, &mut io_map_2, 1024);
+
+        assert_eq!(0x34, io_map_1.get(0xFF01));
+        assert_eq!(0x12, io_map_2.get(0xFF01));
+        assert_eq!(vec![1023], interrupts_1);
+        assert_eq!(vec![1023], interrupts_2);
+    }
+
+    #[test]
+    fn external_clock_end_stays_busy_until_transfer_time() {
+        let (mut io_map_1, mut io_map_2) = get_linked_io_maps();
+
+        io_map_2.set(0xFF02, 0x80);
+        io_map_1.set(0xFF02, 0x81);
+
+        clock_both(&mut io_map_1, &mut io_map_2, 1023);
+        assert_eq!(0xFE, io_map_2.get(0xFF02));
+
+        clock_both(&mut io_map_1, &mut io_map_2, 1);
+        assert_eq!(0x7E, io_map_2.get(0xFF02));
+    }
+
+    #[test]
+    fn replies_with_data_written_during_transfer() {
+        let (mut io_map_1, mut io_map_2) = get_linked_io_maps();
+
+        io_map_2.set(0xFF02, 0x80);
+        io_map_1.set(0xFF02, 0x81);
+        clock_both(&mut io_map_1, &mut io_map_2, 100);
+        io_map_2.set(0xFF01, 0x34);
+        clock_both(&mut io_map_1, &mut io_map_2, 1000);
+
+        assert_eq!(0x34, io_map_1.get(0xFF01));
+    }
+
+    #[test]
+    fn both_ends_on_internal_clock_swap_once() {
+        let (mut peer_1, mut peer_2) = DirectLinkPeer::new_pair();
+
+        peer_1.start_transfer(0x12);
+        peer_2.start_transfer(0x34);
+
+        assert_eq!(Some(0x34), peer_1.poll(0x12));
+        assert_eq!(Some(0x12), peer_2.poll(0x34));
+        assert_eq!(None, peer_1.poll(0x34));
+        assert_eq!(None, peer_2.poll(0x12));
+    }
+
+    #[test]
+    fn nothing_is_received_without_transfer() {
+        let (mut peer_1, mut peer_2) = DirectLinkPeer::new_pair();
+
+        assert_eq!(None, peer_1.poll(0x12));
+        assert_eq!(None, peer_2.poll(0x34));
+    }
+}
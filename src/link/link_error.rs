@@ -0,0 +1,10 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum LinkError {
+    #[error("Could not connect link cable to {address}: {error}")]
+    ConnectionError { address: String, error: std::io::Error },
+    #[cfg(not(unix))]
+    #[error("Link cable address {address} is not supported on this platform")]
+    UnsupportedAddress { address: String },
+}
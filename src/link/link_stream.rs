@@ -0,0 +1,155 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::time::Duration;
+use crate::link::link_error::LinkError;
+
+/**
+    Connection to another emulator instance. Addresses starting with "unix:" are Unix domain socket paths,
+    anything else is a TCP address such as 127.0.0.1:5000.
+*/
+pub enum LinkStream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Read for LinkStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            LinkStream::Tcp(stream) => stream.read(buf),
+            #[cfg(unix)]
+            LinkStream::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for LinkStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            LinkStream::Tcp(stream) => stream.write(buf),
+            #[cfg(unix)]
+            LinkStream::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            LinkStream::Tcp(stream) => stream.flush(),
+            #[cfg(unix)]
+            LinkStream::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+impl LinkStream {
+
+    const UNIX_PREFIX: &'static str = "unix:";
+    const TIMEOUT: Duration = Duration::from_secs(10); //the other side is treated as unplugged after this long without a reply
+
+    /**
+        Waits for the other instance to join
+    */
+    pub fn host(address: &str) -> Result<Self, LinkError> {
+        let connection_error = |error| LinkError::ConnectionError { address: address.to_string(), error };
+
+        match address.strip_prefix(Self::UNIX_PREFIX) {
+            Some(path) => Self::host_unix(address, path),
+            None => {
+                let listener = TcpListener::bind(address).map_err(connection_error)?;
+                let (stream, _) = listener.accept().map_err(connection_error)?;
+
+                Self::from_tcp(stream).map_err(connection_error)
+            }
+        }
+    }
+
+    pub fn join(address: &str) -> Result<Self, LinkError> {
+        let connection_error = |error| LinkError::ConnectionError { address: address.to_string(), error };
+
+        match address.strip_prefix(Self::UNIX_PREFIX) {
+            Some(path) => Self::join_unix(address, path),
+            None => Self::from_tcp(TcpStream::connect(address).map_err(connection_error)?).map_err(connection_error)
+        }
+    }
+
+    fn from_tcp(stream: TcpStream) -> std::io::Result<Self> {
+        stream.set_nodelay(true)?; //messages are tiny and sent thousands of times a second
+        stream.set_read_timeout(Some(Self::TIMEOUT))?;
+
+        Ok(LinkStream::Tcp(stream))
+    }
+
+    #[cfg(unix)]
+    fn host_unix(address: &str, path: &str) -> Result<Self, LinkError> {
+        let connection_error = |error| LinkError::ConnectionError { address: address.to_string(), error };
+
+        let listener = UnixListener::bind(path).map_err(connection_error)?;
+        let (stream, _) = listener.accept().map_err(connection_error)?;
+        let _ = std::fs::remove_file(path); //the socket file is only needed until the other side has connected
+
+        stream.set_read_timeout(Some(Self::TIMEOUT)).map_err(connection_error)?;
+        Ok(LinkStream::Unix(stream))
+    }
+
+    #[cfg(unix)]
+    fn join_unix(address: &str, path: &str) -> Result<Self, LinkError> {
+        let connection_error = |error| LinkError::ConnectionError { address: address.to_string(), error };
+
+        let stream = UnixStream::connect(path).map_err(connection_error)?;
+
+        stream.set_read_timeout(Some(Self::TIMEOUT)).map_err(connection_error)?;
+        Ok(LinkStream::Unix(stream))
+    }
+
+    #[cfg(not(unix))]
+    fn host_unix(address: &str, _path: &str) -> Result<Self, LinkError> {
+        Err(LinkError::UnsupportedAddress { address: address.to_string() })
+    }
+
+    #[cfg(not(unix))]
+    fn join_unix(address: &str, _path: &str) -> Result<Self, LinkError> {
+        Err(LinkError::UnsupportedAddress { address: address.to_string() })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use super::*;
+
+    #[test]
+    fn join_fails_without_host() {
+        let result = LinkStream::join("unix:/nonexistent/gameboy_emulator.sock");
+
+        assert!(matches!(result, Err(LinkError::ConnectionError { .. })));
+    }
+
+    #[test]
+    fn tcp_host_and_join_are_connected() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        drop(listener);
+
+        let host_address = address.clone();
+        let host = thread::spawn(move || {
+            let mut stream = LinkStream::host(&host_address).unwrap();
+            let mut buffer = [0u8; 1];
+
+            stream.read_exact(&mut buffer).unwrap();
+            buffer[0]
+        });
+
+        let mut stream = loop { //the host thread may not be listening yet
+            match LinkStream::join(&address) {
+                Ok(stream) => break stream,
+                Err(_) => thread::sleep(Duration::from_millis(10))
+            }
+        };
+        stream.write_all(&[0x42]).unwrap();
+
+        assert_eq!(0x42, host.join().unwrap());
+    }
+}
@@ -0,0 +1,212 @@
+use std::io::{Read, Write};
+use crate::memory::io_map::SerialPeer;
+
+/**
+    Link cable peer that keeps both Game Boys in lock-step. Every QUANTUM_CLOCKS M-cycles each side sends a message with
+    its SB and whether it started a transfer, then waits for the other side's message from the previous quantum.
+    Everything is decided by those messages, so the result only depends on emulated time and not on the connection.
+
+    A transfer started in quantum n is received by the other side at the end of its quantum n + 1 and answered with
+    the SB it sent for that quantum, which arrives back at the end of quantum n + 2, well before the 1024 M-cycles a transfer takes.
+*/
+pub struct LockStepPeer<S: Read + Write + Send> {
+    stream: Option<S>, //None once the other side has gone away, which behaves like an unplugged cable
+
+    clocks: u16,
+    quantum: u64,
+
+    transfer_started: bool,
+    awaiting_reply: Option<u64>, //quantum the transfer was sent in
+}
+
+impl<S: Read + Write + Send> LockStepPeer<S> {
+
+    pub const QUANTUM_CLOCKS: u16 = 256;
+    const TRANSFER_FLAG: u8 = 0x01;
+
+    /**
+        Sends the message for the quantum before the first, so each side always has one message in hand
+    */
+    pub fn new(mut stream: S) -> std::io::Result<Self> {
+        stream.write_all(&[0x00, 0xFF])?;
+        stream.flush()?;
+
+        Ok(Self {
+            stream: Some(stream),
+
+            clocks: 0,
+            quantum: 0,
+
+            transfer_started: false,
+            awaiting_reply: None,
+        })
+    }
+
+    fn exchange(&mut self, message: [u8; 2]) -> Option<[u8; 2]> {
+        let stream = self.stream.as_mut()?;
+        let mut received = [0u8; 2];
+
+        let result = stream.write_all(&message)
+            .and_then(|_| stream.flush())
+            .and_then(|_| stream.read_exact(&mut received));
+
+        match result {
+            Ok(_) => Some(received),
+            Err(error) => {
+                eprintln!("Link cable disconnected: {}", error);
+                self.stream = None;
+                None
+            }
+        }
+    }
+}
+
+impl<S: Read + Write + Send> SerialPeer for LockStepPeer<S> {
+    fn start_transfer(&mut self, _outgoing: u8) {
+        self.transfer_started = true; //SB doesn't change during the transfer, so it is sent with the next message
+    }
+
+    fn poll(&mut self, data: u8) -> Option<u8> {
+        if self.stream.is_none() {
+            let waiting = self.transfer_started || self.awaiting_reply.is_some();
+
+            self.transfer_started = false;
+            self.awaiting_reply = None;
+
+            return if waiting { Some(0xFF) } else { None };
+        }
+
+        self.clocks += 1;
+        if self.clocks < Self::QUANTUM_CLOCKS {
+            return None;
+        }
+        self.clocks = 0;
+
+        let quantum = self.quantum;
+        self.quantum += 1;
+
+        let message = [if self.transfer_started { Self::TRANSFER_FLAG } else { 0x00 }, data];
+        if self.transfer_started {
+            self.transfer_started = false;
+            self.awaiting_reply = Some(quantum);
+        }
+
+        let received = match self.exchange(message) {
+            Some(received) => received,
+            None => return self.awaiting_reply.take().map(|_| 0xFF)
+        };
+
+        match self.awaiting_reply {
+            Some(sent_quantum) if quantum >= sent_quantum + 2 => { //received message is from quantum - 1
+                self.awaiting_reply = None;
+                Some(received[1])
+            }
+            Some(_) => None,
+            None if received[0] & Self::TRANSFER_FLAG != 0 => Some(received[1]),
+            None => None
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::net::{TcpListener, TcpStream};
+    use crate::cpu::Interrupt;
+    use crate::memory::io_map::IOMap;
+    use crate::memory::MemoryTrait;
+    use super::*;
+
+    fn get_linked_io_maps() -> (IOMap, IOMap) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let joined = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (hosted, _) = listener.accept().unwrap();
+
+        let mut io_map_1 = IOMap::new();
+        let mut io_map_2 = IOMap::new();
+
+        io_map_1.set_serial_peer(Some(Box::new(LockStepPeer::new(hosted).unwrap())));
+        io_map_2.set_serial_peer(Some(Box::new(LockStepPeer::new(joined).unwrap())));
+
+        (io_map_1, io_map_2)
+    }
+
+    fn clock_both(io_map_1: &mut IOMap, io_map_2: &mut IOMap, clocks: u32) -> (Vec<u32>, Vec<u32>) {
+        let mut interrupts = (vec![], vec![]);
+
+        for clock in 0..clocks {
+            if io_map_1.clock().contains(&Interrupt::Serial) { interrupts.0.push(clock); }
+            if io_map_2.clock().contains(&Interrupt::Serial) { interrupts.1.push(clock); }
+        }
+
+        interrupts
+    }
+
+    #[test]
+    fn exchanges_bytes_between_internal_and_external_clock() {
+        let (mut io_map_1, mut io_map_2) = get_linked_io_maps();
+
+        io_map_1.set(0xFF01, 0x12);
+        io_map_2.set(0xFF01, 0x34);
+        io_map_2.set(0xFF02, 0x80);
+        io_map_1.set(0xFF02, 0x81);
+
+        let (interrupts_1, interrupts_2) = clock_both(&mut io_map_1, &mut io_map_2, 2048);
+
+        assert_eq!(0x34, io_map_1.get(0xFF01));
+        assert_eq!(0x12, io_map_2.get(0xFF01));
+        assert_eq!(vec![1023], interrupts_1);
+        assert_eq!(1, interrupts_2.len());
+    }
+
+    #[test]
+    fn transfer_timing_does_not_depend_on_start_cycle() {
+        for delay in [0, 1, 100, 255, 256, 700] {
+            let (mut io_map_1, mut io_map_2) = get_linked_io_maps();
+
+            io_map_2.set(0xFF01, 0x56);
+            io_map_2.set(0xFF02, 0x80);
+            clock_both(&mut io_map_1, &mut io_map_2, delay);
+            io_map_1.set(0xFF02, 0x81);
+
+            let (interrupts_1, _) = clock_both(&mut io_map_1, &mut io_map_2, 1024);
+
+            assert_eq!(vec![1023], interrupts_1);
+            assert_eq!(0x56, io_map_1.get(0xFF01));
+        }
+    }
+
+    #[test]
+    fn slave_without_transfer_enabled_keeps_data() {
+        let (mut io_map_1, mut io_map_2) = get_linked_io_maps();
+
+        io_map_1.set(0xFF01, 0x12);
+        io_map_2.set(0xFF01, 0x34);
+        io_map_1.set(0xFF02, 0x81);
+
+        let (_, interrupts_2) = clock_both(&mut io_map_1, &mut io_map_2, 2048);
+
+        assert_eq!(0x34, io_map_1.get(0xFF01));
+        assert_eq!(0x34, io_map_2.get(0xFF01));
+        assert!(interrupts_2.is_empty());
+    }
+
+    #[test]
+    fn disconnected_peer_reads_0xff() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let joined = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (hosted, _) = listener.accept().unwrap();
+        drop(joined);
+
+        let mut peer = LockStepPeer::new(hosted).unwrap();
+        peer.start_transfer(0x12);
+
+        let mut reply = None;
+        for _ in 0..LockStepPeer::<TcpStream>::QUANTUM_CLOCKS {
+            reply = reply.or(peer.poll(0x12));
+        }
+
+        assert_eq!(Some(0xFF), reply);
+        assert!(peer.stream.is_none());
+    }
+}
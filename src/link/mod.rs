@@ -0,0 +1,8 @@
+mod link_error;
+mod link_stream;
+mod lock_step_peer;
+mod direct_link;
+
+pub use link_stream::LinkStream;
+pub use lock_step_peer::LockStepPeer;
+pub use direct_link::DirectLinkPeer;
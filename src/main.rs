@@ -1,14 +1,33 @@
 mod app;
+mod audio;
 mod memory;
 mod renderer;
 mod cpu;
+mod headless;
+mod input;
+mod link;
+mod movie;
+mod printer;
+mod rewind;
+mod save_state;
 mod system;
 
 use std::env;
 use dec_gl::GLHandler;
 use crate::app::App;
+use crate::headless::{HeadlessRunner, TestRomHarness};
 
 fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.iter().any(|arg| arg == "--headless") {
+        std::process::exit(HeadlessRunner::run_from_args(&args));
+    }
+
+    if args.iter().any(|arg| arg == "--test-roms") {
+        std::process::exit(TestRomHarness::run_from_args(&args));
+    }
+
     match GLHandler::new("GB Emulator",
                          800,
                          720,
@@ -16,7 +35,7 @@ fn main() {
                          true)
     {
         Ok(gl_handler) => {
-            let mut app = App::new(env::args().collect(), gl_handler.clone());
+            let mut app = App::new(args, gl_handler.clone());
             app.run();
         },
         Err(_e) => {
@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CartridgeError {
+    #[error("Could not open file: {error}")]
+    ReadError { error: std::io::Error },
+    #[error("ROM is too small to contain a header ({length} bytes)")]
+    HeaderTooShort { length: usize },
+    #[error("Unknown ROM size code {value:#04X}")]
+    UnknownRomSize { value: u8 },
+    #[error("Header checksum mismatch: expected {expected:#04X}, calculated {calculated:#04X}")]
+    HeaderChecksumMismatch { expected: u8, calculated: u8 },
+    #[error("Mapper state has {length} bytes, expected {expected}")]
+    InvalidMapperState { expected: usize, length: usize },
+}
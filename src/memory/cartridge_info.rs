@@ -0,0 +1,313 @@
+use crate::memory::cartridge_error::CartridgeError;
+use crate::memory::mbc::MBC2;
+use crate::memory::sram::SRAM;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CGBSupport {
+    None,
+    Supported,
+    Required,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapperType {
+    RomOnly,
+    MBC1,
+    MBC2,
+    MBC3,
+    MBC5,
+    MBC6,
+    MBC7,
+    MMM01,
+    PocketCamera,
+    TAMA5,
+    HuC1,
+    HuC3,
+    Unknown,
+}
+
+/**
+    Decoded form of the cartridge header found at 0x0100-0x014F of bank 0.
+*/
+#[derive(Debug, Clone)]
+pub struct CartridgeInfo {
+    title: String,
+    cartridge_type: u8,
+    ram_size: usize,
+    global_checksum: u16,
+}
+
+impl CartridgeInfo {
+
+    const HEADER_END: usize = 0x0150;
+
+    const TITLE_START: usize = 0x0134;
+    const MANUFACTURER_START: usize = 0x013F;
+    const CGB_FLAG_ADDRESS: usize = 0x0143;
+    const CARTRIDGE_TYPE_ADDRESS: usize = 0x0147;
+    const ROM_SIZE_ADDRESS: usize = 0x0148;
+    const RAM_SIZE_ADDRESS: usize = 0x0149;
+    const HEADER_CHECKSUM_ADDRESS: usize = 0x014D;
+    const GLOBAL_CHECKSUM_ADDRESS: usize = 0x014E;
+
+    /**
+        Decodes the header and checks the header checksum, which the boot ROM refuses to start without.
+        The global checksum is not checked since real hardware ignores it.
+    */
+    pub fn from_rom_data(data: &[u8]) -> Result<Self, CartridgeError> {
+        if data.len() < Self::HEADER_END {
+            return Err(CartridgeError::HeaderTooShort { length: data.len() });
+        }
+
+        let header_checksum = data[Self::HEADER_CHECKSUM_ADDRESS];
+        let calculated_header_checksum = Self::calculate_header_checksum(data);
+
+        if header_checksum != calculated_header_checksum {
+            return Err(CartridgeError::HeaderChecksumMismatch { expected: header_checksum, calculated: calculated_header_checksum });
+        }
+
+        let cgb_support = match data[Self::CGB_FLAG_ADDRESS] {
+            0x80 => CGBSupport::Supported,
+            0xC0 => CGBSupport::Required,
+            _ => CGBSupport::None
+        };
+
+        let title_end = match (cgb_support, Self::has_manufacturer_code(data, cgb_support)) {
+            (_, true) => Self::MANUFACTURER_START,
+            (CGBSupport::None, false) => Self::CGB_FLAG_ADDRESS + 1,
+            (_, false) => Self::CGB_FLAG_ADDRESS,
+        };
+
+        let cartridge_type = data[Self::CARTRIDGE_TYPE_ADDRESS];
+        let ram_size = match cartridge_type {
+            0x05 | 0x06 => MBC2::RAM_SIZE, //MBC2 RAM is inside the controller so the header reports none
+            _ => SRAM::get_size_from_header(data[Self::RAM_SIZE_ADDRESS])
+        };
+
+        Self::check_rom_size(data[Self::ROM_SIZE_ADDRESS])?;
+
+        Ok(Self {
+            title: Self::get_ascii_string(&data[Self::TITLE_START..title_end]),
+            cartridge_type,
+            ram_size,
+            global_checksum: u16::from_be_bytes([data[Self::GLOBAL_CHECKSUM_ADDRESS], data[Self::GLOBAL_CHECKSUM_ADDRESS + 1]]),
+        })
+    }
+
+    pub fn calculate_header_checksum(data: &[u8]) -> u8 {
+        data[Self::TITLE_START..Self::HEADER_CHECKSUM_ADDRESS]
+            .iter()
+            .fold(0u8, |checksum, byte| checksum.wrapping_sub(*byte).wrapping_sub(1))
+    }
+
+    pub fn get_mapper_type(&self) -> MapperType {
+        match self.cartridge_type {
+            0x00 | 0x08 | 0x09 => MapperType::RomOnly,
+            0x01..=0x03 => MapperType::MBC1,
+            0x05 | 0x06 => MapperType::MBC2,
+            0x0B..=0x0D => MapperType::MMM01,
+            0x0F..=0x13 => MapperType::MBC3,
+            0x19..=0x1E => MapperType::MBC5,
+            0x20 => MapperType::MBC6,
+            0x22 => MapperType::MBC7,
+            0xFC => MapperType::PocketCamera,
+            0xFD => MapperType::TAMA5,
+            0xFE => MapperType::HuC3,
+            0xFF => MapperType::HuC1,
+            _ => MapperType::Unknown
+        }
+    }
+
+    pub fn has_battery(&self) -> bool {
+        match self.cartridge_type {
+            0x03 | 0x06 | 0x09 | 0x0D | 0x0F | 0x10 | 0x13 | 0x1B | 0x1E | 0x22 | 0xFF => true,
+            _ => false
+        }
+    }
+
+    pub fn has_rtc(&self) -> bool {
+        match self.cartridge_type {
+            0x0F | 0x10 | 0xFE => true,
+            _ => false
+        }
+    }
+
+    pub fn has_rumble(&self) -> bool {
+        match self.cartridge_type {
+            0x1C..=0x1E | 0x22 => true,
+            _ => false
+        }
+    }
+
+    pub fn get_title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn get_ram_size(&self) -> usize {
+        self.ram_size
+    }
+
+    /**
+        Not verified, but still identifies the ROM
+    */
+    pub fn get_global_checksum(&self) -> u16 {
+        self.global_checksum
+    }
+
+    fn check_rom_size(rom_size: u8) -> Result<(), CartridgeError> {
+        match rom_size {
+            0x00..=0x08 | 0x52..=0x54 => Ok(()),
+            value => Err(CartridgeError::UnknownRomSize { value })
+        }
+    }
+
+    /**
+        Newer cartridges shortened the title to hold a four character manufacturer code.
+        There is no flag for this, so it is only assumed when the CGB flag is set and the bytes are uppercase ASCII.
+    */
+    fn has_manufacturer_code(data: &[u8], cgb_support: CGBSupport) -> bool {
+        let code = &data[Self::MANUFACTURER_START..Self::CGB_FLAG_ADDRESS];
+
+        cgb_support != CGBSupport::None && code.iter().all(|byte| byte.is_ascii_uppercase() || byte.is_ascii_digit())
+    }
+
+    fn get_ascii_string(data: &[u8]) -> String {
+        data.iter()
+            .take_while(|byte| **byte != 0x00)
+            .map(|byte| if byte.is_ascii_graphic() || *byte == b' ' { *byte as char } else { '?' })
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+}
+
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    pub fn get_rom_data_with_header(cartridge_type: u8, rom_size: u8, ram_size: u8) -> Vec<u8> {
+        let mut data = vec![0; 0x8000 << rom_size];
+
+        data[CartridgeInfo::TITLE_START..CartridgeInfo::TITLE_START + 8].copy_from_slice(b"TESTGAME");
+        data[CartridgeInfo::CARTRIDGE_TYPE_ADDRESS] = cartridge_type;
+        data[CartridgeInfo::ROM_SIZE_ADDRESS] = rom_size;
+        data[CartridgeInfo::RAM_SIZE_ADDRESS] = ram_size;
+
+        update_checksums(&mut data);
+
+        data
+    }
+
+    pub fn update_checksums(data: &mut Vec<u8>) {
+        data[CartridgeInfo::HEADER_CHECKSUM_ADDRESS] = CartridgeInfo::calculate_header_checksum(data);
+    }
+
+    #[test]
+    fn decodes_title() {
+        let data = get_rom_data_with_header(0x00, 0x00, 0x00);
+
+        let info = CartridgeInfo::from_rom_data(&data).unwrap();
+
+        assert_eq!("TESTGAME", info.get_title());
+    }
+
+    #[test]
+    fn decodes_sizes_and_type() {
+        let data = get_rom_data_with_header(0x13, 0x02, 0x03);
+
+        let info = CartridgeInfo::from_rom_data(&data).unwrap();
+
+        assert_eq!(0x8000, info.get_ram_size());
+        assert_eq!(MapperType::MBC3, info.get_mapper_type());
+        assert!(info.has_battery());
+        assert!(!info.has_rtc());
+    }
+
+    #[test]
+    fn mbc2_has_built_in_ram() {
+        let data = get_rom_data_with_header(0x06, 0x00, 0x00);
+
+        let info = CartridgeInfo::from_rom_data(&data).unwrap();
+
+        assert_eq!(0x200, info.get_ram_size());
+        assert_eq!(MapperType::MBC2, info.get_mapper_type());
+        assert!(info.has_battery());
+    }
+
+    #[test]
+    fn decodes_cgb_title_and_manufacturer_code() {
+        let mut data = get_rom_data_with_header(0x00, 0x00, 0x00);
+        data[0x0134..0x0143].copy_from_slice(b"POKEMON_SLVAAXE");
+        data[0x0143] = 0x80;
+        update_checksums(&mut data);
+
+        let info = CartridgeInfo::from_rom_data(&data).unwrap();
+
+        assert_eq!("POKEMON_SLV", info.get_title());
+    }
+
+    #[test]
+    fn cgb_only_title_without_manufacturer_code() {
+        let mut data = get_rom_data_with_header(0x00, 0x00, 0x00);
+        data[0x0134..0x0143].copy_from_slice(b"LONG CGB title1");
+        data[0x0143] = 0xC0;
+        update_checksums(&mut data);
+
+        let info = CartridgeInfo::from_rom_data(&data).unwrap();
+
+        assert_eq!("LONG CGB title1", info.get_title());
+    }
+
+    #[test]
+    fn short_data_is_rejected() {
+        let data = vec![0; 0x0100];
+
+        match CartridgeInfo::from_rom_data(&data) {
+            Err(CartridgeError::HeaderTooShort { length }) => assert_eq!(0x0100, length),
+            _ => panic!("Expected HeaderTooShort")
+        }
+    }
+
+    #[test]
+    fn bad_header_checksum_is_rejected() {
+        let mut data = get_rom_data_with_header(0x00, 0x00, 0x00);
+        data[0x014D] = data[0x014D].wrapping_add(1);
+
+        match CartridgeInfo::from_rom_data(&data) {
+            Err(CartridgeError::HeaderChecksumMismatch { expected, calculated }) => assert_eq!(expected, calculated.wrapping_add(1)),
+            _ => panic!("Expected HeaderChecksumMismatch")
+        }
+    }
+
+    #[test]
+    fn unknown_rom_size_is_rejected() {
+        let mut data = get_rom_data_with_header(0x00, 0x00, 0x00);
+        data[0x0148] = 0x20;
+        update_checksums(&mut data);
+
+        match CartridgeInfo::from_rom_data(&data) {
+            Err(CartridgeError::UnknownRomSize { value }) => assert_eq!(0x20, value),
+            _ => panic!("Expected UnknownRomSize")
+        }
+    }
+
+    #[test]
+    fn mbc3_timer_cartridge_has_rtc() {
+        let data = get_rom_data_with_header(0x10, 0x00, 0x00);
+
+        let info = CartridgeInfo::from_rom_data(&data).unwrap();
+
+        assert!(info.has_rtc());
+    }
+
+    #[test]
+    fn mbc5_rumble_cartridge_has_rumble() {
+        let data = get_rom_data_with_header(0x1C, 0x00, 0x00);
+
+        let info = CartridgeInfo::from_rom_data(&data).unwrap();
+
+        assert_eq!(MapperType::MBC5, info.get_mapper_type());
+        assert!(info.has_rumble());
+    }
+}
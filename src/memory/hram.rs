@@ -1,4 +1,5 @@
 use crate::memory::MemoryTrait;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
 
 pub struct HRAM {
     data: Vec<u8>,
@@ -28,6 +29,15 @@ impl HRAM {
     pub fn new() -> Self {
         Self { data: vec![0; Self::HRAM_SIZE] }
     }
+
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bytes(&self.data);
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.data = reader.read_bytes_of_length(Self::HRAM_SIZE, "HRAM")?.to_vec();
+        Ok(())
+    }
 }
 
 
@@ -1,15 +1,18 @@
 use crate::memory::MemoryTrait;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
 
+/**
+    DIV is the upper byte of a 16 bit counter that runs at the 4.19MHz clock.
+    The timer watches bits of the same counter, so it is exposed through `get_counter`.
+*/
 pub struct Divider {
-    clock: u16,
-
-    memory_value: u8
+    counter: u16,
 }
 
 impl MemoryTrait for Divider {
     fn get(&self, position: u16) -> u8 {
         if position == 0xFF04 {
-            self.memory_value
+            (self.counter >> 8) as u8
         } else {
             0xFF
         }
@@ -17,7 +20,7 @@ impl MemoryTrait for Divider {
 
     fn set(&mut self, position: u16, _value: u8) -> u8 {
         if position == 0xFF04 {
-            self.memory_value = 0;
+            self.counter = 0;
             0xFF
         } else {
             0xFF
@@ -31,20 +34,29 @@ impl MemoryTrait for Divider {
 
 
 impl Divider {
+    const INITIAL_COUNTER: u16 = 0xABCC; //value left behind by the DMG boot ROM
+
     pub fn new() -> Self {
         Self {
-            clock: 0,
-            memory_value: 0xAB
+            counter: Self::INITIAL_COUNTER
         }
     }
 
     pub fn clock(&mut self) {
-        self.clock += 1;
+        self.counter = self.counter.wrapping_add(4); //because the emulator only emulates every 4 clock cycles
+    }
 
-        if self.clock >= 512 { // Increment every 64 ticks
-            self.clock = 0;
-            self.memory_value = self.memory_value.wrapping_add(1);
-        }
+    pub fn get_counter(&self) -> u16 {
+        self.counter
+    }
+
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u16(self.counter);
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.counter = reader.read_u16()?;
+        Ok(())
     }
 }
 
@@ -85,10 +97,38 @@ mod tests {
     fn increments_value_on_clock() {
         let mut divider = super::Divider::new();
         
-        for _ in 0..512 {
+        for _ in 0..64 { //DIV runs at 16384Hz, once every 64 M-cycles
             divider.clock();
         }
         
         assert_eq!(divider.get(0xFF04), 0xAC);
+
+        for _ in 0..64 {
+            divider.clock();
+        }
+
+        assert_eq!(divider.get(0xFF04), 0xAD);
+    }
+
+    #[test]
+    fn counter_advances_4_per_clock() {
+        let mut divider = super::Divider::new();
+        divider.set(0xFF04, 0x00);
+
+        divider.clock();
+
+        assert_eq!(divider.get_counter(), 4);
+    }
+
+    #[test]
+    fn write_resets_whole_counter() {
+        let mut divider = super::Divider::new();
+
+        for _ in 0..63 {
+            divider.clock();
+        }
+        divider.set(0xFF04, 0x00);
+
+        assert_eq!(divider.get_counter(), 0);
     }
 }
@@ -1,4 +1,5 @@
 use crate::memory::MemoryTrait;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
 
 pub struct InterruptIO {
     pub interrupt_flag: u8, // Interrupt Flag Register
@@ -42,6 +43,18 @@ impl InterruptIO {
             interrupt_enable: 0x00,
         }
     }
+
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u8(self.interrupt_flag);
+        writer.write_u8(self.interrupt_enable);
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.interrupt_flag = reader.read_u8()?;
+        self.interrupt_enable = reader.read_u8()?;
+
+        Ok(())
+    }
 }
 
 
@@ -1,40 +1,53 @@
 use std::sync::{Arc};
 use parking_lot::Mutex;
-use crate::memory::io_map::divider::Divider;
+use crate::audio::APU;
+use crate::cpu::Interrupt;
 use crate::memory::io_map::interrupt_io::InterruptIO;
-use crate::memory::io_map::JoypadIO;
+use crate::memory::io_map::{JoypadIO, SerialPeer};
+use crate::memory::io_map::serial_io::SerialIO;
+use crate::memory::io_map::timer::Timer;
 use crate::memory::io_map::video_io::VideoIO;
 use crate::memory::memory_trait::MemoryTrait;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
 
 pub struct IOMap {
     joypad_io: Arc<Mutex<JoypadIO>>,
-    divider: Divider,
+    serial_io: SerialIO,
+    timer: Timer,
     interrupt_io: InterruptIO,
     video_io: Arc<Mutex<VideoIO>>,
+    apu: Arc<Mutex<APU>>,
+    stopped: bool,
 }
 
 impl MemoryTrait for IOMap {
     fn get(&self, position: u16) -> u8 {
         if self.joypad_io.lock().has_address(position) { self.joypad_io.lock().get(position) }
-        else if self.divider.has_address(position) { self.divider.get(position) }
+        else if self.serial_io.has_address(position) { self.serial_io.get(position) }
+        else if self.timer.has_address(position) { self.timer.get(position) }
         else if self.interrupt_io.has_address(position) { self.interrupt_io.get(position) }
         else if self.video_io.lock().has_address(position) { self.video_io.lock().get(position) }
+        else if self.apu.lock().has_address(position) { self.apu.lock().get(position) }
         else { 0xFF }
     }
 
     fn set(&mut self, position: u16, value: u8) -> u8 {
         if self.joypad_io.lock().has_address(position) { self.joypad_io.lock().set(position, value) }
-        else if self.divider.has_address(position) { self.divider.set(position, value) }
+        else if self.serial_io.has_address(position) { self.serial_io.set(position, value) }
+        else if self.timer.has_address(position) { self.timer.set(position, value) }
         else if self.interrupt_io.has_address(position) { self.interrupt_io.set(position, value) }
         else if self.video_io.lock().has_address(position) { self.video_io.lock().set(position, value) }
+        else if self.apu.lock().has_address(position) { self.apu.lock().set(position, value) }
         else { 0xFF }
     }
 
     fn has_address(&self, position: u16) -> bool { //all members must be or'd together for this
         self.joypad_io.lock().has_address(position) ||
-        self.divider.has_address(position)  ||
+        self.serial_io.has_address(position) ||
+        self.timer.has_address(position)  ||
         self.interrupt_io.has_address(position) ||
-        self.video_io.lock().has_address(position)
+        self.video_io.lock().has_address(position) ||
+        self.apu.lock().has_address(position)
     }
 }
 
@@ -42,17 +55,25 @@ impl IOMap {
     pub fn new() -> Self {
         Self {
             joypad_io: Arc::new(Mutex::new(JoypadIO::new())),
-            divider: Divider::new(),
+            serial_io: SerialIO::new(),
+            timer: Timer::new(),
             interrupt_io: InterruptIO::new(),
-            video_io: Arc::new(Mutex::new(VideoIO::new()))
+            video_io: Arc::new(Mutex::new(VideoIO::new())),
+            apu: Arc::new(Mutex::new(APU::new())),
+            stopped: false,
         }
     }
 
     pub fn reset(&mut self) {
         *self.joypad_io.lock() = JoypadIO::new();
-        self.divider = Divider::new();
+        self.serial_io.reset();
+        self.timer = Timer::new();
         self.interrupt_io = InterruptIO::new();
         *self.video_io.lock() = VideoIO::new();
+        let sample_rate = self.apu.lock().get_sample_rate();
+        *self.apu.lock() = APU::new();
+        self.apu.lock().set_sample_rate(sample_rate);
+        self.stopped = false;
     }
 
     pub fn get_joypad_io(&self) -> Arc<Mutex<JoypadIO>> {
@@ -63,8 +84,75 @@ impl IOMap {
         self.video_io.clone()
     }
 
-    pub fn clock(&mut self) {
-        self.divider.clock();
+    pub fn set_serial_peer(&mut self, peer: Option<Box<dyn SerialPeer>>) {
+        self.serial_io.set_peer(peer);
     }
 
+    pub fn get_apu(&self) -> Arc<Mutex<APU>> {
+        self.apu.clone()
+    }
+
+    /**
+        Returns the interrupts raised by IO registers during this M-cycle
+    */
+    pub fn clock(&mut self) -> Vec<Interrupt> {
+        let mut interrupts = vec![];
+
+        let joypad_interrupt = self.joypad_io.lock().take_interrupt();
+        if joypad_interrupt {
+            interrupts.push(Interrupt::Joypad);
+        }
+
+        if self.stopped {
+            self.stopped = !self.joypad_io.lock().has_selected_input();
+            return interrupts;
+        }
+
+        if self.timer.clock() {
+            interrupts.push(Interrupt::Timer);
+        }
+
+        if self.serial_io.clock() {
+            interrupts.push(Interrupt::Serial);
+        }
+
+        self.apu.lock().clock(self.timer.get_divider_counter());
+
+        interrupts
+    }
+
+
+    /**
+        STOP resets DIV, then the timer, serial port and sound are paused until a selected joypad line goes low
+    */
+    pub fn stop(&mut self) {
+        self.timer.set(0xFF04, 0x00);
+        self.stopped = true;
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        self.joypad_io.lock().save_state(writer);
+        self.serial_io.save_state(writer);
+        self.timer.save_state(writer);
+        self.interrupt_io.save_state(writer);
+        self.video_io.lock().save_state(writer);
+        self.apu.lock().save_state(writer);
+        writer.write_bool(self.stopped);
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.joypad_io.lock().load_state(reader)?;
+        self.serial_io.load_state(reader)?;
+        self.timer.load_state(reader)?;
+        self.interrupt_io.load_state(reader)?;
+        self.video_io.lock().load_state(reader)?;
+        self.apu.lock().load_state(reader)?;
+        self.stopped = reader.read_bool()?;
+
+        Ok(())
+    }
 }
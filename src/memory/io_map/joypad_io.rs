@@ -1,17 +1,14 @@
 use crate::memory::MemoryTrait;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
 
+/**
+    The buttons only change through set_buttons, so whatever sets them last each frame decides what the game sees
+*/
 pub struct JoypadIO {
-    up: bool,
-    down: bool,
-    left: bool,
-    right: bool,
+    buttons: u8, //lowest bit first: right, left, up, down, A, B, select, start
 
-    a: bool,
-    b: bool,
-    select: bool,
-    start: bool,
-
-    memory_value: u8
+    memory_value: u8,
+    interrupt_requested: bool,
 }
 
 impl MemoryTrait for JoypadIO {
@@ -22,7 +19,7 @@ impl MemoryTrait for JoypadIO {
     fn set(&mut self, _position: u16, value: u8) -> u8 {
         let old_value = self.memory_value;
 
-        self.memory_value = value | 0xC0;
+        self.memory_value = (value & 0x30) | (old_value & 0x0F) | 0xC0; //the input lines are kept so a press edge can be seen
         self.calculate_memory_value();
 
         old_value
@@ -37,94 +34,74 @@ impl JoypadIO {
 
     pub fn new() -> Self{
         Self {
-            up: false,
-            down: false,
-            left: false,
-            right: false,
-
-            a: false,
-            b: false,
-            select: false,
-            start: false,
+            buttons: 0,
 
-            memory_value: 0xCF
+            memory_value: 0xCF,
+            interrupt_requested: false,
         }
     }
 
-    pub fn calculate_memory_value(&mut self) {
+    /**
+        A selected input line going from high to low requests the joypad interrupt
+    */
+    fn calculate_memory_value(&mut self) {
+        let old_lines = self.memory_value & 0x0F;
+
         if self.memory_value & 0x30 == 0x20 {
-            self.memory_value |= 0x0F;
-            if self.right {
-                self.memory_value &= 0b11111110;
-            }
-            if self.left {
-                self.memory_value &= 0b11111101;
-            }
-            if self.up {
-                self.memory_value &= 0b11111011;
-            }
-            if self.down {
-                self.memory_value &= 0b11110111;
-            }
+            self.memory_value = (self.memory_value & 0xF0) | (!self.buttons & 0x0F);
         }
         else if self.memory_value & 0x30 == 0x10 {
-            self.memory_value |= 0x0F;
-            if self.a {
-                self.memory_value &= 0b11111110;
-            }
-            if self.b {
-                self.memory_value &= 0b11111101;
-            }
-            if self.select {
-                self.memory_value &= 0b11111011;
-            }
-            if self.start {
-                self.memory_value &= 0b11110111;
-            }
+            self.memory_value = (self.memory_value & 0xF0) | (!self.buttons >> 4);
         }
         else {
             self.memory_value |= 0xCF;
         }
-    }
 
-    pub fn set_right(&mut self, value: bool) {
-        self.right = value;
-        self.calculate_memory_value();
+        if old_lines & !self.memory_value & 0x0F != 0 {
+            self.interrupt_requested = true;
+        }
     }
 
-    pub fn set_left(&mut self, value: bool) {
-        self.left = value;
-        self.calculate_memory_value();
+    /**
+        Whether the joypad interrupt has been requested since this was last called
+    */
+    pub fn take_interrupt(&mut self) -> bool {
+        std::mem::replace(&mut self.interrupt_requested, false)
     }
 
-    pub fn set_up(&mut self, value: bool) {
-        self.up = value;
-        self.calculate_memory_value();
+    /**
+        Whether any selected input line is low, which wakes the CPU from STOP
+    */
+    pub fn has_selected_input(&self) -> bool {
+        self.memory_value & 0x0F != 0x0F
     }
 
-    pub fn set_down(&mut self, value: bool) {
-        self.down = value;
-        self.calculate_memory_value();
+    /**
+        All eight buttons as one byte, lowest bit first: right, left, up, down, A, B, select, start
+    */
+    pub fn get_buttons(&self) -> u8 {
+        self.buttons
     }
 
-    pub fn set_a(&mut self, value: bool) {
-        self.a = value;
+    pub fn set_buttons(&mut self, buttons: u8) {
+        self.buttons = buttons;
         self.calculate_memory_value();
     }
 
-    pub fn set_b(&mut self, value: bool) {
-        self.b = value;
-        self.calculate_memory_value();
+    /**
+        Only the selected button group and its input lines are saved, the buttons are whatever is held down when the state is loaded.
+        The lines are restored as they were so the next set_buttons sees the same press edges it did when the state was saved.
+    */
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u8(self.memory_value);
+        writer.write_bool(self.interrupt_requested);
     }
 
-    pub fn set_select(&mut self, value: bool) {
-        self.select = value;
-        self.calculate_memory_value();
-    }
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.memory_value = reader.read_u8()?;
+        self.interrupt_requested = reader.read_bool()?;
 
-    pub fn set_start(&mut self, value: bool) {
-        self.start = value;
-        self.calculate_memory_value();
+        Ok(())
     }
 }
 
@@ -168,7 +145,7 @@ mod tests {
     fn gets_right_correctly() {
         let mut joypad_io = JoypadIO::new();
 
-        joypad_io.set_right(true);
+        joypad_io.set_buttons(0x01);
 
         joypad_io.set(0xFF00, 0x20);
         assert_eq!(0b11101110, joypad_io.get(0xFF00));
@@ -178,7 +155,7 @@ mod tests {
     fn gets_left_correctly() {
         let mut joypad_io = JoypadIO::new();
 
-        joypad_io.set_left(true);
+        joypad_io.set_buttons(0x02);
 
         joypad_io.set(0xFF00, 0x20);
         assert_eq!(0b11101101, joypad_io.get(0xFF00));
@@ -188,7 +165,7 @@ mod tests {
     fn gets_up_correctly() {
         let mut joypad_io = JoypadIO::new();
 
-        joypad_io.set_up(true);
+        joypad_io.set_buttons(0x04);
 
         joypad_io.set(0xFF00, 0x20);
         assert_eq!(0b11101011, joypad_io.get(0xFF00));
@@ -198,7 +175,7 @@ mod tests {
     fn gets_down_correctly() {
         let mut joypad_io = JoypadIO::new();
 
-        joypad_io.set_down(true);
+        joypad_io.set_buttons(0x08);
 
         joypad_io.set(0xFF00, 0x20);
         assert_eq!(0b11100111, joypad_io.get(0xFF00));
@@ -208,7 +185,7 @@ mod tests {
     fn gets_a_correctly() {
         let mut joypad_io = JoypadIO::new();
 
-        joypad_io.set_a(true);
+        joypad_io.set_buttons(0x10);
 
         joypad_io.set(0xFF00, 0x10);
         assert_eq!(0b11011110, joypad_io.get(0xFF00));
@@ -218,7 +195,7 @@ mod tests {
     fn gets_b_correctly() {
         let mut joypad_io = JoypadIO::new();
 
-        joypad_io.set_b(true);
+        joypad_io.set_buttons(0x20);
 
         joypad_io.set(0xFF00, 0x10);
         assert_eq!(0b11011101, joypad_io.get(0xFF00));
@@ -228,7 +205,7 @@ mod tests {
     fn gets_select_correctly() {
         let mut joypad_io = JoypadIO::new();
 
-        joypad_io.set_select(true);
+        joypad_io.set_buttons(0x40);
 
         joypad_io.set(0xFF00, 0x10);
         assert_eq!(0b11011011, joypad_io.get(0xFF00));
@@ -238,9 +215,82 @@ mod tests {
     fn gets_start_correctly() {
         let mut joypad_io = JoypadIO::new();
 
-        joypad_io.set_start(true);
+        joypad_io.set_buttons(0x80);
 
         joypad_io.set(0xFF00, 0x10);
         assert_eq!(0b11010111, joypad_io.get(0xFF00));
     }
+
+    #[test]
+    fn set_buttons_sets_each_button() {
+        let mut joypad_io = JoypadIO::new();
+
+        joypad_io.set_buttons(0b10010001); //start, A and right
+
+        joypad_io.set(0xFF00, 0x20);
+        assert_eq!(0b11101110, joypad_io.get(0xFF00));
+        joypad_io.set(0xFF00, 0x10);
+        assert_eq!(0b11010110, joypad_io.get(0xFF00));
+        assert_eq!(0b10010001, joypad_io.get_buttons());
+    }
+
+    #[test]
+    fn pressing_selected_button_requests_interrupt() {
+        let mut joypad_io = JoypadIO::new();
+        joypad_io.set(0xFF00, 0x10);
+
+        joypad_io.set_buttons(0x10);
+
+        assert!(joypad_io.take_interrupt());
+        assert!(!joypad_io.take_interrupt());
+    }
+
+    #[test]
+    fn pressing_unselected_button_does_not_request_interrupt() {
+        let mut joypad_io = JoypadIO::new();
+        joypad_io.set(0xFF00, 0x20);
+
+        joypad_io.set_buttons(0x10);
+
+        assert!(!joypad_io.take_interrupt());
+    }
+
+    #[test]
+    fn releasing_button_does_not_request_interrupt() {
+        let mut joypad_io = JoypadIO::new();
+        joypad_io.set(0xFF00, 0x10);
+        joypad_io.set_buttons(0x10);
+        joypad_io.take_interrupt();
+
+        joypad_io.set_buttons(0);
+
+        assert!(!joypad_io.take_interrupt());
+    }
+
+    #[test]
+    fn selecting_held_button_requests_interrupt() {
+        let mut joypad_io = JoypadIO::new();
+        joypad_io.set_buttons(0x10);
+
+        joypad_io.set(0xFF00, 0x10);
+
+        assert!(joypad_io.take_interrupt());
+    }
+
+    #[test]
+    fn load_state_restores_saved_input_lines() {
+        let mut saved = JoypadIO::new();
+        saved.set(0xFF00, 0x10);
+        let mut writer = StateWriter::new();
+        saved.save_state(&mut writer);
+        let state = writer.into_data();
+
+        let mut joypad_io = JoypadIO::new();
+        joypad_io.set(0xFF00, 0x10);
+        joypad_io.set_buttons(0x10);
+
+        joypad_io.load_state(&mut StateReader::new(&state)).unwrap();
+
+        assert_eq!(0xDF, joypad_io.get(0xFF00));
+    }
 }
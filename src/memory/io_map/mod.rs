@@ -3,7 +3,11 @@ mod joypad_io;
 mod video_io;
 mod interrupt_io;
 mod divider;
+mod timer;
+mod serial_io;
+mod serial_peer;
 
 pub use io_map::IOMap;
 pub use joypad_io::JoypadIO;
 pub use video_io::VideoIO;
+pub use serial_peer::{SerialConsole, SerialPeer, SerialRecorder};
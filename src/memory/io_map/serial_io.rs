@@ -0,0 +1,342 @@
+use crate::memory::io_map::serial_peer::SerialPeer;
+use crate::memory::MemoryTrait;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
+
+/**
+    SB (0xFF01) and SC (0xFF02). On the internal clock a byte is exchanged at 8192Hz, taking 1024 M-cycles, and SB holds
+    the byte from the other end once it completes. If the peer hasn't replied by then the transfer stays busy until it does.
+    On the external clock the transfer completes whenever the peer clocks one, and SB is left alone while no transfer is enabled.
+*/
+pub struct SerialIO {
+    data: u8,
+    control: u8,
+
+    transferring: bool,
+    transfer_clocks: u16,
+    reply: Option<u8>,
+
+    peer: Option<Box<dyn SerialPeer>>,
+}
+
+impl MemoryTrait for SerialIO {
+    fn get(&self, position: u16) -> u8 {
+        match position {
+            0xFF01 => self.data,
+            0xFF02 => self.control | 0x7E,
+            _ => 0xFF
+        }
+    }
+
+    fn set(&mut self, position: u16, value: u8) -> u8 {
+        match position {
+            0xFF01 => {
+                let old_value = self.data;
+                self.data = value;
+
+                old_value
+            }
+            0xFF02 => {
+                let old_value = self.control | 0x7E;
+                self.control = value & (Self::TRANSFER_ENABLE | Self::INTERNAL_CLOCK);
+
+                if self.control == Self::TRANSFER_ENABLE | Self::INTERNAL_CLOCK {
+                    self.start_transfer();
+                } else {
+                    self.transferring = false;
+                }
+
+                old_value
+            }
+            _ => 0xFF
+        }
+    }
+
+    fn has_address(&self, position: u16) -> bool {
+        position == 0xFF01 || position == 0xFF02
+    }
+}
+
+impl SerialIO {
+
+    const TRANSFER_ENABLE: u8 = 0x80;
+    const INTERNAL_CLOCK: u8 = 0x01;
+    const TRANSFER_CLOCKS: u16 = 1024; //8 bits at 8192Hz
+
+    pub fn new() -> Self {
+        Self {
+            data: 0x00,
+            control: 0x00,
+
+            transferring: false,
+            transfer_clocks: 0,
+            reply: None,
+
+            peer: None,
+        }
+    }
+
+    /**
+        Clears the registers, the cable stays plugged in
+    */
+    pub fn reset(&mut self) {
+        let peer = self.peer.take();
+
+        *self = Self::new();
+        self.peer = peer;
+    }
+
+    pub fn set_peer(&mut self, peer: Option<Box<dyn SerialPeer>>) {
+        self.peer = peer;
+    }
+
+    /**
+        Returns true when the serial interrupt should be requested
+    */
+    pub fn clock(&mut self) -> bool {
+        let polled = match &mut self.peer {
+            Some(peer) => peer.poll(self.data),
+            None => None
+        };
+
+        if self.transferring {
+            if polled.is_some() {
+                self.reply = polled;
+            }
+            if self.transfer_clocks > 0 {
+                self.transfer_clocks -= 1;
+            }
+
+            let reply = if self.peer.is_none() { Some(0xFF) } else { self.reply }; //nothing drives the line without a cable
+
+            return match reply {
+                Some(incoming) if self.transfer_clocks == 0 => {
+                    self.complete_transfer(incoming);
+                    true
+                }
+                _ => false
+            };
+        }
+
+        match polled {
+            Some(incoming) if self.control == Self::TRANSFER_ENABLE => { //SB only shifts while waiting on the external clock
+                self.complete_transfer(incoming);
+                true
+            }
+            _ => false
+        }
+    }
+
+    fn start_transfer(&mut self) {
+        self.transferring = true;
+        self.transfer_clocks = Self::TRANSFER_CLOCKS;
+        self.reply = None;
+
+        match &mut self.peer {
+            Some(peer) => peer.start_transfer(self.data),
+            None => {}
+        }
+    }
+
+    fn complete_transfer(&mut self, incoming: u8) {
+        self.data = incoming;
+        self.control &= !Self::TRANSFER_ENABLE;
+        self.transferring = false;
+    }
+
+    /**
+        The peer isn't part of the state, so a transfer in progress carries on with whatever is plugged in
+    */
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u8(self.data);
+        writer.write_u8(self.control);
+        writer.write_bool(self.transferring);
+        writer.write_u16(self.transfer_clocks);
+        writer.write_bool(self.reply.is_some());
+        writer.write_u8(self.reply.unwrap_or(0xFF));
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.data = reader.read_u8()?;
+        self.control = reader.read_u8()?;
+        self.transferring = reader.read_bool()?;
+        self.transfer_clocks = reader.read_u16()?;
+        let has_reply = reader.read_bool()?;
+        let reply = reader.read_u8()?;
+        self.reply = if has_reply { Some(reply) } else { None };
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use parking_lot::Mutex;
+    use crate::memory::io_map::SerialConsole;
+    use super::*;
+
+    struct TestPeer {
+        sent: Arc<Mutex<Vec<u8>>>,
+        replies: Vec<Option<u8>>, //one per poll, None once empty
+    }
+
+    impl SerialPeer for TestPeer {
+        fn start_transfer(&mut self, outgoing: u8) {
+            self.sent.lock().push(outgoing);
+        }
+
+        fn poll(&mut self, _data: u8) -> Option<u8> {
+            if self.replies.is_empty() { None } else { self.replies.remove(0) }
+        }
+    }
+
+    fn get_serial_io_with_peer(replies: Vec<Option<u8>>) -> (SerialIO, Arc<Mutex<Vec<u8>>>) {
+        let mut serial_io = SerialIO::new();
+        let sent = Arc::new(Mutex::new(vec![]));
+
+        serial_io.set_peer(Some(Box::new(TestPeer { sent: sent.clone(), replies })));
+
+        (serial_io, sent)
+    }
+
+    fn clock_serial_io(serial_io: &mut SerialIO, clocks: u32) -> bool {
+        let mut interrupt = false;
+
+        for _ in 0..clocks {
+            interrupt |= serial_io.clock();
+        }
+
+        interrupt
+    }
+
+    #[test]
+    fn has_addresses_0xff01_and_0xff02() {
+        let serial_io = SerialIO::new();
+
+        assert!(!serial_io.has_address(0xFF00));
+        assert!(serial_io.has_address(0xFF01));
+        assert!(serial_io.has_address(0xFF02));
+        assert!(!serial_io.has_address(0xFF03));
+    }
+
+    #[test]
+    fn unused_control_bits_read_as_1() {
+        let mut serial_io = SerialIO::new();
+
+        serial_io.set(0xFF02, 0x00);
+
+        assert_eq!(0x7E, serial_io.get(0xFF02));
+    }
+
+    #[test]
+    fn internal_transfer_takes_1024_clocks_and_interrupts() {
+        let mut serial_io = SerialIO::new();
+
+        serial_io.set(0xFF01, 0x12);
+        serial_io.set(0xFF02, 0x81);
+
+        assert!(!clock_serial_io(&mut serial_io, 1023));
+        assert_eq!(0xFF, serial_io.get(0xFF02));
+        assert_eq!(0x12, serial_io.get(0xFF01));
+
+        assert!(serial_io.clock());
+        assert_eq!(0x7F, serial_io.get(0xFF02));
+    }
+
+    #[test]
+    fn reads_0xff_without_peer() {
+        let mut serial_io = SerialIO::new();
+
+        serial_io.set(0xFF01, 0x12);
+        serial_io.set(0xFF02, 0x81);
+        clock_serial_io(&mut serial_io, 1024);
+
+        assert_eq!(0xFF, serial_io.get(0xFF01));
+    }
+
+    #[test]
+    fn sends_byte_and_receives_peer_reply() {
+        let (mut serial_io, sent) = get_serial_io_with_peer(vec![None, Some(0xA5)]);
+
+        serial_io.set(0xFF01, 0x0F);
+        serial_io.set(0xFF02, 0x81);
+
+        assert!(clock_serial_io(&mut serial_io, 1024));
+        assert_eq!(0xA5, serial_io.get(0xFF01));
+        assert_eq!(vec![0x0F], *sent.lock());
+    }
+
+    #[test]
+    fn waits_for_late_reply() {
+        let mut replies = vec![None; 1100];
+        replies.push(Some(0xA5));
+        let (mut serial_io, _) = get_serial_io_with_peer(replies);
+
+        serial_io.set(0xFF02, 0x81);
+
+        assert!(!clock_serial_io(&mut serial_io, 1100));
+        assert_eq!(0xFF, serial_io.get(0xFF02));
+
+        assert!(serial_io.clock());
+        assert_eq!(0xA5, serial_io.get(0xFF01));
+    }
+
+    #[test]
+    fn does_not_transfer_without_enable_bit() {
+        let (mut serial_io, sent) = get_serial_io_with_peer(vec![]);
+
+        serial_io.set(0xFF01, 0x0F);
+        serial_io.set(0xFF02, 0x01);
+
+        assert!(!clock_serial_io(&mut serial_io, 2048));
+        assert_eq!(0x0F, serial_io.get(0xFF01));
+        assert!(sent.lock().is_empty());
+    }
+
+    #[test]
+    fn external_transfer_completes_when_peer_clocks() {
+        let (mut serial_io, _) = get_serial_io_with_peer(vec![Some(0x42)]);
+
+        serial_io.set(0xFF01, 0x24);
+        serial_io.set(0xFF02, 0x80);
+
+        assert!(serial_io.clock());
+        assert_eq!(0x42, serial_io.get(0xFF01));
+        assert_eq!(0x7E, serial_io.get(0xFF02));
+    }
+
+    #[test]
+    fn ignores_peer_byte_while_idle() {
+        let (mut serial_io, _) = get_serial_io_with_peer(vec![Some(0x42)]);
+
+        serial_io.set(0xFF01, 0x24);
+
+        assert!(!serial_io.clock());
+        assert_eq!(0x24, serial_io.get(0xFF01));
+        assert_eq!(0x7E, serial_io.get(0xFF02));
+    }
+
+    #[test]
+    fn reset_keeps_peer() {
+        let (mut serial_io, sent) = get_serial_io_with_peer(vec![]);
+
+        serial_io.set(0xFF01, 0x33);
+        serial_io.reset();
+        serial_io.set(0xFF02, 0x81);
+
+        assert_eq!(vec![0x00], *sent.lock());
+    }
+
+    #[test]
+    fn console_replies_0xff() {
+        let mut serial_io = SerialIO::new();
+        serial_io.set_peer(Some(Box::new(SerialConsole::new())));
+
+        serial_io.set(0xFF01, 0x0A);
+        serial_io.set(0xFF02, 0x81);
+
+        assert!(clock_serial_io(&mut serial_io, 1024));
+        assert_eq!(0xFF, serial_io.get(0xFF01));
+    }
+}
@@ -0,0 +1,127 @@
+use std::io::Write;
+use std::sync::Arc;
+use parking_lot::Mutex;
+
+/**
+    Whatever is plugged into the other end of the link cable.
+*/
+pub trait SerialPeer: Send {
+    /**
+        Called when this Game Boy starts a transfer on its internal clock
+    */
+    fn start_transfer(&mut self, outgoing: u8);
+
+    /**
+        Called every M-cycle with the current value of SB. Returns the byte shifted in from the other end, either
+        in reply to a transfer we started or because the other end clocked one itself.
+    */
+    fn poll(&mut self, data: u8) -> Option<u8>;
+}
+
+/**
+    Writes every byte sent over the cable to stdout, which is how test ROMs report their results.
+    Nothing drives the line back, so transfers read 0xFF.
+*/
+pub struct SerialConsole {
+    replying: bool,
+}
+
+impl SerialConsole {
+    pub fn new() -> Self {
+        Self {
+            replying: false,
+        }
+    }
+}
+
+impl SerialPeer for SerialConsole {
+    fn start_transfer(&mut self, outgoing: u8) {
+        let mut stdout = std::io::stdout();
+
+        let _ = stdout.write_all(&[outgoing]);
+        let _ = stdout.flush();
+
+        self.replying = true;
+    }
+
+    fn poll(&mut self, _data: u8) -> Option<u8> {
+        if self.replying {
+            self.replying = false;
+            Some(0xFF)
+        } else {
+            None
+        }
+    }
+}
+
+/**
+    Keeps every byte sent over the cable so it can be checked later, optionally echoing it to stdout like SerialConsole
+*/
+pub struct SerialRecorder {
+    output: Arc<Mutex<Vec<u8>>>,
+    console: Option<SerialConsole>,
+    replying: bool,
+}
+
+impl SerialRecorder {
+    pub fn new(echo: bool) -> Self {
+        Self {
+            output: Arc::new(Mutex::new(vec![])),
+            console: if echo { Some(SerialConsole::new()) } else { None },
+            replying: false,
+        }
+    }
+
+    pub fn get_output(&self) -> Arc<Mutex<Vec<u8>>> {
+        self.output.clone()
+    }
+}
+
+impl SerialPeer for SerialRecorder {
+    fn start_transfer(&mut self, outgoing: u8) {
+        self.output.lock().push(outgoing);
+
+        match &mut self.console {
+            Some(console) => console.start_transfer(outgoing),
+            None => {}
+        }
+
+        self.replying = true;
+    }
+
+    fn poll(&mut self, _data: u8) -> Option<u8> {
+        if self.replying {
+            self.replying = false;
+            Some(0xFF)
+        } else {
+            None
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recorder_keeps_transferred_bytes() {
+        let mut recorder = SerialRecorder::new(false);
+        let output = recorder.get_output();
+
+        recorder.start_transfer(b'O');
+        recorder.start_transfer(b'K');
+
+        assert_eq!(b"OK".to_vec(), *output.lock());
+    }
+
+    #[test]
+    fn recorder_replies_0xff_once_per_transfer() {
+        let mut recorder = SerialRecorder::new(false);
+
+        recorder.start_transfer(0x12);
+
+        assert_eq!(Some(0xFF), recorder.poll(0x12));
+        assert_eq!(None, recorder.poll(0x12));
+    }
+}
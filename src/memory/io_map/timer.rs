@@ -0,0 +1,336 @@
+use crate::memory::io_map::divider::Divider;
+use crate::memory::MemoryTrait;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
+
+/**
+    TIMA is incremented on the falling edge of one bit of the divider's counter (ANDed with the enable bit),
+    so resetting DIV or changing TAC can increment TIMA early, just like on hardware.
+    After an overflow TIMA reads 0x00 for one M-cycle before being reloaded from TMA and raising the interrupt.
+*/
+pub struct Timer {
+    divider: Divider,
+
+    tima: u8,
+    tma: u8,
+    tac: u8,
+
+    overflow_pending: bool, //TIMA overflowed on the previous M-cycle
+    reloaded: bool,         //TIMA was reloaded from TMA on this M-cycle
+}
+
+impl MemoryTrait for Timer {
+    fn get(&self, position: u16) -> u8 {
+        match position {
+            0xFF04 => self.divider.get(position),
+            0xFF05 => self.tima,
+            0xFF06 => self.tma,
+            0xFF07 => self.tac | 0xF8,
+            _ => 0xFF
+        }
+    }
+
+    fn set(&mut self, position: u16, value: u8) -> u8 {
+        match position {
+            0xFF04 => {
+                let old_signal = self.get_signal();
+                let old_value = self.divider.set(position, value);
+                self.check_falling_edge(old_signal);
+
+                old_value
+            }
+            0xFF05 => {
+                let old_value = self.tima;
+
+                if !self.reloaded { //the reload wins over writes in the same cycle
+                    self.tima = value;
+                    self.overflow_pending = false;
+                }
+
+                old_value
+            }
+            0xFF06 => {
+                let old_value = self.tma;
+                self.tma = value;
+
+                if self.reloaded {
+                    self.tima = value;
+                }
+
+                old_value
+            }
+            0xFF07 => {
+                let old_value = self.tac | 0xF8;
+                let old_signal = self.get_signal();
+                self.tac = value & 0x07;
+                self.check_falling_edge(old_signal);
+
+                old_value
+            }
+            _ => 0xFF
+        }
+    }
+
+    fn has_address(&self, position: u16) -> bool {
+        position >= 0xFF04 && position <= 0xFF07
+    }
+}
+
+impl Timer {
+
+    const TIMER_ENABLE: u8 = 0x04;
+
+    pub fn new() -> Self {
+        Self {
+            divider: Divider::new(),
+
+            tima: 0x00,
+            tma: 0x00,
+            tac: 0x00,
+
+            overflow_pending: false,
+            reloaded: false,
+        }
+    }
+
+    /**
+        Returns true when the timer interrupt should be requested
+    */
+    pub fn clock(&mut self) -> bool {
+        let mut interrupt = false;
+        self.reloaded = false;
+
+        if self.overflow_pending {
+            self.overflow_pending = false;
+            self.reloaded = true;
+            self.tima = self.tma;
+
+            interrupt = true;
+        }
+
+        let old_signal = self.get_signal();
+        self.divider.clock();
+        self.check_falling_edge(old_signal);
+
+        interrupt
+    }
+
+    /**
+        The frame sequencer of the APU is stepped by the same counter
+    */
+    pub fn get_divider_counter(&self) -> u16 {
+        self.divider.get_counter()
+    }
+
+    fn get_signal(&self) -> bool {
+        let bit = match self.tac & 0x03 {
+            0x00 => 9, //4096Hz
+            0x01 => 3, //262144Hz
+            0x02 => 5, //65536Hz
+            _ => 7     //16384Hz
+        };
+
+        self.tac & Self::TIMER_ENABLE != 0 && self.divider.get_counter() & (1 << bit) != 0
+    }
+
+    fn check_falling_edge(&mut self, old_signal: bool) {
+        if old_signal && !self.get_signal() {
+            let (tima, overflow) = self.tima.overflowing_add(1);
+
+            self.tima = tima;
+            self.overflow_pending |= overflow;
+        }
+    }
+
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        self.divider.save_state(writer);
+        writer.write_u8(self.tima);
+        writer.write_u8(self.tma);
+        writer.write_u8(self.tac);
+        writer.write_bool(self.overflow_pending);
+        writer.write_bool(self.reloaded);
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.divider.load_state(reader)?;
+        self.tima = reader.read_u8()?;
+        self.tma = reader.read_u8()?;
+        self.tac = reader.read_u8()?;
+        self.overflow_pending = reader.read_bool()?;
+        self.reloaded = reader.read_bool()?;
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_timer_at_zero(tac: u8) -> Timer {
+        let mut timer = Timer::new();
+
+        timer.set(0xFF04, 0x00);
+        timer.set(0xFF07, tac);
+
+        timer
+    }
+
+    fn clock_timer(timer: &mut Timer, clocks: u32) -> bool {
+        let mut interrupt = false;
+
+        for _ in 0..clocks {
+            interrupt |= timer.clock();
+        }
+
+        interrupt
+    }
+
+    #[test]
+    fn has_addresses_0xff04_to_0xff07() {
+        let timer = Timer::new();
+
+        assert!(!timer.has_address(0xFF03));
+        assert!(timer.has_address(0xFF04));
+        assert!(timer.has_address(0xFF07));
+        assert!(!timer.has_address(0xFF08));
+    }
+
+    #[test]
+    fn reads_div_through_divider() {
+        let timer = Timer::new();
+
+        assert_eq!(0xAB, timer.get(0xFF04));
+    }
+
+    #[test]
+    fn unused_tac_bits_read_as_1() {
+        let mut timer = Timer::new();
+
+        timer.set(0xFF07, 0x05);
+
+        assert_eq!(0xFD, timer.get(0xFF07));
+    }
+
+    #[test]
+    fn does_not_count_when_disabled() {
+        let mut timer = get_timer_at_zero(0x01);
+        timer.set(0xFF07, 0x01 & !Timer::TIMER_ENABLE);
+
+        clock_timer(&mut timer, 1024);
+
+        assert_eq!(0x00, timer.get(0xFF05));
+    }
+
+    #[test]
+    fn counts_every_4_clocks_at_262144hz() {
+        let mut timer = get_timer_at_zero(0x05);
+
+        clock_timer(&mut timer, 4);
+        assert_eq!(0x01, timer.get(0xFF05));
+
+        clock_timer(&mut timer, 3);
+        assert_eq!(0x01, timer.get(0xFF05));
+    }
+
+    #[test]
+    fn counts_at_each_rate() {
+        for (tac, clocks) in [(0x04, 256), (0x05, 4), (0x06, 16), (0x07, 64)] {
+            let mut timer = get_timer_at_zero(tac);
+
+            clock_timer(&mut timer, clocks * 10);
+
+            assert_eq!(10, timer.get(0xFF05));
+        }
+    }
+
+    #[test]
+    fn overflow_reads_0_for_one_cycle_then_reloads_with_interrupt() {
+        let mut timer = get_timer_at_zero(0x05);
+        timer.set(0xFF05, 0xFF);
+        timer.set(0xFF06, 0x23);
+
+        assert!(!clock_timer(&mut timer, 4));
+        assert_eq!(0x00, timer.get(0xFF05));
+
+        assert!(timer.clock());
+        assert_eq!(0x23, timer.get(0xFF05));
+    }
+
+    #[test]
+    fn writing_tima_during_overflow_cycle_cancels_reload() {
+        let mut timer = get_timer_at_zero(0x05);
+        timer.set(0xFF05, 0xFF);
+        timer.set(0xFF06, 0x23);
+
+        clock_timer(&mut timer, 4);
+        timer.set(0xFF05, 0x10);
+
+        assert!(!timer.clock());
+        assert_eq!(0x10, timer.get(0xFF05));
+    }
+
+    #[test]
+    fn writing_tima_during_reload_cycle_is_ignored() {
+        let mut timer = get_timer_at_zero(0x05);
+        timer.set(0xFF05, 0xFF);
+        timer.set(0xFF06, 0x23);
+
+        clock_timer(&mut timer, 5);
+        timer.set(0xFF05, 0x10);
+
+        assert_eq!(0x23, timer.get(0xFF05));
+    }
+
+    #[test]
+    fn writing_tma_during_reload_cycle_is_copied_to_tima() {
+        let mut timer = get_timer_at_zero(0x05);
+        timer.set(0xFF05, 0xFF);
+        timer.set(0xFF06, 0x23);
+
+        clock_timer(&mut timer, 5);
+        timer.set(0xFF06, 0x45);
+
+        assert_eq!(0x45, timer.get(0xFF05));
+    }
+
+    #[test]
+    fn resetting_div_with_selected_bit_set_increments_tima() {
+        let mut timer = get_timer_at_zero(0x05);
+
+        clock_timer(&mut timer, 2); //bit 3 of the counter is now set
+        timer.set(0xFF04, 0x00);
+
+        assert_eq!(0x01, timer.get(0xFF05));
+    }
+
+    #[test]
+    fn resetting_div_with_selected_bit_clear_does_not_increment_tima() {
+        let mut timer = get_timer_at_zero(0x05);
+
+        clock_timer(&mut timer, 1);
+        timer.set(0xFF04, 0x00);
+
+        assert_eq!(0x00, timer.get(0xFF05));
+    }
+
+    #[test]
+    fn disabling_timer_with_selected_bit_set_increments_tima() {
+        let mut timer = get_timer_at_zero(0x05);
+
+        clock_timer(&mut timer, 2);
+        timer.set(0xFF07, 0x01);
+
+        assert_eq!(0x01, timer.get(0xFF05));
+    }
+
+    #[test]
+    fn shares_counter_with_div() {
+        let mut timer = get_timer_at_zero(0x04);
+
+        clock_timer(&mut timer, 64);
+
+        assert_eq!(0x01, timer.get(0xFF04));
+        assert_eq!(256, timer.divider.get_counter());
+    }
+}
@@ -1,4 +1,5 @@
 use crate::memory::memory_trait::MemoryTrait;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
 
 pub struct VideoIO {
     lcd_ctrl: u8,   //0xFF40
@@ -136,6 +137,26 @@ impl VideoIO {
     pub fn get_win_y(&self) -> u8 {
         self.win_y
     }
+
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        for position in 0xFF40..=0xFF4B {
+            writer.write_u8(self.get(position));
+        }
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        let mut registers = [0; 12];
+        for register in registers.iter_mut() {
+            *register = reader.read_u8()?;
+        }
+
+        [
+            self.lcd_ctrl, self.lcd_stat, self.scroll_y, self.scroll_x, self.ly, self.lyc,
+            self.oam_dma, self.bg_pal, self.obj_pal_0, self.obj_pal_1, self.win_x, self.win_y
+        ] = registers;
+
+        Ok(())
+    }
 }
 
 
@@ -0,0 +1,187 @@
+use std::sync::Arc;
+use crate::memory::cartridge_error::CartridgeError;
+use crate::memory::cartridge_info::{CartridgeInfo, MapperType};
+use crate::memory::mbc::{RomOnly, MBC1, MBC2, MBC3, MBC5};
+use crate::memory::sram::SRAM;
+
+/**
+    A cartridge's banking hardware. The ROM and external RAM themselves are owned by `ROM` and `SRAM`
+    and handed in on each access, so a mapper only holds its own registers.
+*/
+pub trait Mapper: Send {
+    fn write_rom(&mut self, position: u16, value: u8);
+    fn get_rom_bank(&self, position: u16) -> usize;
+
+    fn read_rom(&self, rom: &Vec<Vec<u8>>, position: u16) -> u8 {
+        let bank = &rom[self.get_rom_bank(position) % rom.len()];
+
+        match bank.get(position as usize % ROM_BANK_SIZE) {
+            Some(byte) => *byte,
+            None => 0xFF
+        }
+    }
+
+    fn read_ram(&self, sram: &SRAM, position: u16) -> u8;
+    fn write_ram(&mut self, sram: &mut SRAM, position: u16, value: u8) -> u8;
+
+    fn clock(&mut self) {}
+
+    fn save_state(&self) -> Vec<u8>;
+    fn load_state(&mut self, state: &[u8]) -> Result<(), CartridgeError>;
+
+    fn get_battery_data(&self, sram: &SRAM) -> Vec<u8> {
+        sram.get_data()
+    }
+
+    fn load_battery_data(&mut self, sram: &mut SRAM, data: &[u8]) {
+        sram.load_data(&data[..sram.get_size().min(data.len())]);
+    }
+
+    fn set_rumble_callback(&mut self, _callback: RumbleCallback) {} //only rumble carts have a motor
+}
+
+/**
+    Called with the new motor state whenever a rumble cartridge turns its motor on or off
+*/
+pub type RumbleCallback = Arc<dyn Fn(bool) + Send + Sync>;
+
+pub const ROM_BANK_SIZE: usize = 0x4000;
+
+pub fn create_mapper(cartridge_info: &CartridgeInfo, data: &Vec<Vec<u8>>) -> Box<dyn Mapper> {
+    match cartridge_info.get_mapper_type() {
+        MapperType::MBC1 => Box::new(MBC1::new(MBC1::is_multicart(data))),
+        MapperType::MBC2 => Box::new(MBC2::new()),
+        MapperType::MBC3 => Box::new(MBC3::new(cartridge_info.has_rtc())),
+        MapperType::MBC5 => Box::new(MBC5::new(cartridge_info.has_rumble())),
+        _ => Box::new(RomOnly::new())
+    }
+}
+
+pub fn check_state_length(state: &[u8], expected: usize) -> Result<(), CartridgeError> {
+    if state.len() == expected {
+        Ok(())
+    } else {
+        Err(CartridgeError::InvalidMapperState { expected, length: state.len() })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::cartridge_info::tests::get_rom_data_with_header;
+
+    fn get_mapper_with_type(cartridge_type: u8) -> Box<dyn Mapper> {
+        let rom_data = get_rom_data_with_header(cartridge_type, 0x01, 0x00);
+        let cartridge_info = CartridgeInfo::from_rom_data(&rom_data).unwrap();
+
+        create_mapper(&cartridge_info, &rom_data.chunks(ROM_BANK_SIZE).map(|bank| bank.to_vec()).collect())
+    }
+
+    /**
+        Each controller masks the ROM bank register differently, so the bank it switches to tells them apart
+    */
+    fn get_bank_after_write(cartridge_type: u8, position: u16, value: u8) -> usize {
+        let mut mapper = get_mapper_with_type(cartridge_type);
+        mapper.write_rom(position, value);
+
+        mapper.get_rom_bank(0x4000)
+    }
+
+    #[test]
+    fn rom_only_uses_rom_only_mapper() {
+        assert_eq!(0xFF, get_bank_after_write(0x00, 0x2100, 0xFF));
+        assert_eq!(0x01, get_bank_after_write(0x00, 0x3000, 0x01));
+    }
+
+    #[test]
+    fn unsupported_types_use_rom_only_mapper() {
+        assert_eq!(0xFF, get_bank_after_write(0xFD, 0x2100, 0xFF));
+        assert_eq!(0x01, get_bank_after_write(0xFD, 0x3000, 0x01));
+    }
+
+    #[test]
+    fn mbc1_types_use_mbc1() {
+        for cartridge_type in 0x01..=0x03 {
+            assert_eq!(0x1F, get_bank_after_write(cartridge_type, 0x2100, 0xFF));
+        }
+    }
+
+    #[test]
+    fn mbc2_types_use_mbc2() {
+        for cartridge_type in 0x05..=0x06 {
+            assert_eq!(0x0F, get_bank_after_write(cartridge_type, 0x2100, 0xFF));
+        }
+    }
+
+    #[test]
+    fn mbc3_types_use_mbc3() {
+        for cartridge_type in 0x0F..=0x13 {
+            assert_eq!(0x7F, get_bank_after_write(cartridge_type, 0x2100, 0xFF));
+        }
+    }
+
+    #[test]
+    fn mbc5_types_use_mbc5() {
+        for cartridge_type in 0x19..=0x1E {
+            assert_eq!(0x101, get_bank_after_write(cartridge_type, 0x3000, 0x01));
+        }
+    }
+
+    #[test]
+    fn mbc3_timer_types_have_rtc() {
+        let sram = SRAM::new();
+
+        assert!(get_mapper_with_type(0x10).get_battery_data(&sram).len() > 0);
+        assert_eq!(0, get_mapper_with_type(0x13).get_battery_data(&sram).len());
+    }
+
+    #[test]
+    fn reads_rom_from_selected_bank() {
+        let mut mapper = get_mapper_with_type(0x01);
+        let mut rom = vec![vec![0; ROM_BANK_SIZE]; 4];
+        rom[3][0x0123] = 0x12;
+
+        mapper.write_rom(0x2000, 0x03);
+
+        assert_eq!(0x12, mapper.read_rom(&rom, 0x4123));
+    }
+
+    #[test]
+    fn rom_read_wraps_to_rom_size() {
+        let mut mapper = get_mapper_with_type(0x01);
+        let mut rom = vec![vec![0; ROM_BANK_SIZE]; 2];
+        rom[1][0x0123] = 0x12;
+
+        mapper.write_rom(0x2000, 0x03);
+
+        assert_eq!(0x12, mapper.read_rom(&rom, 0x4123));
+    }
+
+    #[test]
+    fn rom_read_past_short_bank_is_0xff() {
+        let mapper = get_mapper_with_type(0x00);
+        let rom = vec![vec![0; ROM_BANK_SIZE], vec![0; 0x10]];
+
+        assert_eq!(0xFF, mapper.read_rom(&rom, 0x4010));
+    }
+
+    #[test]
+    fn battery_data_is_raw_ram() {
+        let mapper = get_mapper_with_type(0x03);
+        let mut sram = SRAM::new_with_size(0x2000);
+
+        sram.set(0, 0xA001, 0x12);
+
+        let data = mapper.get_battery_data(&sram);
+
+        assert_eq!(0x2000, data.len());
+        assert_eq!(0x12, data[1]);
+    }
+
+    #[test]
+    fn state_length_is_checked() {
+        assert!(check_state_length(&[0; 4], 4).is_ok());
+        assert!(matches!(check_state_length(&[0; 3], 4), Err(CartridgeError::InvalidMapperState { expected: 4, length: 3 })));
+    }
+}
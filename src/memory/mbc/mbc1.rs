@@ -0,0 +1,350 @@
+use crate::memory::cartridge_error::CartridgeError;
+use crate::memory::mbc::mapper::check_state_length;
+use crate::memory::mbc::Mapper;
+use crate::memory::sram::SRAM;
+
+pub struct MBC1 {
+    ram_enabled: bool,
+    bank_1: u8,             //0x2000-0x3FFF, 5 bits
+    bank_2: u8,             //0x4000-0x5FFF, 2 bits
+    advanced_banking: bool, //0x6000-0x7FFF
+    multicart: bool,
+}
+
+impl MBC1 {
+
+    const MULTICART_BANK_COUNT: usize = 0x40;
+    const MULTICART_HEADER_BANK: usize = 0x10;
+    const NINTENDO_LOGO: [u8; 0x30] = [
+        0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+        0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+        0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E
+    ];
+
+    pub fn new(multicart: bool) -> Self {
+        Self {
+            ram_enabled: false,
+            bank_1: 1,
+            bank_2: 0,
+            advanced_banking: false,
+            multicart
+        }
+    }
+
+    /**
+        MBC1M carts are 1MB MBC1 carts with bank 2 wired one bit lower, so each game gets 16 banks.
+        They can only be told apart from a regular MBC1 cart by the second game's header at bank 0x10.
+    */
+    pub fn is_multicart(data: &Vec<Vec<u8>>) -> bool {
+        if data.len() != Self::MULTICART_BANK_COUNT {
+            return false;
+        }
+
+        let header_bank = &data[Self::MULTICART_HEADER_BANK];
+
+        header_bank.len() >= 0x0134 && header_bank[0x0104..0x0134] == Self::NINTENDO_LOGO
+    }
+
+    fn get_ram_bank(&self) -> usize {
+        if self.advanced_banking { self.bank_2 as usize } else { 0 }
+    }
+
+    fn is_ram_enabled(&self) -> bool {
+        self.ram_enabled
+    }
+
+    fn get_bank_2_shift(&self) -> u8 {
+        if self.multicart { 4 } else { 5 }
+    }
+}
+
+impl Mapper for MBC1 {
+    fn write_rom(&mut self, position: u16, value: u8) {
+        match position {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => {
+                self.bank_1 = value & 0x1F;
+
+                if self.bank_1 == 0 { //bank 0 can't be selected here, so 0x00, 0x20, 0x40 and 0x60 all map one bank higher
+                    self.bank_1 = 1;
+                }
+            }
+            0x4000..=0x5FFF => self.bank_2 = value & 0x03,
+            0x6000..=0x7FFF => self.advanced_banking = value & 0x01 != 0,
+            _ => {}
+        }
+    }
+
+    fn get_rom_bank(&self, position: u16) -> usize {
+        let upper_bits = (self.bank_2 as usize) << self.get_bank_2_shift();
+
+        if position < 0x4000 {
+            if self.advanced_banking { upper_bits } else { 0 }
+        }
+        else if self.multicart {
+            upper_bits | (self.bank_1 & 0x0F) as usize
+        }
+        else {
+            upper_bits | self.bank_1 as usize
+        }
+    }
+
+    fn read_ram(&self, sram: &SRAM, position: u16) -> u8 {
+        if self.is_ram_enabled() { sram.get(self.get_ram_bank(), position) } else { 0xFF }
+    }
+
+    fn write_ram(&mut self, sram: &mut SRAM, position: u16, value: u8) -> u8 {
+        if self.is_ram_enabled() { sram.set(self.get_ram_bank(), position, value) } else { 0xFF }
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        vec![self.ram_enabled as u8, self.bank_1, self.bank_2, self.advanced_banking as u8]
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), CartridgeError> {
+        check_state_length(state, 4)?;
+
+        self.ram_enabled = state[0] != 0;
+        self.bank_1 = state[1];
+        self.bank_2 = state[2];
+        self.advanced_banking = state[3] != 0;
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_to_bank_1() {
+        let mbc1 = MBC1::new(false);
+
+        assert_eq!(0, mbc1.get_rom_bank(0x0000));
+        assert_eq!(1, mbc1.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn selects_lower_bank_bits() {
+        let mut mbc1 = MBC1::new(false);
+
+        mbc1.write_rom(0x2000, 0x12);
+
+        assert_eq!(0x12, mbc1.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn only_uses_5_bits_of_lower_bank() {
+        let mut mbc1 = MBC1::new(false);
+
+        mbc1.write_rom(0x3FFF, 0xE3);
+
+        assert_eq!(0x03, mbc1.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn maps_bank_0_to_bank_1() {
+        let mut mbc1 = MBC1::new(false);
+
+        mbc1.write_rom(0x2000, 0x00);
+
+        assert_eq!(1, mbc1.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn maps_bank_20_to_bank_21() {
+        let mut mbc1 = MBC1::new(false);
+
+        mbc1.write_rom(0x2000, 0x00);
+        mbc1.write_rom(0x4000, 0x01);
+
+        assert_eq!(0x21, mbc1.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn selects_upper_bank_bits() {
+        let mut mbc1 = MBC1::new(false);
+
+        mbc1.write_rom(0x2000, 0x05);
+        mbc1.write_rom(0x5FFF, 0x03);
+
+        assert_eq!(0x65, mbc1.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn bank_0_area_is_fixed_in_simple_mode() {
+        let mut mbc1 = MBC1::new(false);
+
+        mbc1.write_rom(0x4000, 0x02);
+
+        assert_eq!(0, mbc1.get_rom_bank(0x0000));
+        assert_eq!(0, mbc1.get_ram_bank());
+    }
+
+    #[test]
+    fn bank_0_area_uses_upper_bits_in_advanced_mode() {
+        let mut mbc1 = MBC1::new(false);
+
+        mbc1.write_rom(0x4000, 0x02);
+        mbc1.write_rom(0x6000, 0x01);
+
+        assert_eq!(0x40, mbc1.get_rom_bank(0x0000));
+    }
+
+    #[test]
+    fn ram_bank_uses_upper_bits_in_advanced_mode() {
+        let mut mbc1 = MBC1::new(false);
+
+        mbc1.write_rom(0x4000, 0x02);
+        mbc1.write_rom(0x7FFF, 0x01);
+
+        assert_eq!(2, mbc1.get_ram_bank());
+    }
+
+    #[test]
+    fn ram_is_disabled_by_default() {
+        let mbc1 = MBC1::new(false);
+
+        assert!(!mbc1.is_ram_enabled());
+    }
+
+    #[test]
+    fn ram_is_enabled_by_0xa_in_lower_nibble() {
+        let mut mbc1 = MBC1::new(false);
+
+        mbc1.write_rom(0x1234, 0xFA);
+
+        assert!(mbc1.is_ram_enabled());
+    }
+
+    #[test]
+    fn ram_is_disabled_by_other_values() {
+        let mut mbc1 = MBC1::new(false);
+
+        mbc1.write_rom(0x0000, 0x0A);
+        mbc1.write_rom(0x1FFF, 0x0B);
+
+        assert!(!mbc1.is_ram_enabled());
+    }
+
+    #[test]
+    fn multicart_wires_upper_bits_from_bit_4() {
+        let mut mbc1 = MBC1::new(true);
+
+        mbc1.write_rom(0x2000, 0x12);
+        mbc1.write_rom(0x4000, 0x03);
+
+        assert_eq!(0x32, mbc1.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn multicart_bank_0_area_selects_game_in_advanced_mode() {
+        let mut mbc1 = MBC1::new(true);
+
+        mbc1.write_rom(0x4000, 0x01);
+        mbc1.write_rom(0x6000, 0x01);
+
+        assert_eq!(0x10, mbc1.get_rom_bank(0x0000));
+    }
+
+    #[test]
+    fn multicart_still_maps_bank_0_to_bank_1() {
+        let mut mbc1 = MBC1::new(true);
+
+        mbc1.write_rom(0x2000, 0x00);
+        mbc1.write_rom(0x4000, 0x01);
+
+        assert_eq!(0x11, mbc1.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn multicart_bank_0x10_maps_to_game_start() {
+        let mut mbc1 = MBC1::new(true);
+
+        mbc1.write_rom(0x2000, 0x10);
+
+        assert_eq!(0x00, mbc1.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn detects_multicart_from_second_header() {
+        let mut data = vec![vec![0; 0x4000]; 0x40];
+
+        data[0][0x0104..0x0134].copy_from_slice(&MBC1::NINTENDO_LOGO);
+        data[0x10][0x0104..0x0134].copy_from_slice(&MBC1::NINTENDO_LOGO);
+
+        assert!(MBC1::is_multicart(&data));
+    }
+
+    #[test]
+    fn does_not_detect_multicart_without_second_header() {
+        let mut data = vec![vec![0; 0x4000]; 0x40];
+
+        data[0][0x0104..0x0134].copy_from_slice(&MBC1::NINTENDO_LOGO);
+
+        assert!(!MBC1::is_multicart(&data));
+    }
+
+    #[test]
+    fn does_not_detect_multicart_for_other_sizes() {
+        let mut data = vec![vec![0; 0x4000]; 0x20];
+
+        data[0x10][0x0104..0x0134].copy_from_slice(&MBC1::NINTENDO_LOGO);
+
+        assert!(!MBC1::is_multicart(&data));
+    }
+
+    #[test]
+    fn gates_ram_with_enable_latch() {
+        let mut mbc1 = MBC1::new(false);
+        let mut sram = SRAM::new_with_size(0x2000);
+
+        sram.set(0, 0xA000, 0x12);
+
+        assert_eq!(0xFF, mbc1.read_ram(&sram, 0xA000));
+        assert_eq!(0xFF, mbc1.write_ram(&mut sram, 0xA000, 0x34));
+
+        mbc1.write_rom(0x0000, 0x0A);
+
+        assert_eq!(0x12, mbc1.read_ram(&sram, 0xA000));
+    }
+
+    #[test]
+    fn accesses_selected_ram_bank() {
+        let mut mbc1 = MBC1::new(false);
+        let mut sram = SRAM::new_with_size(0x8000);
+
+        mbc1.write_rom(0x0000, 0x0A);
+        mbc1.write_rom(0x6000, 0x01);
+        mbc1.write_rom(0x4000, 0x02);
+        mbc1.write_ram(&mut sram, 0xA000, 0x12);
+
+        assert_eq!(0x12, sram.get(2, 0xA000));
+    }
+
+    #[test]
+    fn state_round_trips() {
+        let mut mbc1 = MBC1::new(false);
+
+        mbc1.write_rom(0x0000, 0x0A);
+        mbc1.write_rom(0x2000, 0x05);
+        mbc1.write_rom(0x4000, 0x02);
+        mbc1.write_rom(0x6000, 0x01);
+
+        let mut loaded_mbc1 = MBC1::new(false);
+        loaded_mbc1.load_state(&mbc1.save_state()).unwrap();
+
+        assert_eq!(0x45, loaded_mbc1.get_rom_bank(0x4000));
+        assert_eq!(0x02, loaded_mbc1.get_ram_bank());
+        assert!(loaded_mbc1.is_ram_enabled());
+    }
+
+    #[test]
+    fn rejects_state_of_wrong_length() {
+        let mut mbc1 = MBC1::new(false);
+
+        assert!(mbc1.load_state(&[0; 3]).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+use crate::memory::cartridge_error::CartridgeError;
+use crate::memory::mbc::mapper::check_state_length;
+use crate::memory::mbc::Mapper;
+use crate::memory::sram::SRAM;
+
+/**
+    MBC2 has 512 half-bytes of RAM built into the controller, echoed across the whole of 0xA000-0xBFFF.
+    Only the lower nibble is stored; the upper nibble is not connected and reads as 1s.
+*/
+pub struct MBC2 {
+    ram_enabled: bool,
+    rom_bank: u8,           //0x0000-0x3FFF with address bit 8 set, 4 bits
+}
+
+impl MBC2 {
+
+    pub const RAM_SIZE: usize = 0x200;
+
+    const REGISTER_SELECT_BIT: u16 = 0x0100;
+
+    pub fn new() -> Self {
+        Self {
+            ram_enabled: false,
+            rom_bank: 1,
+        }
+    }
+}
+
+impl Mapper for MBC2 {
+    fn write_rom(&mut self, position: u16, value: u8) {
+        match position {
+            0x0000..=0x3FFF => {
+                if position & Self::REGISTER_SELECT_BIT == 0 {
+                    self.ram_enabled = value & 0x0F == 0x0A;
+                } else {
+                    self.rom_bank = value & 0x0F;
+
+                    if self.rom_bank == 0 {
+                        self.rom_bank = 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn get_rom_bank(&self, position: u16) -> usize {
+        if position < 0x4000 { 0 } else { self.rom_bank as usize }
+    }
+
+    fn read_ram(&self, sram: &SRAM, position: u16) -> u8 {
+        if self.ram_enabled { sram.get(0, position) | 0xF0 } else { 0xFF }
+    }
+
+    fn write_ram(&mut self, sram: &mut SRAM, position: u16, value: u8) -> u8 {
+        if self.ram_enabled { sram.set(0, position, value & 0x0F) | 0xF0 } else { 0xFF }
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        vec![self.ram_enabled as u8, self.rom_bank]
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), CartridgeError> {
+        check_state_length(state, 2)?;
+
+        self.ram_enabled = state[0] != 0;
+        self.rom_bank = state[1];
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_to_bank_1() {
+        let mbc2 = MBC2::new();
+
+        assert_eq!(0, mbc2.get_rom_bank(0x0000));
+        assert_eq!(1, mbc2.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn selects_rom_bank_with_address_bit_8_set() {
+        let mut mbc2 = MBC2::new();
+
+        mbc2.write_rom(0x2100, 0x05);
+
+        assert_eq!(0x05, mbc2.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn only_uses_4_bits_of_rom_bank() {
+        let mut mbc2 = MBC2::new();
+
+        mbc2.write_rom(0x0100, 0xF3);
+
+        assert_eq!(0x03, mbc2.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn maps_bank_0_to_bank_1() {
+        let mut mbc2 = MBC2::new();
+
+        mbc2.write_rom(0x3FFF, 0x10);
+
+        assert_eq!(0x01, mbc2.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn rom_bank_write_does_not_enable_ram() {
+        let mut mbc2 = MBC2::new();
+        let sram = SRAM::new_with_size(MBC2::RAM_SIZE);
+
+        mbc2.write_rom(0x0100, 0x0A);
+
+        assert_eq!(0xFF, mbc2.read_ram(&sram, 0xA000));
+        assert_eq!(0x0A, mbc2.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn ram_enable_write_does_not_select_bank() {
+        let mut mbc2 = MBC2::new();
+
+        mbc2.write_rom(0x2000, 0x0A);
+
+        assert_eq!(0x01, mbc2.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn ignores_writes_above_0x3fff() {
+        let mut mbc2 = MBC2::new();
+
+        mbc2.write_rom(0x4100, 0x05);
+
+        assert_eq!(0x01, mbc2.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn ram_is_disabled_by_default() {
+        let mbc2 = MBC2::new();
+        let mut sram = SRAM::new_with_size(MBC2::RAM_SIZE);
+
+        sram.set(0, 0xA000, 0x05);
+
+        assert_eq!(0xFF, mbc2.read_ram(&sram, 0xA000));
+    }
+
+    #[test]
+    fn ram_stores_lower_nibble_and_reads_upper_nibble_as_1() {
+        let mut mbc2 = MBC2::new();
+        let mut sram = SRAM::new_with_size(MBC2::RAM_SIZE);
+
+        mbc2.write_rom(0x0000, 0x0A);
+        mbc2.write_ram(&mut sram, 0xA000, 0x35);
+
+        assert_eq!(0x05, sram.get(0, 0xA000));
+        assert_eq!(0xF5, mbc2.read_ram(&sram, 0xA000));
+    }
+
+    #[test]
+    fn ram_is_echoed_every_512_bytes() {
+        let mut mbc2 = MBC2::new();
+        let mut sram = SRAM::new_with_size(MBC2::RAM_SIZE);
+
+        mbc2.write_rom(0x0000, 0x0A);
+        mbc2.write_ram(&mut sram, 0xA001, 0x07);
+
+        assert_eq!(0xF7, mbc2.read_ram(&sram, 0xA201));
+        assert_eq!(0xF7, mbc2.read_ram(&sram, 0xBE01));
+    }
+
+    #[test]
+    fn battery_data_is_512_bytes() {
+        let mbc2 = MBC2::new();
+        let sram = SRAM::new_with_size(MBC2::RAM_SIZE);
+
+        assert_eq!(0x200, mbc2.get_battery_data(&sram).len());
+    }
+
+    #[test]
+    fn state_round_trips() {
+        let mut mbc2 = MBC2::new();
+
+        mbc2.write_rom(0x0000, 0x0A);
+        mbc2.write_rom(0x0100, 0x07);
+
+        let mut loaded_mbc2 = MBC2::new();
+        loaded_mbc2.load_state(&mbc2.save_state()).unwrap();
+
+        assert_eq!(0x07, loaded_mbc2.get_rom_bank(0x4000));
+        assert!(loaded_mbc2.ram_enabled);
+    }
+}
@@ -0,0 +1,375 @@
+use crate::memory::cartridge_error::CartridgeError;
+use crate::memory::mbc::mapper::check_state_length;
+use crate::memory::mbc::{Mapper, RealTimeClock};
+use crate::memory::sram::SRAM;
+
+pub struct MBC3 {
+    ram_and_rtc_enabled: bool,
+    rom_bank: u8,           //0x2000-0x3FFF, 7 bits
+    ram_bank_or_rtc: u8,    //0x4000-0x5FFF, 0x00-0x03 for RAM or 0x08-0x0C for RTC
+    last_latch_write: u8,   //0x6000-0x7FFF
+
+    rtc: RealTimeClock,
+    has_rtc: bool,
+}
+
+impl MBC3 {
+    pub fn new(has_rtc: bool) -> Self {
+        Self {
+            ram_and_rtc_enabled: false,
+            rom_bank: 1,
+            ram_bank_or_rtc: 0,
+            last_latch_write: 0xFF,
+
+            rtc: RealTimeClock::new(),
+            has_rtc,
+        }
+    }
+
+    fn get_ram_bank(&self) -> usize {
+        (self.ram_bank_or_rtc & 0x03) as usize
+    }
+
+    fn is_ram_enabled(&self) -> bool {
+        self.ram_and_rtc_enabled && self.ram_bank_or_rtc <= 0x03
+    }
+
+    fn read_rtc(&self) -> Option<u8> {
+        if self.is_rtc_selected() {
+            Some(self.rtc.get(self.ram_bank_or_rtc))
+        } else {
+            None
+        }
+    }
+
+    fn write_rtc(&mut self, value: u8) -> bool {
+        if self.is_rtc_selected() {
+            self.rtc.set(self.ram_bank_or_rtc, value);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn get_rtc_save_footer(&self) -> Option<Vec<u8>> {
+        if self.has_rtc {
+            Some(self.rtc.get_save_footer())
+        } else {
+            None
+        }
+    }
+
+    fn load_rtc_save_footer(&mut self, footer: &[u8]) {
+        if self.has_rtc {
+            self.rtc.load_save_footer(footer);
+        }
+    }
+
+    fn is_rtc_selected(&self) -> bool {
+        self.has_rtc &&
+            self.ram_and_rtc_enabled &&
+            self.ram_bank_or_rtc >= RealTimeClock::SECONDS &&
+            self.ram_bank_or_rtc <= RealTimeClock::DAYS_HIGH
+    }
+}
+
+impl Mapper for MBC3 {
+    fn write_rom(&mut self, position: u16, value: u8) {
+        match position {
+            0x0000..=0x1FFF => self.ram_and_rtc_enabled = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => {
+                self.rom_bank = value & 0x7F;
+
+                if self.rom_bank == 0 {
+                    self.rom_bank = 1;
+                }
+            }
+            0x4000..=0x5FFF => self.ram_bank_or_rtc = value,
+            0x6000..=0x7FFF => {
+                if self.last_latch_write == 0x00 && value == 0x01 {
+                    self.rtc.latch();
+                }
+
+                self.last_latch_write = value;
+            }
+            _ => {}
+        }
+    }
+
+    fn get_rom_bank(&self, position: u16) -> usize {
+        if position < 0x4000 { 0 } else { self.rom_bank as usize }
+    }
+
+    fn read_ram(&self, sram: &SRAM, position: u16) -> u8 {
+        match self.read_rtc() {
+            Some(value) => value,
+            None => if self.is_ram_enabled() { sram.get(self.get_ram_bank(), position) } else { 0xFF }
+        }
+    }
+
+    fn write_ram(&mut self, sram: &mut SRAM, position: u16, value: u8) -> u8 {
+        if self.write_rtc(value) { 0xFF }
+        else if self.is_ram_enabled() { sram.set(self.get_ram_bank(), position, value) }
+        else { 0xFF }
+    }
+
+    fn clock(&mut self) {
+        self.rtc.clock();
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        let mut state = vec![self.ram_and_rtc_enabled as u8, self.rom_bank, self.ram_bank_or_rtc, self.last_latch_write];
+        state.extend(self.rtc.save_state());
+
+        state
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), CartridgeError> {
+        check_state_length(state, 4 + RealTimeClock::STATE_SIZE)?;
+
+        self.ram_and_rtc_enabled = state[0] != 0;
+        self.rom_bank = state[1];
+        self.ram_bank_or_rtc = state[2];
+        self.last_latch_write = state[3];
+        self.rtc.load_state(&state[4..]);
+
+        Ok(())
+    }
+
+    fn get_battery_data(&self, sram: &SRAM) -> Vec<u8> {
+        let mut data = sram.get_data();
+
+        match self.get_rtc_save_footer() {
+            Some(footer) => data.extend(footer),
+            None => {}
+        }
+
+        data
+    }
+
+    fn load_battery_data(&mut self, sram: &mut SRAM, data: &[u8]) {
+        let ram_size = sram.get_size().min(data.len());
+        sram.load_data(&data[..ram_size]);
+
+        self.load_rtc_save_footer(&data[ram_size..]);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_to_bank_1() {
+        let mbc3 = MBC3::new(true);
+
+        assert_eq!(0, mbc3.get_rom_bank(0x0000));
+        assert_eq!(1, mbc3.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn selects_7_bit_rom_bank() {
+        let mut mbc3 = MBC3::new(true);
+
+        mbc3.write_rom(0x2000, 0xFF);
+
+        assert_eq!(0x7F, mbc3.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn maps_bank_0_to_bank_1() {
+        let mut mbc3 = MBC3::new(true);
+
+        mbc3.write_rom(0x3FFF, 0x00);
+
+        assert_eq!(1, mbc3.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn does_not_remap_bank_0x20() {
+        let mut mbc3 = MBC3::new(true);
+
+        mbc3.write_rom(0x2000, 0x20);
+
+        assert_eq!(0x20, mbc3.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn selects_ram_banks_0_to_3() {
+        let mut mbc3 = MBC3::new(true);
+
+        mbc3.write_rom(0x0000, 0x0A);
+        mbc3.write_rom(0x4000, 0x03);
+
+        assert!(mbc3.is_ram_enabled());
+        assert_eq!(3, mbc3.get_ram_bank());
+    }
+
+    #[test]
+    fn ram_is_disabled_by_default() {
+        let mbc3 = MBC3::new(true);
+
+        assert!(!mbc3.is_ram_enabled());
+        assert_eq!(None, mbc3.read_rtc());
+    }
+
+    #[test]
+    fn selecting_rtc_register_unmaps_ram() {
+        let mut mbc3 = MBC3::new(true);
+
+        mbc3.write_rom(0x0000, 0x0A);
+        mbc3.write_rom(0x4000, 0x08);
+
+        assert!(!mbc3.is_ram_enabled());
+        assert_eq!(Some(0), mbc3.read_rtc());
+    }
+
+    #[test]
+    fn rtc_is_not_readable_when_disabled() {
+        let mut mbc3 = MBC3::new(true);
+
+        mbc3.write_rom(0x4000, 0x08);
+
+        assert_eq!(None, mbc3.read_rtc());
+        assert!(!mbc3.write_rtc(0x12));
+    }
+
+    #[test]
+    fn rtc_is_not_mapped_without_rtc_chip() {
+        let mut mbc3 = MBC3::new(false);
+
+        mbc3.write_rom(0x0000, 0x0A);
+        mbc3.write_rom(0x4000, 0x08);
+
+        assert_eq!(None, mbc3.read_rtc());
+        assert_eq!(None, mbc3.get_rtc_save_footer());
+    }
+
+    #[test]
+    fn writes_selected_rtc_register() {
+        let mut mbc3 = MBC3::new(true);
+
+        mbc3.write_rom(0x0000, 0x0A);
+        mbc3.write_rom(0x4000, 0x09);
+
+        assert!(mbc3.write_rtc(0x12));
+        assert_eq!(Some(0x12), mbc3.read_rtc());
+    }
+
+    #[test]
+    fn latches_on_0_then_1() {
+        let mut mbc3 = MBC3::new(true);
+
+        mbc3.write_rom(0x0000, 0x0A);
+        mbc3.write_rom(0x4000, 0x08);
+
+        for _ in 0..RealTimeClock::M_CYCLES_PER_SECOND {
+            mbc3.clock();
+        }
+        assert_eq!(Some(0), mbc3.read_rtc());
+
+        mbc3.write_rom(0x6000, 0x00);
+        mbc3.write_rom(0x6000, 0x01);
+
+        assert_eq!(Some(1), mbc3.read_rtc());
+    }
+
+    #[test]
+    fn does_not_latch_on_1_alone() {
+        let mut mbc3 = MBC3::new(true);
+
+        mbc3.write_rom(0x0000, 0x0A);
+        mbc3.write_rom(0x4000, 0x08);
+
+        for _ in 0..RealTimeClock::M_CYCLES_PER_SECOND {
+            mbc3.clock();
+        }
+
+        mbc3.write_rom(0x6000, 0x01);
+        mbc3.write_rom(0x6000, 0x01);
+
+        assert_eq!(Some(0), mbc3.read_rtc());
+    }
+
+    #[test]
+    fn accesses_selected_ram_bank() {
+        let mut mbc3 = MBC3::new(true);
+        let mut sram = SRAM::new_with_size(0x8000);
+
+        mbc3.write_rom(0x0000, 0x0A);
+        mbc3.write_rom(0x4000, 0x03);
+        mbc3.write_ram(&mut sram, 0xA000, 0x12);
+
+        assert_eq!(0x12, sram.get(3, 0xA000));
+        assert_eq!(0x12, mbc3.read_ram(&sram, 0xA000));
+    }
+
+    #[test]
+    fn maps_rtc_over_ram() {
+        let mut mbc3 = MBC3::new(true);
+        let mut sram = SRAM::new_with_size(0x8000);
+
+        mbc3.write_rom(0x0000, 0x0A);
+        mbc3.write_rom(0x4000, 0x09);
+        mbc3.write_ram(&mut sram, 0xA000, 0x12);
+
+        assert_eq!(0x00, sram.get(0, 0xA000));
+        assert_eq!(0x12, mbc3.read_ram(&sram, 0xA000));
+    }
+
+    #[test]
+    fn battery_data_has_rtc_footer() {
+        let mbc3 = MBC3::new(true);
+        let sram = SRAM::new_with_size(0x8000);
+
+        assert_eq!(0x8000 + 48, mbc3.get_battery_data(&sram).len());
+    }
+
+    #[test]
+    fn battery_data_round_trips_rtc() {
+        let mut mbc3 = MBC3::new(true);
+        let mut sram = SRAM::new_with_size(0x2000);
+
+        mbc3.write_rom(0x0000, 0x0A);
+        mbc3.write_rom(0x4000, 0x09);
+        mbc3.write_ram(&mut sram, 0xA000, 0x2A);
+        mbc3.write_rom(0x4000, 0x00);
+        mbc3.write_ram(&mut sram, 0xA000, 0x12);
+
+        let data = mbc3.get_battery_data(&sram);
+
+        let mut loaded_mbc3 = MBC3::new(true);
+        let mut loaded_sram = SRAM::new_with_size(0x2000);
+        loaded_mbc3.load_battery_data(&mut loaded_sram, &data);
+
+        loaded_mbc3.write_rom(0x0000, 0x0A);
+        assert_eq!(0x12, loaded_mbc3.read_ram(&loaded_sram, 0xA000));
+
+        loaded_mbc3.write_rom(0x4000, 0x09);
+        assert_eq!(0x2A, loaded_mbc3.read_ram(&loaded_sram, 0xA000));
+    }
+
+    #[test]
+    fn state_round_trips() {
+        let mut mbc3 = MBC3::new(true);
+
+        mbc3.write_rom(0x0000, 0x0A);
+        mbc3.write_rom(0x2000, 0x45);
+        mbc3.write_rom(0x4000, 0x0A);
+        mbc3.write_rtc(0x07);
+
+        let mut loaded_mbc3 = MBC3::new(true);
+        loaded_mbc3.load_state(&mbc3.save_state()).unwrap();
+
+        assert_eq!(0x45, loaded_mbc3.get_rom_bank(0x4000));
+        assert_eq!(Some(0x07), loaded_mbc3.read_rtc());
+    }
+
+    #[test]
+    fn rejects_state_of_wrong_length() {
+        let mut mbc3 = MBC3::new(true);
+
+        assert!(mbc3.load_state(&[0; 4]).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use crate::memory::cartridge_error::CartridgeError;
+use crate::memory::mbc::mapper::check_state_length;
+use crate::memory::mbc::{Mapper, RumbleCallback};
+use crate::memory::sram::SRAM;
+
+pub struct MBC5 {
+    ram_enabled: bool,
+    rom_bank: u16,          //0x2000-0x2FFF low 8 bits, 0x3000-0x3FFF bit 8
+    ram_bank: u8,           //0x4000-0x5FFF, 4 bits, bit 3 drives the motor on rumble carts
+
+    has_rumble: bool,
+    rumble: bool,
+    rumble_callback: Option<RumbleCallback>,
+}
+
+impl MBC5 {
+
+    const RUMBLE_BIT: u8 = 0x08;
+
+    pub fn new(has_rumble: bool) -> Self {
+        Self {
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+
+            has_rumble,
+            rumble: false,
+            rumble_callback: None,
+        }
+    }
+
+    fn get_ram_bank(&self) -> usize {
+        if self.has_rumble {
+            (self.ram_bank & !Self::RUMBLE_BIT) as usize
+        } else {
+            self.ram_bank as usize
+        }
+    }
+
+    fn set_rumble(&mut self, rumble: bool) {
+        if self.rumble != rumble {
+            self.rumble = rumble;
+
+            match &self.rumble_callback {
+                Some(callback) => callback(rumble),
+                None => {}
+            }
+        }
+    }
+}
+
+impl Mapper for MBC5 {
+    fn write_rom(&mut self, position: u16, value: u8) {
+        match position {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+            0x2000..=0x2FFF => self.rom_bank = (self.rom_bank & 0x100) | value as u16,
+            0x3000..=0x3FFF => self.rom_bank = (self.rom_bank & 0xFF) | ((value as u16 & 0x01) << 8),
+            0x4000..=0x5FFF => {
+                self.ram_bank = value & 0x0F;
+
+                if self.has_rumble {
+                    self.set_rumble(value & Self::RUMBLE_BIT != 0);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn get_rom_bank(&self, position: u16) -> usize {
+        if position < 0x4000 { 0 } else { self.rom_bank as usize } //unlike older controllers bank 0 can be mapped here
+    }
+
+    fn read_ram(&self, sram: &SRAM, position: u16) -> u8 {
+        if self.ram_enabled { sram.get(self.get_ram_bank(), position) } else { 0xFF }
+    }
+
+    fn write_ram(&mut self, sram: &mut SRAM, position: u16, value: u8) -> u8 {
+        if self.ram_enabled { sram.set(self.get_ram_bank(), position, value) } else { 0xFF }
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        let rom_bank = self.rom_bank.to_le_bytes();
+
+        vec![self.ram_enabled as u8, rom_bank[0], rom_bank[1], self.ram_bank]
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), CartridgeError> {
+        check_state_length(state, 4)?;
+
+        self.ram_enabled = state[0] != 0;
+        self.rom_bank = u16::from_le_bytes([state[1], state[2]]) & 0x1FF;
+        self.ram_bank = state[3] & 0x0F;
+
+        if self.has_rumble {
+            self.set_rumble(self.ram_bank & Self::RUMBLE_BIT != 0);
+        }
+
+        Ok(())
+    }
+
+    fn set_rumble_callback(&mut self, callback: RumbleCallback) {
+        self.rumble_callback = Some(callback);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use super::*;
+
+    #[test]
+    fn defaults_to_bank_1() {
+        let mbc5 = MBC5::new(false);
+
+        assert_eq!(0, mbc5.get_rom_bank(0x0000));
+        assert_eq!(1, mbc5.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn selects_low_8_bits_of_rom_bank() {
+        let mut mbc5 = MBC5::new(false);
+
+        mbc5.write_rom(0x2000, 0xAB);
+
+        assert_eq!(0xAB, mbc5.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn selects_bit_8_of_rom_bank() {
+        let mut mbc5 = MBC5::new(false);
+
+        mbc5.write_rom(0x2FFF, 0x12);
+        mbc5.write_rom(0x3000, 0xFF);
+
+        assert_eq!(0x112, mbc5.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn low_bits_write_keeps_bit_8() {
+        let mut mbc5 = MBC5::new(false);
+
+        mbc5.write_rom(0x3000, 0x01);
+        mbc5.write_rom(0x2000, 0x34);
+
+        assert_eq!(0x134, mbc5.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn allows_bank_0_in_switchable_area() {
+        let mut mbc5 = MBC5::new(false);
+
+        mbc5.write_rom(0x2000, 0x00);
+
+        assert_eq!(0x00, mbc5.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn ram_is_disabled_by_default() {
+        let mbc5 = MBC5::new(false);
+        let mut sram = SRAM::new_with_size(0x2000);
+
+        sram.set(0, 0xA000, 0x12);
+
+        assert_eq!(0xFF, mbc5.read_ram(&sram, 0xA000));
+    }
+
+    #[test]
+    fn accesses_all_16_ram_banks() {
+        let mut mbc5 = MBC5::new(false);
+        let mut sram = SRAM::new_with_size(0x20000);
+
+        mbc5.write_rom(0x0000, 0x0A);
+        mbc5.write_rom(0x4000, 0x0F);
+        mbc5.write_ram(&mut sram, 0xA000, 0x12);
+
+        assert_eq!(0x12, sram.get(0x0F, 0xA000));
+        assert_eq!(0x12, mbc5.read_ram(&sram, 0xA000));
+    }
+
+    #[test]
+    fn rumble_bit_is_not_a_ram_bank_bit_on_rumble_carts() {
+        let mut mbc5 = MBC5::new(true);
+        let mut sram = SRAM::new_with_size(0x20000);
+
+        mbc5.write_rom(0x0000, 0x0A);
+        mbc5.write_rom(0x4000, 0x0B);
+        mbc5.write_ram(&mut sram, 0xA000, 0x12);
+
+        assert_eq!(0x12, sram.get(0x03, 0xA000));
+        assert!(mbc5.rumble);
+    }
+
+    #[test]
+    fn does_not_rumble_without_motor() {
+        let mut mbc5 = MBC5::new(false);
+
+        mbc5.write_rom(0x4000, 0x08);
+
+        assert!(!mbc5.rumble);
+    }
+
+    #[test]
+    fn rumble_callback_sees_motor_changes() {
+        let mut mbc5 = MBC5::new(true);
+        let rumble = Arc::new(AtomicBool::new(false));
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let callback_rumble = rumble.clone();
+        let callback_calls = calls.clone();
+        mbc5.set_rumble_callback(Arc::new(move |state| {
+            callback_rumble.store(state, Ordering::Relaxed);
+            callback_calls.fetch_add(1, Ordering::Relaxed);
+        }));
+
+        mbc5.write_rom(0x4000, 0x08);
+        assert!(rumble.load(Ordering::Relaxed));
+
+        mbc5.write_rom(0x4000, 0x09); //still on, so no new call
+        mbc5.write_rom(0x4000, 0x01);
+        assert!(!rumble.load(Ordering::Relaxed));
+        assert_eq!(2, calls.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn state_round_trips() {
+        let mut mbc5 = MBC5::new(true);
+
+        mbc5.write_rom(0x0000, 0x0A);
+        mbc5.write_rom(0x2000, 0x23);
+        mbc5.write_rom(0x3000, 0x01);
+        mbc5.write_rom(0x4000, 0x0A);
+
+        let mut loaded_mbc5 = MBC5::new(true);
+        loaded_mbc5.load_state(&mbc5.save_state()).unwrap();
+
+        assert_eq!(0x123, loaded_mbc5.get_rom_bank(0x4000));
+        assert_eq!(0x02, loaded_mbc5.get_ram_bank());
+        assert!(loaded_mbc5.rumble);
+    }
+}
@@ -0,0 +1,15 @@
+mod mapper;
+mod rom_only;
+mod mbc1;
+mod mbc2;
+mod mbc3;
+mod mbc5;
+mod real_time_clock;
+
+pub use mapper::{create_mapper, Mapper, RumbleCallback};
+pub use rom_only::RomOnly;
+pub use mbc1::MBC1;
+pub use mbc2::MBC2;
+pub use mbc3::MBC3;
+pub use mbc5::MBC5;
+pub use real_time_clock::RealTimeClock;
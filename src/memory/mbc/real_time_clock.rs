@@ -0,0 +1,429 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/**
+    The MBC3 clock is advanced by emulated machine cycles rather than the host's clock so that
+    behaviour is deterministic and keeps pace with the emulation speed.
+*/
+pub struct RealTimeClock {
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    days: u16,
+    halted: bool,
+    day_carry: bool,
+
+    cycle_counter: u32,
+    latched: [u8; 5],
+}
+
+impl RealTimeClock {
+
+    pub const M_CYCLES_PER_SECOND: u32 = 1_048_576;
+    pub const SAVE_FOOTER_SIZE: usize = 48;
+    pub const STATE_SIZE: usize = 14;
+
+    pub const SECONDS: u8 = 0x08;
+    pub const MINUTES: u8 = 0x09;
+    pub const HOURS: u8 = 0x0A;
+    pub const DAYS_LOW: u8 = 0x0B;
+    pub const DAYS_HIGH: u8 = 0x0C;
+
+    pub fn new() -> Self {
+        Self {
+            seconds: 0,
+            minutes: 0,
+            hours: 0,
+            days: 0,
+            halted: false,
+            day_carry: false,
+
+            cycle_counter: 0,
+            latched: [0; 5],
+        }
+    }
+
+    pub fn clock(&mut self) {
+        if self.halted {
+            return;
+        }
+
+        self.cycle_counter += 1;
+
+        if self.cycle_counter >= Self::M_CYCLES_PER_SECOND {
+            self.cycle_counter = 0;
+            self.tick_second();
+        }
+    }
+
+    pub fn latch(&mut self) {
+        for register in Self::SECONDS..=Self::DAYS_HIGH {
+            self.latched[(register - Self::SECONDS) as usize] = self.get_live_register(register);
+        }
+    }
+
+    pub fn get(&self, register: u8) -> u8 {
+        if register >= Self::SECONDS && register <= Self::DAYS_HIGH {
+            self.latched[(register - Self::SECONDS) as usize]
+        } else {
+            0xFF
+        }
+    }
+
+    pub fn set(&mut self, register: u8, value: u8) {
+        match register {
+            Self::SECONDS => {
+                self.seconds = value & 0x3F;
+                self.cycle_counter = 0; //writing the seconds resets the sub-second divider
+            }
+            Self::MINUTES => self.minutes = value & 0x3F,
+            Self::HOURS => self.hours = value & 0x1F,
+            Self::DAYS_LOW => self.days = (self.days & 0x100) | value as u16,
+            Self::DAYS_HIGH => {
+                self.days = (self.days & 0xFF) | (((value & 0x01) as u16) << 8);
+                self.halted = value & 0x40 != 0;
+                self.day_carry = value & 0x80 != 0;
+            }
+            _ => return
+        }
+
+        self.latched[(register - Self::SECONDS) as usize] = self.get_live_register(register);
+    }
+
+    /**
+        Produces the 48 byte footer most emulators append to MBC3 saves: the live registers and then
+        the latched registers as little-endian u32s, followed by a u64 UNIX timestamp.
+        The timestamp is only written for compatibility since the clock here runs on emulated time.
+    */
+    pub fn get_save_footer(&self) -> Vec<u8> {
+        let mut footer = Vec::with_capacity(Self::SAVE_FOOTER_SIZE);
+
+        for register in Self::SECONDS..=Self::DAYS_HIGH {
+            footer.extend_from_slice(&(self.get_live_register(register) as u32).to_le_bytes());
+        }
+        for latched_register in self.latched {
+            footer.extend_from_slice(&(latched_register as u32).to_le_bytes());
+        }
+
+        let timestamp = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs(),
+            Err(_) => 0
+        };
+        footer.extend_from_slice(&timestamp.to_le_bytes());
+
+        footer
+    }
+
+    pub fn load_save_footer(&mut self, footer: &[u8]) {
+        if footer.len() < 40 { //some emulators write a 44 byte footer with a 32 bit timestamp
+            return;
+        }
+
+        for register in Self::SECONDS..=Self::DAYS_HIGH {
+            let index = (register - Self::SECONDS) as usize;
+
+            self.set(register, footer[index * 4]);
+            self.latched[index] = footer[(index + 5) * 4];
+        }
+    }
+
+    /**
+        Unlike the save footer this includes the progress towards the next second, so it must be loaded
+        with exactly `STATE_SIZE` bytes.
+    */
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut state = Vec::with_capacity(Self::STATE_SIZE);
+
+        for register in Self::SECONDS..=Self::DAYS_HIGH {
+            state.push(self.get_live_register(register));
+        }
+        state.extend_from_slice(&self.latched);
+        state.extend_from_slice(&self.cycle_counter.to_le_bytes());
+
+        state
+    }
+
+    pub fn load_state(&mut self, state: &[u8]) {
+        for register in Self::SECONDS..=Self::DAYS_HIGH {
+            self.set(register, state[(register - Self::SECONDS) as usize]);
+        }
+        self.latched.copy_from_slice(&state[5..10]);
+        self.cycle_counter = u32::from_le_bytes([state[10], state[11], state[12], state[13]]);
+    }
+
+    fn get_live_register(&self, register: u8) -> u8 {
+        match register {
+            Self::SECONDS => self.seconds,
+            Self::MINUTES => self.minutes,
+            Self::HOURS => self.hours,
+            Self::DAYS_LOW => (self.days & 0xFF) as u8,
+            Self::DAYS_HIGH => {
+                let halted = if self.halted { 0x40 } else { 0x00 };
+                let day_carry = if self.day_carry { 0x80 } else { 0x00 };
+
+                ((self.days >> 8) as u8 & 0x01) | halted | day_carry
+            }
+            _ => 0xFF
+        }
+    }
+
+    /**
+        Out-of-range values written by the game count up to the register's bit width and then wrap
+        to 0 without carrying, the same as the real chip.
+    */
+    fn tick_second(&mut self) {
+        self.seconds = (self.seconds + 1) & 0x3F;
+        if self.seconds != 60 {
+            return;
+        }
+        self.seconds = 0;
+
+        self.minutes = (self.minutes + 1) & 0x3F;
+        if self.minutes != 60 {
+            return;
+        }
+        self.minutes = 0;
+
+        self.hours = (self.hours + 1) & 0x1F;
+        if self.hours != 24 {
+            return;
+        }
+        self.hours = 0;
+
+        self.days += 1;
+        if self.days > 0x1FF {
+            self.days = 0;
+            self.day_carry = true;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock_seconds(rtc: &mut RealTimeClock, seconds: u32) {
+        for _ in 0..seconds * RealTimeClock::M_CYCLES_PER_SECOND {
+            rtc.clock();
+        }
+    }
+
+    #[test]
+    fn advances_one_second_per_second_of_cycles() {
+        let mut rtc = RealTimeClock::new();
+
+        clock_seconds(&mut rtc, 1);
+        rtc.latch();
+
+        assert_eq!(1, rtc.get(RealTimeClock::SECONDS));
+    }
+
+    #[test]
+    fn does_not_advance_before_a_full_second() {
+        let mut rtc = RealTimeClock::new();
+
+        for _ in 0..RealTimeClock::M_CYCLES_PER_SECOND - 1 {
+            rtc.clock();
+        }
+        rtc.latch();
+
+        assert_eq!(0, rtc.get(RealTimeClock::SECONDS));
+    }
+
+    #[test]
+    fn does_not_advance_when_halted() {
+        let mut rtc = RealTimeClock::new();
+
+        rtc.set(RealTimeClock::DAYS_HIGH, 0x40);
+        clock_seconds(&mut rtc, 1);
+        rtc.latch();
+
+        assert_eq!(0, rtc.get(RealTimeClock::SECONDS));
+        assert_eq!(0x40, rtc.get(RealTimeClock::DAYS_HIGH));
+    }
+
+    #[test]
+    fn reads_return_latched_values() {
+        let mut rtc = RealTimeClock::new();
+
+        rtc.latch();
+        clock_seconds(&mut rtc, 1);
+
+        assert_eq!(0, rtc.get(RealTimeClock::SECONDS));
+    }
+
+    #[test]
+    fn seconds_carry_into_minutes() {
+        let mut rtc = RealTimeClock::new();
+
+        rtc.set(RealTimeClock::SECONDS, 59);
+        clock_seconds(&mut rtc, 1);
+        rtc.latch();
+
+        assert_eq!(0, rtc.get(RealTimeClock::SECONDS));
+        assert_eq!(1, rtc.get(RealTimeClock::MINUTES));
+    }
+
+    #[test]
+    fn hours_carry_into_days() {
+        let mut rtc = RealTimeClock::new();
+
+        rtc.set(RealTimeClock::SECONDS, 59);
+        rtc.set(RealTimeClock::MINUTES, 59);
+        rtc.set(RealTimeClock::HOURS, 23);
+        rtc.set(RealTimeClock::DAYS_LOW, 0xFF);
+        clock_seconds(&mut rtc, 1);
+        rtc.latch();
+
+        assert_eq!(0, rtc.get(RealTimeClock::HOURS));
+        assert_eq!(0x00, rtc.get(RealTimeClock::DAYS_LOW));
+        assert_eq!(0x01, rtc.get(RealTimeClock::DAYS_HIGH));
+    }
+
+    #[test]
+    fn day_overflow_sets_carry() {
+        let mut rtc = RealTimeClock::new();
+
+        rtc.set(RealTimeClock::SECONDS, 59);
+        rtc.set(RealTimeClock::MINUTES, 59);
+        rtc.set(RealTimeClock::HOURS, 23);
+        rtc.set(RealTimeClock::DAYS_LOW, 0xFF);
+        rtc.set(RealTimeClock::DAYS_HIGH, 0x01);
+        clock_seconds(&mut rtc, 1);
+        rtc.latch();
+
+        assert_eq!(0x00, rtc.get(RealTimeClock::DAYS_LOW));
+        assert_eq!(0x80, rtc.get(RealTimeClock::DAYS_HIGH));
+    }
+
+    #[test]
+    fn day_carry_stays_set_until_cleared() {
+        let mut rtc = RealTimeClock::new();
+
+        rtc.set(RealTimeClock::DAYS_HIGH, 0x80);
+        clock_seconds(&mut rtc, 1);
+        rtc.latch();
+        assert_eq!(0x80, rtc.get(RealTimeClock::DAYS_HIGH));
+
+        rtc.set(RealTimeClock::DAYS_HIGH, 0x00);
+        assert_eq!(0x00, rtc.get(RealTimeClock::DAYS_HIGH));
+    }
+
+    #[test]
+    fn invalid_seconds_wrap_without_carry() {
+        let mut rtc = RealTimeClock::new();
+
+        rtc.set(RealTimeClock::SECONDS, 63);
+        clock_seconds(&mut rtc, 1);
+        rtc.latch();
+
+        assert_eq!(0, rtc.get(RealTimeClock::SECONDS));
+        assert_eq!(0, rtc.get(RealTimeClock::MINUTES));
+    }
+
+    #[test]
+    fn writing_seconds_resets_sub_second_counter() {
+        let mut rtc = RealTimeClock::new();
+
+        for _ in 0..RealTimeClock::M_CYCLES_PER_SECOND - 1 {
+            rtc.clock();
+        }
+        rtc.set(RealTimeClock::SECONDS, 0);
+        rtc.clock();
+        rtc.latch();
+
+        assert_eq!(0, rtc.get(RealTimeClock::SECONDS));
+    }
+
+    #[test]
+    fn registers_are_masked_on_write() {
+        let mut rtc = RealTimeClock::new();
+
+        rtc.set(RealTimeClock::SECONDS, 0xFF);
+        rtc.set(RealTimeClock::MINUTES, 0xFF);
+        rtc.set(RealTimeClock::HOURS, 0xFF);
+        rtc.set(RealTimeClock::DAYS_HIGH, 0xFF);
+
+        assert_eq!(0x3F, rtc.get(RealTimeClock::SECONDS));
+        assert_eq!(0x3F, rtc.get(RealTimeClock::MINUTES));
+        assert_eq!(0x1F, rtc.get(RealTimeClock::HOURS));
+        assert_eq!(0xC1, rtc.get(RealTimeClock::DAYS_HIGH));
+    }
+
+    #[test]
+    fn save_footer_is_48_bytes() {
+        let rtc = RealTimeClock::new();
+
+        assert_eq!(RealTimeClock::SAVE_FOOTER_SIZE, rtc.get_save_footer().len());
+    }
+
+    #[test]
+    fn save_footer_stores_live_then_latched_registers() {
+        let mut rtc = RealTimeClock::new();
+
+        rtc.set(RealTimeClock::MINUTES, 0x12);
+        rtc.latch();
+        rtc.set(RealTimeClock::HOURS, 0x05);
+        rtc.latched[2] = 0x03;
+
+        let footer = rtc.get_save_footer();
+
+        assert_eq!(&[0x12, 0, 0, 0], &footer[4..8]);
+        assert_eq!(&[0x05, 0, 0, 0], &footer[8..12]);
+        assert_eq!(&[0x12, 0, 0, 0], &footer[24..28]);
+        assert_eq!(&[0x03, 0, 0, 0], &footer[28..32]);
+    }
+
+    #[test]
+    fn save_footer_round_trips() {
+        let mut rtc = RealTimeClock::new();
+
+        rtc.set(RealTimeClock::SECONDS, 0x21);
+        rtc.set(RealTimeClock::DAYS_HIGH, 0x81);
+        rtc.latch();
+        clock_seconds(&mut rtc, 1);
+
+        let mut loaded_rtc = RealTimeClock::new();
+        loaded_rtc.load_save_footer(&rtc.get_save_footer());
+
+        assert_eq!(0x21, loaded_rtc.get(RealTimeClock::SECONDS));
+        assert_eq!(0x81, loaded_rtc.get(RealTimeClock::DAYS_HIGH));
+
+        loaded_rtc.latch();
+        assert_eq!(0x22, loaded_rtc.get(RealTimeClock::SECONDS));
+    }
+
+    #[test]
+    fn state_keeps_progress_towards_next_second() {
+        let mut rtc = RealTimeClock::new();
+
+        rtc.set(RealTimeClock::MINUTES, 0x12);
+        for _ in 0..RealTimeClock::M_CYCLES_PER_SECOND - 1 {
+            rtc.clock();
+        }
+
+        let mut loaded_rtc = RealTimeClock::new();
+        loaded_rtc.load_state(&rtc.save_state());
+        loaded_rtc.clock();
+        loaded_rtc.latch();
+
+        assert_eq!(0x12, loaded_rtc.get(RealTimeClock::MINUTES));
+        assert_eq!(0x01, loaded_rtc.get(RealTimeClock::SECONDS));
+    }
+
+    #[test]
+    fn short_save_footer_is_ignored() {
+        let mut rtc = RealTimeClock::new();
+
+        rtc.load_save_footer(&[0x12; 20]);
+
+        assert_eq!(0, rtc.get(RealTimeClock::SECONDS));
+    }
+
+    #[test]
+    fn invalid_register_reads_0xff() {
+        let rtc = RealTimeClock::new();
+
+        assert_eq!(0xFF, rtc.get(0x0D));
+    }
+}
@@ -0,0 +1,98 @@
+use crate::memory::cartridge_error::CartridgeError;
+use crate::memory::mbc::mapper::check_state_length;
+use crate::memory::mbc::Mapper;
+use crate::memory::sram::SRAM;
+
+/**
+    Loose MBC5-like banking, used for any cartridge without a supported controller.
+    Real ROM-only carts never write to 0x2000-0x3FFF, so they always stay on bank 1.
+*/
+pub struct RomOnly {
+    active_bank: usize,
+}
+
+impl RomOnly {
+    pub fn new() -> Self {
+        Self {
+            active_bank: 1
+        }
+    }
+}
+
+impl Mapper for RomOnly {
+    fn write_rom(&mut self, position: u16, value: u8) {
+        if position >= 0x2000 && position < 0x4000 {
+            self.active_bank = value as usize;
+        }
+    }
+
+    fn get_rom_bank(&self, position: u16) -> usize {
+        if position < 0x4000 { 0 } else { self.active_bank }
+    }
+
+    fn read_ram(&self, sram: &SRAM, position: u16) -> u8 {
+        sram.get(0, position)
+    }
+
+    fn write_ram(&mut self, sram: &mut SRAM, position: u16, value: u8) -> u8 {
+        sram.set(0, position, value)
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        vec![self.active_bank as u8]
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), CartridgeError> {
+        check_state_length(state, 1)?;
+
+        self.active_bank = state[0] as usize;
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switches_bank_on_0x2000_write() {
+        let mut rom_only = RomOnly::new();
+
+        rom_only.write_rom(0x2000, 0x05);
+
+        assert_eq!(0x05, rom_only.get_rom_bank(0x4000));
+        assert_eq!(0x00, rom_only.get_rom_bank(0x0000));
+    }
+
+    #[test]
+    fn ignores_other_writes() {
+        let mut rom_only = RomOnly::new();
+
+        rom_only.write_rom(0x4000, 0x05);
+
+        assert_eq!(0x01, rom_only.get_rom_bank(0x4000));
+    }
+
+    #[test]
+    fn always_accesses_ram_bank_0() {
+        let mut rom_only = RomOnly::new();
+        let mut sram = SRAM::new_with_size(0x2000);
+
+        rom_only.write_ram(&mut sram, 0xA000, 0x12);
+
+        assert_eq!(0x12, rom_only.read_ram(&sram, 0xA000));
+    }
+
+    #[test]
+    fn state_round_trips() {
+        let mut rom_only = RomOnly::new();
+        rom_only.write_rom(0x2000, 0x05);
+
+        let mut loaded_rom_only = RomOnly::new();
+        loaded_rom_only.load_state(&rom_only.save_state()).unwrap();
+
+        assert_eq!(0x05, loaded_rom_only.get_rom_bank(0x4000));
+    }
+}
@@ -1,14 +1,19 @@
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use parking_lot::Mutex;
+use crate::cpu::Interrupt;
+use crate::memory::cartridge_error::CartridgeError;
 use crate::memory::hram::HRAM;
 use crate::memory::io_map::IOMap;
+use crate::memory::mbc::RumbleCallback;
 use crate::memory::memory_trait::MemoryTrait;
 use crate::memory::oam::OAM;
 use crate::memory::ram::RAM;
 use crate::memory::rom::ROM;
 use crate::memory::sram::SRAM;
 use crate::memory::vram::VRAM;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
 
 pub struct MemoryController {
     oam_dma_position: u16,
@@ -22,6 +27,8 @@ pub struct MemoryController {
     oam: Arc<Mutex<OAM>>,
     io_map: Arc<Mutex<IOMap>>,
     hram: HRAM,
+
+    save_path: Option<PathBuf>,
 }
 
 impl MemoryTrait for MemoryController {
@@ -36,6 +43,9 @@ impl MemoryTrait for MemoryController {
         else if self.vram.lock().has_address(position) {
             self.vram.lock().get(position)
         }
+        else if self.sram.has_address(position) {
+            self.rom.read_ram(&self.sram, position)
+        }
         else if self.ram.has_address(position) {
             self.ram.get(position)
         }
@@ -68,6 +78,9 @@ impl MemoryTrait for MemoryController {
         else if self.vram.lock().has_address(position) {
             self.vram.lock().set(position, value)
         }
+        else if self.sram.has_address(position) {
+            self.rom.write_ram(&mut self.sram, position, value)
+        }
         else if self.ram.has_address(position) {
             self.ram.set(position, value)
         }
@@ -103,13 +116,19 @@ impl MemoryController {
             ram: RAM::new(),
             oam: Arc::new(Mutex::new(OAM::new())),
             io_map: Arc::new(Mutex::new(IOMap::new())),
-            hram: HRAM::new()
+            hram: HRAM::new(),
+
+            save_path: None,
         }
     }
 
-    pub fn clock(&mut self) {
-        self.io_map.lock().clock();
-        
+    /**
+        Returns the interrupts raised by IO registers during this M-cycle
+    */
+    pub fn clock(&mut self) -> Vec<Interrupt> {
+        let interrupts = self.io_map.lock().clock();
+        self.rom.clock();
+
         if self.oam_dma_position < 160 {
             self.performing_dma = true;
 
@@ -122,10 +141,43 @@ impl MemoryController {
 
             self.performing_dma = false;
         }
+
+        interrupts
+    }
+
+    /**
+        With use_battery_save the cartridge RAM is read from and written back to the .sav file next to the ROM,
+        otherwise it always starts empty and is never written
+    */
+    pub fn load_rom(&mut self, path: &String, use_battery_save: bool) -> Result<(), CartridgeError> {
+        let load_result = self.rom.load_rom_file(Path::new(path));
+        self.sram = SRAM::new_with_size(self.rom.get_ram_size());
+
+        self.save_path = if use_battery_save && self.rom.has_battery() {
+            Some(Path::new(path).with_extension("sav"))
+        } else {
+            None
+        };
+
+        match &self.save_path {
+            Some(save_path) => match fs::read(save_path) {
+                Ok(data) => self.rom.load_battery_data(&mut self.sram, &data),
+                Err(_) => {} //no save yet
+            }
+            None => {}
+        }
+
+        load_result
     }
 
-    pub fn load_rom(&mut self, path: &String) {
-        self.rom.load_rom_file(Path::new(path));
+    /**
+        Writes battery-backed cartridge RAM (and the RTC for MBC3) to the .sav file next to the ROM
+    */
+    pub fn save_battery(&self) -> Result<(), std::io::Error> {
+        match &self.save_path {
+            Some(save_path) => fs::write(save_path, self.rom.get_battery_data(&self.sram)),
+            None => Ok(())
+        }
     }
 
     pub fn get_vram_arc(&self) -> Arc<Mutex<VRAM>> {
@@ -140,11 +192,26 @@ impl MemoryController {
         self.io_map.clone()
     }
 
+    pub fn stop(&mut self) {
+        self.io_map.lock().stop();
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.io_map.lock().is_stopped()
+    }
+
     pub fn get_rom(&self) -> &ROM {
         &self.rom
     }
+
+    pub fn set_rumble_callback(&mut self, callback: RumbleCallback) {
+        self.rom.set_rumble_callback(callback);
+    }
     
-    pub fn reset(&mut self) {
+    pub fn reset(&mut self) -> Result<(), std::io::Error> {
+        let save_result = self.save_battery();
+        self.save_path = None;
+
         self.oam_dma_position = 160;
         self.oam_dma_address = 0;
         self.performing_dma = false;
@@ -158,6 +225,37 @@ impl MemoryController {
         self.io_map.lock().reset();
         
         self.hram = HRAM::new();
+
+        save_result
+    }
+
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u16(self.oam_dma_position);
+        writer.write_u16(self.oam_dma_address);
+
+        self.rom.save_state(writer);
+        self.vram.lock().save_state(writer);
+        self.sram.save_state(writer);
+        self.ram.save_state(writer);
+        self.oam.lock().save_state(writer);
+        self.io_map.lock().save_state(writer);
+        self.hram.save_state(writer);
+    }
+
+    /**
+        The ROM must already be loaded, as only its mapper registers are in the state
+    */
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.oam_dma_position = reader.read_u16()?;
+        self.oam_dma_address = reader.read_u16()?;
+
+        self.rom.load_state(reader)?;
+        self.vram.lock().load_state(reader)?;
+        self.sram.load_state(reader)?;
+        self.ram.load_state(reader)?;
+        self.oam.lock().load_state(reader)?;
+        self.io_map.lock().load_state(reader)?;
+        self.hram.load_state(reader)
     }
 }
 
@@ -165,6 +263,7 @@ impl MemoryController {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::memory::cartridge_info::tests::get_rom_data_with_header;
 
     #[test]
     fn writes_to_vram() {
@@ -211,6 +310,91 @@ mod tests {
 
 
 
+    #[test]
+    fn writes_to_sram() {
+        let expected_value = 0x12;
+        let mut memory_controller = MemoryController::new();
+        memory_controller.sram = SRAM::new_with_size(0x2000);
+
+        memory_controller.set(0xA123, expected_value);
+
+        assert_eq!(memory_controller.sram.get(0, 0xA123), expected_value);
+    }
+
+    #[test]
+    fn reads_from_sram() {
+        let expected_value = 0x12;
+        let mut memory_controller = MemoryController::new();
+        memory_controller.sram = SRAM::new_with_size(0x2000);
+
+        memory_controller.sram.set(0, 0xA123, expected_value);
+
+        assert_eq!(memory_controller.get(0xA123), expected_value);
+    }
+
+    #[test]
+    fn sram_reads_0xff_without_cartridge_ram() {
+        let mut memory_controller = MemoryController::new();
+
+        memory_controller.set(0xA000, 0x12);
+
+        assert_eq!(memory_controller.get(0xA000), 0xFF);
+    }
+
+    #[test]
+    fn battery_ram_is_saved_on_reset_and_loaded_with_rom() {
+        let rom_path = std::env::temp_dir().join("memory_controller_battery_test.gb");
+        let save_path = rom_path.with_extension("sav");
+        let rom_data = get_rom_data_with_header(0x03, 0x00, 0x02); //MBC1+RAM+BATTERY
+        fs::write(&rom_path, rom_data).unwrap();
+        let _ = fs::remove_file(&save_path);
+
+        let mut memory_controller = MemoryController::new();
+        memory_controller.load_rom(&rom_path.to_str().unwrap().to_string(), true).unwrap();
+        memory_controller.set(0x0000, 0x0A);
+        memory_controller.set(0xA123, 0x12);
+        memory_controller.reset().unwrap();
+
+        let mut loaded_memory_controller = MemoryController::new();
+        loaded_memory_controller.load_rom(&rom_path.to_str().unwrap().to_string(), true).unwrap();
+        loaded_memory_controller.set(0x0000, 0x0A);
+
+        assert_eq!(0x2000, fs::read(&save_path).unwrap().len());
+        assert_eq!(0x12, loaded_memory_controller.get(0xA123));
+
+        let _ = fs::remove_file(&rom_path);
+        let _ = fs::remove_file(&save_path);
+    }
+
+    #[test]
+    fn battery_save_is_ignored_when_not_used() {
+        let rom_path = std::env::temp_dir().join("memory_controller_no_battery_save_test.gb");
+        let save_path = rom_path.with_extension("sav");
+        fs::write(&rom_path, get_rom_data_with_header(0x03, 0x00, 0x02)).unwrap(); //MBC1+RAM+BATTERY
+        fs::write(&save_path, vec![0x12; 0x2000]).unwrap();
+
+        let mut memory_controller = MemoryController::new();
+        memory_controller.load_rom(&rom_path.to_str().unwrap().to_string(), false).unwrap();
+        memory_controller.set(0x0000, 0x0A);
+        let loaded_value = memory_controller.get(0xA123);
+        memory_controller.set(0xA123, 0x34);
+        memory_controller.reset().unwrap();
+
+        assert_ne!(0x12, loaded_value);
+        assert_eq!(vec![0x12; 0x2000], fs::read(&save_path).unwrap());
+
+        let _ = fs::remove_file(&rom_path);
+        let _ = fs::remove_file(&save_path);
+    }
+
+    #[test]
+    fn reset_without_battery_writes_nothing() {
+        let mut memory_controller = MemoryController::new();
+
+        assert!(memory_controller.reset().is_ok());
+        assert!(memory_controller.save_path.is_none());
+    }
+
     #[test]
     fn writes_to_oam() {
         let expected_value = 0x12;
@@ -290,6 +474,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn returns_timer_interrupt_on_overflow() {
+        let mut memory_controller = MemoryController::new();
+
+        memory_controller.set(0xFF07, 0x05);
+        memory_controller.set(0xFF05, 0xFF);
+
+        let mut interrupts = vec![];
+        for _ in 0..8 {
+            interrupts.extend(memory_controller.clock());
+        }
+
+        assert_eq!(vec![Interrupt::Timer], interrupts);
+    }
+
+    #[test]
+    fn returns_serial_interrupt_after_transfer() {
+        let mut memory_controller = MemoryController::new();
+
+        memory_controller.set(0xFF01, 0x41);
+        memory_controller.set(0xFF02, 0x81);
+
+        let mut interrupts = vec![];
+        for _ in 0..1024 {
+            interrupts.extend(memory_controller.clock());
+        }
+
+        assert_eq!(vec![Interrupt::Serial], interrupts);
+        assert_eq!(0xFF, memory_controller.get(0xFF01));
+    }
+
     #[test]
     fn locks_memory_during_dma() {
         let mut memory_controller = MemoryController::new();
@@ -299,4 +514,57 @@ mod tests {
         assert_eq!(0xFF, memory_controller.get(0xD000));
         assert_eq!(0xFF, memory_controller.get(0xFE00));
     }
+
+    #[test]
+    fn load_state_restores_saved_state() {
+        let mut memory_controller = MemoryController::new();
+        memory_controller.set(0x8010, 0x12);
+        memory_controller.set(0xC000, 0x34);
+        memory_controller.set(0xFE00, 0x56);
+        memory_controller.set(0xFF06, 0x78);
+        memory_controller.set(0xFF80, 0x9A);
+
+        let mut writer = StateWriter::new();
+        memory_controller.save_state(&mut writer);
+        let state = writer.into_data();
+
+        let mut loaded = MemoryController::new();
+        loaded.load_state(&mut StateReader::new(&state)).unwrap();
+
+        for address in [0x8010, 0xC000, 0xFE00, 0xFF06, 0xFF80] {
+            assert_eq!(memory_controller.get(address), loaded.get(address));
+        }
+    }
+
+    #[test]
+    fn returns_joypad_interrupt_on_button_press() {
+        let mut memory_controller = MemoryController::new();
+        let joypad = memory_controller.get_io_map().lock().get_joypad_io();
+
+        memory_controller.set(0xFF00, 0x20);
+        joypad.lock().set_buttons(0x08);
+
+        assert_eq!(vec![Interrupt::Joypad], memory_controller.clock());
+        assert_eq!(Vec::<Interrupt>::new(), memory_controller.clock());
+    }
+
+    #[test]
+    fn stop_pauses_div_until_joypad_input() {
+        let mut memory_controller = MemoryController::new();
+        let joypad = memory_controller.get_io_map().lock().get_joypad_io();
+        memory_controller.set(0xFF00, 0x10);
+
+        memory_controller.stop();
+        for _ in 0..1000 {
+            memory_controller.clock();
+        }
+
+        assert_eq!(0x00, memory_controller.get(0xFF04));
+        assert!(memory_controller.is_stopped());
+
+        joypad.lock().set_buttons(0x80);
+        memory_controller.clock();
+
+        assert!(!memory_controller.is_stopped());
+    }
 }
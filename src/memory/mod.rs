@@ -1,6 +1,9 @@
 mod memory_controller;
 mod memory_trait;
 mod rom;
+mod cartridge_info;
+mod cartridge_error;
+mod mbc;
 mod vram;
 mod sram;
 mod ram;
@@ -13,3 +16,4 @@ pub use memory_controller::MemoryController;
 pub use memory_trait::MemoryTrait;
 pub use vram::VRAM;
 pub use oam::OAM;
+pub use cartridge_error::CartridgeError;
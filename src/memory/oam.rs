@@ -1,5 +1,6 @@
 use crate::memory::MemoryTrait;
 use crate::memory::object::Object;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
 
 pub struct OAM {
     data: Vec<Object>
@@ -35,6 +36,21 @@ impl OAM {
     pub fn get_objects(&self) -> &[Object] {
         self.data.as_slice()
     }
+
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        let data: Vec<u8> = (0..Self::OAM_SIZE as u16).map(|position| self.get(position)).collect();
+        writer.write_bytes(&data);
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        let data = reader.read_bytes_of_length(Self::OAM_SIZE, "OAM")?;
+
+        for (position, value) in data.iter().enumerate() {
+            self.set(position as u16, *value);
+        }
+
+        Ok(())
+    }
 }
 
 
@@ -1,4 +1,5 @@
 use crate::memory::MemoryTrait;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
 
 pub struct RAM { //also echo RAM
     data: Vec<Vec<u8>>,
@@ -69,6 +70,19 @@ impl RAM {
             Some(&mut self.data[active_bank_real])
         }
     }
+
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bytes(&self.data.concat());
+        writer.write_u8(self.active_bank as u8);
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        let data = reader.read_bytes_of_length(self.data.len() * Self::RAM_BANK_SIZE, "RAM")?;
+        self.data = data.chunks(Self::RAM_BANK_SIZE).map(|bank| bank.to_vec()).collect();
+        self.active_bank = reader.read_u8()? as usize % self.data.len();
+
+        Ok(())
+    }
 }
 
 
@@ -1,35 +1,29 @@
 use std::cmp::min;
 use std::fs;
 use std::path::Path;
-use dialog::{DialogBox, Message};
+use crate::memory::cartridge_error::CartridgeError;
+use crate::memory::cartridge_info::CartridgeInfo;
+use crate::memory::mbc::{create_mapper, Mapper, RomOnly, RumbleCallback};
 use crate::memory::MemoryTrait;
+use crate::memory::sram::SRAM;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
 
 pub struct ROM {
     data: Vec<Vec<u8>>,
-    active_bank: usize
+    mapper: Box<dyn Mapper>,
+    cartridge_info: Option<CartridgeInfo>,
+    rumble_callback: Option<RumbleCallback>
 }
 
 impl MemoryTrait for ROM {
     fn get(&self, position: u16) -> u8 {
-        match self.get_rom_ref(position) {
-            Some(byte) => {
-                *byte
-            }
-            None => 0xFF
-        }
+        self.mapper.read_rom(&self.data, position)
     }
 
     fn set(&mut self, position: u16, value: u8) -> u8 {
-        if position >= 0x2000 && position < 0x4000 {
-            self.active_bank = value as usize;
-        }
+        self.mapper.write_rom(position, value);
 
-        match self.get_rom_ref(position) {
-            Some(byte) => {
-                *byte
-            }
-            None => 0xFF
-        }
+        self.mapper.read_rom(&self.data, position)
     }
 
     fn has_address(&self, position: u16) -> bool {
@@ -44,24 +38,33 @@ impl ROM {
     pub fn new() -> Self {
         Self {
             data: vec![vec![0xFF; Self::ROM_BANK_SIZE], vec![0xFF; Self::ROM_BANK_SIZE]],
-            active_bank: 1
+            mapper: Box::new(RomOnly::new()),
+            cartridge_info: None,
+            rumble_callback: None
         }
     }
 
-    pub fn load_rom_file(&mut self, path: &Path) {
-        self.data.clear();
-        self.active_bank = 1;
-
-        let mut file_data = match fs::read(path) {
-            Ok(data) => data,
-            Err(_) => {
-                let _ = Message::new(format!("Could not open file: {}", path.to_str().unwrap())).title("File Error").show();
+    pub fn load_rom_file(&mut self, path: &Path) -> Result<(), CartridgeError> {
+        match fs::read(path) {
+            Ok(file_data) => self.load_rom_data(file_data),
+            Err(error) => {
+                self.load_empty_rom();
+                Err(CartridgeError::ReadError { error })
+            }
+        }
+    }
 
-                self.data = vec![vec![0; Self::ROM_BANK_SIZE], vec![0; Self::ROM_BANK_SIZE]];
-                return
+    pub fn load_rom_data(&mut self, mut file_data: Vec<u8>) -> Result<(), CartridgeError> {
+        let cartridge_info = match CartridgeInfo::from_rom_data(&file_data) {
+            Ok(cartridge_info) => cartridge_info,
+            Err(error) => {
+                self.load_empty_rom();
+                return Err(error);
             }
         };
 
+        self.data.clear();
+
         while file_data.len() > 0 {
             let remaining_bank_size = min(file_data.len(), Self::ROM_BANK_SIZE);
             let current_bank = file_data[0..remaining_bank_size].to_vec();
@@ -71,34 +74,93 @@ impl ROM {
             file_data = file_data[remaining_bank_size..file_data.len()].to_vec();
         }
 
-        return;
+        self.mapper = create_mapper(&cartridge_info, &self.data);
+        match &self.rumble_callback {
+            Some(callback) => self.mapper.set_rumble_callback(callback.clone()),
+            None => {}
+        }
+        self.cartridge_info = Some(cartridge_info);
+
+        Ok(())
+    }
+
+    /**
+        The callback is kept across ROM loads and handed to each new mapper
+    */
+    pub fn set_rumble_callback(&mut self, callback: RumbleCallback) {
+        self.mapper.set_rumble_callback(callback.clone());
+        self.rumble_callback = Some(callback);
     }
 
-    fn get_rom_ref(&self, position: u16) -> Option<&u8> {
-        let bank_position = position as usize % Self::ROM_BANK_SIZE;
-        let relevant_bank = self.get_relevant_bank(position);
+    pub fn get_cartridge_info(&self) -> Option<&CartridgeInfo> {
+        self.cartridge_info.as_ref()
+    }
 
-        if bank_position < self.data[relevant_bank].len() {
-            Some(&self.data[relevant_bank][bank_position])
-        } else {
-            None
+    pub fn read_ram(&self, sram: &SRAM, position: u16) -> u8 {
+        self.mapper.read_ram(sram, position)
+    }
+
+    pub fn write_ram(&mut self, sram: &mut SRAM, position: u16, value: u8) -> u8 {
+        self.mapper.write_ram(sram, position, value)
+    }
+
+    pub fn get_ram_size(&self) -> usize {
+        match &self.cartridge_info {
+            Some(cartridge_info) => cartridge_info.get_ram_size(),
+            None => 0
         }
     }
 
-    pub fn get_relevant_bank(&self, position: u16) -> usize {
-        if position < Self::ROM_BANK_SIZE as u16 {
-            0
-        } else {
-            self.active_bank % self.data.len() //currently simulates MBC5
+    pub fn has_battery(&self) -> bool {
+        match &self.cartridge_info {
+            Some(cartridge_info) => cartridge_info.has_battery(),
+            None => false
         }
     }
+
+    pub fn get_battery_data(&self, sram: &SRAM) -> Vec<u8> {
+        self.mapper.get_battery_data(sram)
+    }
+
+    pub fn load_battery_data(&mut self, sram: &mut SRAM, data: &[u8]) {
+        self.mapper.load_battery_data(sram, data);
+    }
+
+    pub fn clock(&mut self) {
+        self.mapper.clock();
+    }
+
+    fn load_empty_rom(&mut self) {
+        self.data = vec![vec![0; Self::ROM_BANK_SIZE], vec![0; Self::ROM_BANK_SIZE]];
+        self.mapper = Box::new(RomOnly::new());
+        self.cartridge_info = None;
+    }
+
+    pub fn get_relevant_bank(&self, position: u16) -> usize {
+        self.mapper.get_rom_bank(position) % self.data.len()
+    }
+
+    /**
+        Only the mapper's registers are saved, the ROM itself is identified by the save state header
+    */
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bytes(&self.mapper.save_state());
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        let state = reader.read_bytes()?;
+        self.mapper.load_state(state).map_err(|error| SaveStateError::MapperError { error })
+    }
 }
 
 
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
     use super::*;
+    use crate::memory::cartridge_info::tests::get_rom_data_with_header;
 
     #[test]
     fn rom_gets_from_bank_0_correctly() {
@@ -134,5 +196,96 @@ mod tests {
         assert_eq!(rom.get(0x4123), expected_value);
     }
 
-    //might be hard to test rom loading
+    #[test]
+    fn mbc1_switches_banks_through_upper_bits() {
+        let expected_value = 0x12;
+        let mut rom = ROM::new();
+
+        rom.load_rom_data(get_rom_data_with_header(0x01, 0x05, 0x00)).unwrap();
+
+        rom.set(0x2000, 0x03);
+        rom.set(0x4000, 0x01);
+        rom.data[0x23][0x0123] = expected_value;
+
+        assert_eq!(rom.get(0x4123), expected_value);
+    }
+
+    #[test]
+    fn ram_size_is_read_from_header() {
+        let mut rom = ROM::new();
+
+        rom.load_rom_data(get_rom_data_with_header(0x00, 0x00, 0x03)).unwrap();
+
+        assert_eq!(0x8000, rom.get_ram_size());
+    }
+
+    #[test]
+    fn battery_is_read_from_cartridge_type() {
+        let mut rom = ROM::new();
+
+        rom.load_rom_data(get_rom_data_with_header(0x13, 0x00, 0x00)).unwrap();
+
+        assert!(rom.has_battery());
+    }
+
+    #[test]
+    fn cartridge_without_battery_has_no_battery() {
+        let mut rom = ROM::new();
+
+        rom.load_rom_data(get_rom_data_with_header(0x12, 0x00, 0x00)).unwrap();
+
+        assert!(!rom.has_battery());
+    }
+
+    #[test]
+    fn relevant_bank_wraps_to_rom_size() {
+        let mut rom = ROM::new();
+
+        rom.set(0x2000, 0x03);
+
+        assert_eq!(1, rom.get_relevant_bank(0x4000));
+    }
+
+    #[test]
+    fn loads_rom_data_into_banks() {
+        let mut rom = ROM::new();
+
+        rom.load_rom_data(get_rom_data_with_header(0x00, 0x01, 0x00)).unwrap();
+
+        assert_eq!(4, rom.data.len());
+        assert_eq!("TESTGAME", rom.get_cartridge_info().unwrap().get_title());
+    }
+
+    #[test]
+    fn rumble_callback_is_kept_across_loads() {
+        let mut rom = ROM::new();
+        let rumble = Arc::new(AtomicBool::new(false));
+
+        let callback_rumble = rumble.clone();
+        rom.set_rumble_callback(Arc::new(move |state| callback_rumble.store(state, Ordering::Relaxed)));
+        rom.load_rom_data(get_rom_data_with_header(0x1C, 0x01, 0x00)).unwrap();
+
+        rom.set(0x4000, 0x08);
+
+        assert!(rumble.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn invalid_header_loads_empty_rom() {
+        let mut rom = ROM::new();
+        let mut data = get_rom_data_with_header(0x13, 0x01, 0x03);
+        data[0x014D] = data[0x014D].wrapping_add(1);
+
+        assert!(matches!(rom.load_rom_data(data), Err(CartridgeError::HeaderChecksumMismatch { .. })));
+        assert_eq!(2, rom.data.len());
+        assert!(rom.get_cartridge_info().is_none());
+        assert_eq!(0, rom.get_ram_size());
+    }
+
+    #[test]
+    fn missing_file_is_a_read_error() {
+        let mut rom = ROM::new();
+
+        assert!(matches!(rom.load_rom_file(Path::new("./does_not_exist.gb")), Err(CartridgeError::ReadError { .. })));
+    }
 }
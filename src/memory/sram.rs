@@ -1,11 +1,255 @@
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
 pub struct SRAM {
-
+    data: Vec<Vec<u8>>,
 }
 
+/*
+    External cartridge RAM at 0xA000-0xBFFF.
+    Banking and the RAM enable latch belong to the cartridge's memory bank controller,
+    so accesses always come through it with the bank it has selected.
+ */
+
 impl SRAM {
+
+    const SRAM_BANK_SIZE: usize = 0x2000;
+    const SRAM_LOWER_BOUND: u16 = 0xA000;
+    const SRAM_UPPER_BOUND: u16 = 0xC000;
+
     pub fn new() -> Self {
-        Self {}
+        Self { data: vec![] }
+    }
+
+    pub fn new_with_size(size: usize) -> Self {
+        let mut data = vec![];
+        let mut remaining_size = size;
+
+        while remaining_size > 0 {
+            let bank_size = remaining_size.min(Self::SRAM_BANK_SIZE);
+            data.push(vec![0; bank_size]);
+
+            remaining_size -= bank_size;
+        }
+
+        Self { data }
+    }
+
+    /**
+        Converts the RAM size byte at 0x0149 of the cartridge header into a size in bytes
+    */
+    pub fn get_size_from_header(ram_size: u8) -> usize {
+        match ram_size {
+            0x01 => 0x800,
+            0x02 => 0x2000,
+            0x03 => 0x8000,
+            0x04 => 0x20000,
+            0x05 => 0x10000,
+            _ => 0
+        }
+    }
+
+    pub fn has_address(&self, position: u16) -> bool {
+        position >= Self::SRAM_LOWER_BOUND && position < Self::SRAM_UPPER_BOUND
+    }
+
+    pub fn get(&self, bank: usize, position: u16) -> u8 {
+        match self.get_bank_ref(bank) {
+            Some(bank) => bank[position as usize % bank.len()],
+            None => 0xFF
+        }
+    }
+
+    pub fn set(&mut self, bank: usize, position: u16, value: u8) -> u8 {
+        match self.get_bank_ref_mut(bank) {
+            Some(bank) => {
+                let bank_position = position as usize % bank.len();
+                let old_value = bank[bank_position];
+                bank[bank_position] = value;
+                old_value
+            }
+            None => 0xFF
+        }
+    }
+
+    pub fn get_size(&self) -> usize {
+        self.data.iter().map(|bank| bank.len()).sum()
+    }
+
+    pub fn get_data(&self) -> Vec<u8> {
+        self.data.concat()
+    }
+
+    /**
+        Fills the RAM from the start with the given data, leaving anything past its end untouched
+    */
+    pub fn load_data(&mut self, data: &[u8]) {
+        let mut remaining_data = data;
+
+        for bank in self.data.iter_mut() {
+            let length = bank.len().min(remaining_data.len());
+            bank[..length].copy_from_slice(&remaining_data[..length]);
+
+            remaining_data = &remaining_data[length..];
+        }
+    }
+
+    fn get_bank_ref(&self, bank: usize) -> Option<&Vec<u8>> {
+        if self.data.is_empty() {
+            None
+        } else {
+            Some(&self.data[bank % self.data.len()])
+        }
+    }
+
+    fn get_bank_ref_mut(&mut self, bank: usize) -> Option<&mut Vec<u8>> {
+        if self.data.is_empty() {
+            None
+        } else {
+            let bank_count = self.data.len();
+            Some(&mut self.data[bank % bank_count])
+        }
+    }
+
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bytes(&self.get_data());
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        let data = reader.read_bytes_of_length(self.get_size(), "Cartridge RAM")?;
+        self.load_data(data);
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_sram_reads_0xff() {
+        let sram = SRAM::new();
+
+        assert_eq!(0xFF, sram.get(0, 0xA000));
+    }
+
+    #[test]
+    fn empty_sram_ignores_writes() {
+        let mut sram = SRAM::new();
+
+        assert_eq!(0xFF, sram.set(0, 0xA000, 0x12));
+        assert_eq!(0xFF, sram.get(0, 0xA000));
     }
 
+    #[test]
+    fn stores_data_in_bank_0() {
+        let mut sram = SRAM::new_with_size(0x2000);
 
-}
\ No newline at end of file
+        sram.set(0, 0xA123, 0x12);
+
+        assert_eq!(0x12, sram.get(0, 0xA123));
+    }
+
+    #[test]
+    fn banks_are_separate() {
+        let mut sram = SRAM::new_with_size(0x8000);
+
+        sram.set(0, 0xA000, 0x12);
+        sram.set(3, 0xA000, 0x34);
+
+        assert_eq!(0x12, sram.get(0, 0xA000));
+        assert_eq!(0x34, sram.get(3, 0xA000));
+    }
+
+    #[test]
+    fn out_of_range_banks_wrap() {
+        let mut sram = SRAM::new_with_size(0x8000);
+
+        sram.set(1, 0xA000, 0x12);
+
+        assert_eq!(0x12, sram.get(5, 0xA000));
+    }
+
+    #[test]
+    fn small_ram_is_mirrored() {
+        let mut sram = SRAM::new_with_size(0x800);
+
+        sram.set(0, 0xA000, 0x12);
+
+        assert_eq!(0x12, sram.get(0, 0xA800));
+        assert_eq!(0x12, sram.get(0, 0xB800));
+    }
+
+    #[test]
+    fn set_returns_old_value() {
+        let mut sram = SRAM::new_with_size(0x2000);
+
+        sram.set(0, 0xA000, 0x12);
+
+        assert_eq!(0x12, sram.set(0, 0xA000, 0x34));
+    }
+
+    #[test]
+    fn size_is_decoded_from_header() {
+        assert_eq!(0, SRAM::get_size_from_header(0x00));
+        assert_eq!(0x800, SRAM::get_size_from_header(0x01));
+        assert_eq!(0x2000, SRAM::get_size_from_header(0x02));
+        assert_eq!(0x8000, SRAM::get_size_from_header(0x03));
+        assert_eq!(0x20000, SRAM::get_size_from_header(0x04));
+        assert_eq!(0x10000, SRAM::get_size_from_header(0x05));
+    }
+
+    #[test]
+    fn reports_size() {
+        let sram = SRAM::new_with_size(0x8000);
+
+        assert_eq!(0x8000, sram.get_size());
+    }
+
+    #[test]
+    fn data_is_flattened_in_bank_order() {
+        let mut sram = SRAM::new_with_size(0x4000);
+
+        sram.set(1, 0xA000, 0x12);
+
+        assert_eq!(0x12, sram.get_data()[0x2000]);
+    }
+
+    #[test]
+    fn loads_data_across_banks() {
+        let mut sram = SRAM::new_with_size(0x4000);
+        let mut data = vec![0; 0x4000];
+        data[0x2001] = 0x12;
+
+        sram.load_data(&data);
+
+        assert_eq!(0x12, sram.get(1, 0xA001));
+    }
+
+    #[test]
+    fn loading_short_data_keeps_the_rest() {
+        let mut sram = SRAM::new_with_size(0x2000);
+        sram.set(0, 0xA010, 0x34);
+
+        sram.load_data(&[0x12; 0x10]);
+
+        assert_eq!(0x12, sram.get(0, 0xA00F));
+        assert_eq!(0x34, sram.get(0, 0xA010));
+    }
+
+    #[test]
+    fn has_address_in_bounds_is_true() {
+        let sram = SRAM::new();
+
+        assert!(sram.has_address(0xA000));
+        assert!(sram.has_address(0xBFFF));
+    }
+
+    #[test]
+    fn has_address_out_of_bounds_is_false() {
+        let sram = SRAM::new();
+
+        assert!(!sram.has_address(0x9FFF));
+        assert!(!sram.has_address(0xC000));
+    }
+}
@@ -1,6 +1,7 @@
 use std::fs;
 use std::path::Path;
 use crate::memory::memory_trait::MemoryTrait;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
 
 pub struct VRAM {
     tile_bank_0: Vec<u8>, tile_bank_0_stale: bool,
@@ -171,6 +172,31 @@ impl VRAM {
         self.map_bank_0_stale = false;
         self.map_bank_1_stale = false;
     }
+
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        for bank in [&self.tile_bank_0, &self.tile_bank_1, &self.tile_bank_2, &self.map_bank_0, &self.map_bank_1] {
+            writer.write_bytes(bank);
+        }
+    }
+
+    /**
+        Every bank is marked stale so the renderer uploads the loaded data
+    */
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.tile_bank_0 = reader.read_bytes_of_length(Self::TILE_BANK_SIZE, "VRAM tile bank 0")?.to_vec();
+        self.tile_bank_1 = reader.read_bytes_of_length(Self::TILE_BANK_SIZE, "VRAM tile bank 1")?.to_vec();
+        self.tile_bank_2 = reader.read_bytes_of_length(Self::TILE_BANK_SIZE, "VRAM tile bank 2")?.to_vec();
+        self.map_bank_0 = reader.read_bytes_of_length(Self::MAP_BANK_SIZE, "VRAM map bank 0")?.to_vec();
+        self.map_bank_1 = reader.read_bytes_of_length(Self::MAP_BANK_SIZE, "VRAM map bank 1")?.to_vec();
+
+        self.tile_bank_0_stale = true;
+        self.tile_bank_1_stale = true;
+        self.tile_bank_2_stale = true;
+        self.map_bank_0_stale = true;
+        self.map_bank_1_stale = true;
+
+        Ok(())
+    }
 }
 
 
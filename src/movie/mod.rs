@@ -0,0 +1,7 @@
+mod movie;
+mod movie_error;
+mod movie_player;
+
+pub use movie::{Movie, MovieStart};
+pub use movie_error::MovieError;
+pub use movie_player::MoviePlayer;
@@ -0,0 +1,197 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::movie::movie_error::MovieError;
+use crate::save_state::{SaveStateError, SaveStateFile, StateReader, StateWriter};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MovieStart {
+    PowerOn,
+    SaveState,
+}
+
+/**
+    Joypad input for every frame, from a start state taken either just after power-on or from a running game.
+    The start state is kept either way, so battery RAM loaded at power-on plays back the same.
+
+    On disk: magic bytes, movie version, save state version, model, a CRC-32 of the ROM file, the start kind,
+    then the start state and the inputs, one byte per frame as given by `JoypadIO::get_buttons`.
+*/
+pub struct Movie {
+    rom_hash: u32,
+    start: MovieStart,
+    start_state: Vec<u8>,
+    inputs: Vec<u8>,
+}
+
+impl Movie {
+
+    const MAGIC: &'static [u8; 8] = b"GBMOVIE\0";
+    pub const VERSION: u16 = 1;
+    pub const MODEL_DMG: u8 = 0; //the only model emulated
+
+    pub fn new(rom_hash: u32, start: MovieStart, start_state: Vec<u8>) -> Self {
+        Self {
+            rom_hash,
+            start,
+            start_state,
+            inputs: vec![],
+        }
+    }
+
+    /**
+        Movies live next to the ROM, as game.gbm
+    */
+    pub fn get_path(rom_path: &Path) -> PathBuf {
+        rom_path.with_extension("gbm")
+    }
+
+    /**
+        CRC-32 of the whole ROM file, the header checksums are too weak to tell ROM hacks and revisions apart
+    */
+    pub fn hash_rom(data: &[u8]) -> u32 {
+        !data.iter().fold(0xFFFFFFFF, |crc, byte| {
+            (0..8).fold(crc ^ *byte as u32, |crc, _| {
+                if crc & 1 != 0 {
+                    (crc >> 1) ^ 0xEDB88320
+                } else {
+                    crc >> 1
+                }
+            })
+        })
+    }
+
+    pub fn hash_rom_file(path: &Path) -> Result<u32, MovieError> {
+        let data = fs::read(path).map_err(|error| MovieError::ReadError { error })?;
+
+        Ok(Self::hash_rom(&data))
+    }
+
+    pub fn push_input(&mut self, buttons: u8) {
+        self.inputs.push(buttons);
+    }
+
+    pub fn get_input(&self, frame: usize) -> Option<u8> {
+        self.inputs.get(frame).copied()
+    }
+
+    pub fn get_frame_count(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn get_start_state(&self) -> &[u8] {
+        &self.start_state
+    }
+
+    pub fn check_rom(&self, rom_hash: u32) -> Result<(), MovieError> {
+        if self.rom_hash == rom_hash {
+            Ok(())
+        } else {
+            Err(MovieError::RomMismatch { expected: rom_hash, found: self.rom_hash })
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), MovieError> {
+        let mut writer = StateWriter::new();
+        for byte in Self::MAGIC {
+            writer.write_u8(*byte);
+        }
+        writer.write_u16(Self::VERSION);
+        writer.write_u16(SaveStateFile::VERSION);
+        writer.write_u8(Self::MODEL_DMG);
+        writer.write_u32(self.rom_hash);
+        writer.write_u8(match self.start {
+            MovieStart::PowerOn => 0,
+            MovieStart::SaveState => 1,
+        });
+        writer.write_bytes(&self.start_state);
+        writer.write_bytes(&self.inputs);
+
+        fs::write(path, writer.into_data()).map_err(|error| MovieError::WriteError { error })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, MovieError> {
+        let data = fs::read(path).map_err(|error| MovieError::ReadError { error })?;
+
+        if data.len() < Self::MAGIC.len() || &data[..Self::MAGIC.len()] != Self::MAGIC {
+            return Err(MovieError::NotAMovie);
+        }
+
+        let mut reader = StateReader::new(&data[Self::MAGIC.len()..]);
+        let state_error = |error| MovieError::StateError { error };
+
+        let version = reader.read_u16().map_err(state_error)?;
+        if version != Self::VERSION {
+            return Err(MovieError::UnsupportedVersion { version, expected: Self::VERSION });
+        }
+
+        let state_version = reader.read_u16().map_err(state_error)?;
+        if state_version != SaveStateFile::VERSION {
+            return Err(state_error(SaveStateError::UnsupportedVersion { version: state_version, expected: SaveStateFile::VERSION }));
+        }
+
+        let model = reader.read_u8().map_err(state_error)?;
+        if model != Self::MODEL_DMG {
+            return Err(MovieError::UnsupportedModel { model });
+        }
+
+        let rom_hash = reader.read_u32().map_err(state_error)?;
+        let start = match reader.read_u8().map_err(state_error)? {
+            0 => MovieStart::PowerOn,
+            1 => MovieStart::SaveState,
+            start => return Err(state_error(SaveStateError::InvalidState { message: format!("Unknown movie start {}", start) }))
+        };
+
+        Ok(Self {
+            rom_hash,
+            start,
+            start_state: reader.read_bytes().map_err(state_error)?.to_vec(),
+            inputs: reader.read_bytes().map_err(state_error)?.to_vec(),
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("movie_{}_{}.gbm", name, std::process::id()))
+    }
+
+    #[test]
+    fn hashes_rom_with_crc32() {
+        assert_eq!(0xCBF43926, Movie::hash_rom(b"123456789"));
+    }
+
+    #[test]
+    fn loads_saved_movie() {
+        let path = get_path("round_trip");
+        let mut movie = Movie::new(0x12345678, MovieStart::SaveState, vec![1, 2, 3]);
+        movie.push_input(0x00);
+        movie.push_input(0x81);
+
+        movie.save(&path).unwrap();
+        let loaded = Movie::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(MovieStart::SaveState, loaded.start);
+        assert_eq!(&[1, 2, 3], loaded.get_start_state());
+        assert_eq!(2, loaded.get_frame_count());
+        assert_eq!(Some(0x81), loaded.get_input(1));
+        assert_eq!(None, loaded.get_input(2));
+        assert!(loaded.check_rom(0x12345678).is_ok());
+        assert!(matches!(loaded.check_rom(0x87654321), Err(MovieError::RomMismatch { .. })));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let path = get_path("not_a_movie");
+        fs::write(&path, b"GBSTATE\0").unwrap();
+
+        let result = Movie::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(MovieError::NotAMovie)));
+    }
+}
@@ -0,0 +1,20 @@
+use thiserror::Error;
+use crate::save_state::SaveStateError;
+
+#[derive(Error, Debug)]
+pub enum MovieError {
+    #[error("Could not write movie: {error}")]
+    WriteError { error: std::io::Error },
+    #[error("Could not read movie: {error}")]
+    ReadError { error: std::io::Error },
+    #[error("Not a movie file")]
+    NotAMovie,
+    #[error("Movie version {version} is not supported, expected {expected}")]
+    UnsupportedVersion { version: u16, expected: u16 },
+    #[error("Movie was recorded on an unsupported model ({model})")]
+    UnsupportedModel { model: u8 },
+    #[error("Movie is for a different ROM (hash {found:#010X}, expected {expected:#010X})")]
+    RomMismatch { expected: u32, found: u32 },
+    #[error("Invalid movie start state: {error}")]
+    StateError { error: SaveStateError },
+}
@@ -0,0 +1,101 @@
+use crate::memory::io_map::JoypadIO;
+use crate::movie::movie::Movie;
+use crate::movie::movie_error::MovieError;
+use crate::system::MainBoard;
+
+/**
+    Plays a movie back by setting every button at the start of each frame, overriding any other input
+*/
+pub struct MoviePlayer {
+    movie: Movie,
+    frame: usize,
+}
+
+impl MoviePlayer {
+
+    pub fn new(movie: Movie) -> Self {
+        Self {
+            movie,
+            frame: 0,
+        }
+    }
+
+    /**
+        Puts the machine in the movie's start state. The ROM it was recorded with must already be loaded.
+    */
+    pub fn start(&mut self, main_board: &mut MainBoard, rom_hash: u32) -> Result<(), MovieError> {
+        self.movie.check_rom(rom_hash)?;
+        main_board.load_state(self.movie.get_start_state()).map_err(|error| MovieError::StateError { error })?;
+        self.frame = 0;
+
+        Ok(())
+    }
+
+    /**
+        Call before each frame. Returns false once every frame has been played, leaving the joypad as it was.
+    */
+    pub fn apply_next_input(&mut self, joypad: &mut JoypadIO) -> bool {
+        match self.movie.get_input(self.frame) {
+            Some(buttons) => {
+                joypad.set_buttons(buttons);
+                self.frame += 1;
+                true
+            }
+            None => false
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.movie.get_frame_count()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::memory::MemoryTrait;
+    use crate::movie::MovieStart;
+    use crate::save_state::{StateReader, StateWriter};
+    use super::*;
+
+    #[test]
+    fn applies_one_input_per_frame() {
+        let mut movie = Movie::new(0, MovieStart::PowerOn, vec![]);
+        movie.push_input(0x01);
+        movie.push_input(0x80);
+        let mut player = MoviePlayer::new(movie);
+        let mut joypad = JoypadIO::new();
+
+        assert!(player.apply_next_input(&mut joypad));
+        assert_eq!(0x01, joypad.get_buttons());
+        assert!(player.apply_next_input(&mut joypad));
+        assert_eq!(0x80, joypad.get_buttons());
+        assert!(player.is_finished());
+        assert!(!player.apply_next_input(&mut joypad));
+        assert_eq!(0x80, joypad.get_buttons());
+    }
+
+    #[test]
+    fn playback_frame_overrides_host_input() {
+        let mut recorded = JoypadIO::new();
+        recorded.set(0xFF00, 0x10);
+        let mut writer = StateWriter::new();
+        recorded.save_state(&mut writer);
+        let state = writer.into_data();
+
+        let mut movie = Movie::new(0, MovieStart::SaveState, vec![]);
+        movie.push_input(0x10); //the first recorded frame presses A
+        let mut player = MoviePlayer::new(movie);
+
+        let mut joypad = JoypadIO::new();
+        joypad.set(0xFF00, 0x10);
+        joypad.set_buttons(0x10); //the host is holding A when playback starts
+        joypad.take_interrupt();
+        joypad.load_state(&mut StateReader::new(&state)).unwrap();
+
+        player.apply_next_input(&mut joypad);
+
+        assert!(joypad.take_interrupt());
+        assert_eq!(0xDE, joypad.get(0xFF00));
+    }
+}
@@ -0,0 +1,423 @@
+use std::path::PathBuf;
+use crate::memory::io_map::SerialPeer;
+use crate::printer::printout::Printout;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum PacketState {
+    Magic1,
+    Magic2,
+    Command,
+    Compression,
+    LengthLow,
+    LengthHigh,
+    Data,
+    ChecksumLow,
+    ChecksumHigh,
+    Alive,
+    Status,
+}
+
+/**
+    Game Boy Printer on the other end of the link cable. The Game Boy always clocks the printer, sending packets of
+    0x88 0x33, command, compression, length (LE), data and checksum (LE), then two more bytes during which the printer
+    answers 0x81 and its status.
+
+    Received image data is added to the printout by the print command. A print with a bottom margin feeds the paper out,
+    so the printout is saved as a PNG in the output directory and a new one started.
+*/
+pub struct GameBoyPrinter {
+    output_directory: PathBuf,
+
+    state: PacketState,
+    command: u8,
+    compressed: bool,
+    length: u16,
+    packet_data: Vec<u8>,
+    checksum: u16,
+    received_checksum: u16,
+    reply: Option<u8>,
+
+    status: u8,
+    busy_clocks: u32,
+    image_data: Vec<u8>,
+    printout: Printout,
+}
+
+impl GameBoyPrinter {
+
+    const MAGIC_1: u8 = 0x88;
+    const MAGIC_2: u8 = 0x33;
+    const ALIVE: u8 = 0x81;
+
+    const COMMAND_INITIALISE: u8 = 0x01;
+    const COMMAND_PRINT: u8 = 0x02;
+    const COMMAND_DATA: u8 = 0x04;
+
+    const STATUS_CHECKSUM_ERROR: u8 = 0x01;
+    const STATUS_BUSY: u8 = 0x02;
+    const STATUS_IMAGE_FULL: u8 = 0x04;
+    const STATUS_UNPROCESSED_DATA: u8 = 0x08;
+
+    const BAND_SIZE: usize = 0x280; //two rows of 20 tiles
+    const MAX_IMAGE_DATA: usize = Self::BAND_SIZE * 9;
+    const DEFAULT_PALETTE: u8 = 0xE4; //a palette of 0 is treated as the usual 3, 2, 1, 0
+    const MARGIN_LINES: usize = 8;
+    const PRINT_CLOCKS: u32 = 262144; //a quarter of a second of the printer reporting busy
+
+    pub fn new(output_directory: PathBuf) -> Self {
+        Self {
+            output_directory,
+
+            state: PacketState::Magic1,
+            command: 0x00,
+            compressed: false,
+            length: 0,
+            packet_data: vec![],
+            checksum: 0,
+            received_checksum: 0,
+            reply: None,
+
+            status: 0x00,
+            busy_clocks: 0,
+            image_data: vec![],
+            printout: Printout::new(),
+        }
+    }
+
+    fn get_status(&self) -> u8 {
+        if self.busy_clocks > 0 {
+            self.status | Self::STATUS_BUSY
+        } else {
+            self.status
+        }
+    }
+
+    /**
+        Returns the byte the printer shifts out while receiving this one
+    */
+    fn receive(&mut self, value: u8) -> u8 {
+        match self.state {
+            PacketState::Magic1 => {
+                if value == Self::MAGIC_1 {
+                    self.state = PacketState::Magic2;
+                }
+            }
+            PacketState::Magic2 => {
+                self.state = match value {
+                    Self::MAGIC_2 => PacketState::Command,
+                    Self::MAGIC_1 => PacketState::Magic2,
+                    _ => PacketState::Magic1
+                };
+            }
+            PacketState::Command => {
+                self.command = value;
+                self.checksum = value as u16;
+                self.state = PacketState::Compression;
+            }
+            PacketState::Compression => {
+                self.compressed = value & 0x01 != 0;
+                self.checksum = self.checksum.wrapping_add(value as u16);
+                self.state = PacketState::LengthLow;
+            }
+            PacketState::LengthLow => {
+                self.length = value as u16;
+                self.checksum = self.checksum.wrapping_add(value as u16);
+                self.state = PacketState::LengthHigh;
+            }
+            PacketState::LengthHigh => {
+                self.length |= (value as u16) << 8;
+                self.checksum = self.checksum.wrapping_add(value as u16);
+                self.packet_data.clear();
+                self.state = if self.length == 0 { PacketState::ChecksumLow } else { PacketState::Data };
+            }
+            PacketState::Data => {
+                self.packet_data.push(value);
+                self.checksum = self.checksum.wrapping_add(value as u16);
+
+                if self.packet_data.len() == self.length as usize {
+                    self.state = PacketState::ChecksumLow;
+                }
+            }
+            PacketState::ChecksumLow => {
+                self.received_checksum = value as u16;
+                self.state = PacketState::ChecksumHigh;
+            }
+            PacketState::ChecksumHigh => {
+                self.received_checksum |= (value as u16) << 8;
+                self.state = PacketState::Alive;
+                self.complete_packet();
+            }
+            PacketState::Alive => {
+                self.state = PacketState::Status;
+                return Self::ALIVE;
+            }
+            PacketState::Status => {
+                self.state = PacketState::Magic1;
+                return self.get_status();
+            }
+        }
+
+        0x00
+    }
+
+    fn complete_packet(&mut self) {
+        if self.checksum != self.received_checksum {
+            self.status |= Self::STATUS_CHECKSUM_ERROR;
+            return;
+        }
+        self.status &= !Self::STATUS_CHECKSUM_ERROR;
+
+        match self.command {
+            Self::COMMAND_INITIALISE => {
+                self.image_data.clear();
+                self.status = 0x00;
+            }
+            Self::COMMAND_DATA => {
+                let data = if self.compressed { Self::decompress(&self.packet_data) } else { self.packet_data.clone() };
+                let space = Self::MAX_IMAGE_DATA - self.image_data.len();
+
+                self.image_data.extend(data.into_iter().take(space));
+
+                if !self.image_data.is_empty() {
+                    self.status |= Self::STATUS_UNPROCESSED_DATA;
+                }
+                if self.image_data.len() == Self::MAX_IMAGE_DATA {
+                    self.status |= Self::STATUS_IMAGE_FULL;
+                }
+            }
+            Self::COMMAND_PRINT if self.packet_data.len() >= 4 => {
+                let sheets = self.packet_data[0];
+                let margins = self.packet_data[1];
+                let palette = match self.packet_data[2] {
+                    0x00 => Self::DEFAULT_PALETTE,
+                    palette => palette
+                };
+
+                self.print(sheets, margins >> 4, margins & 0x0F, palette);
+            }
+            _ => {} //the status command, or anything else, just returns the status
+        }
+    }
+
+    fn print(&mut self, sheets: u8, top_margin: u8, bottom_margin: u8, palette: u8) {
+        self.printout.add_margin(top_margin as usize * Self::MARGIN_LINES);
+        for _ in 0..sheets {
+            self.printout.add_band(&self.image_data, palette);
+        }
+        self.printout.add_margin(bottom_margin as usize * Self::MARGIN_LINES);
+
+        self.image_data.clear();
+        self.status &= !(Self::STATUS_UNPROCESSED_DATA | Self::STATUS_IMAGE_FULL);
+        self.busy_clocks = Self::PRINT_CLOCKS;
+
+        if bottom_margin > 0 {
+            self.save_printout();
+        }
+    }
+
+    fn save_printout(&mut self) {
+        if self.printout.is_empty() {
+            return;
+        }
+
+        let path = (1..)
+            .map(|index| self.output_directory.join(format!("print_{:04}.png", index)))
+            .find(|path| !path.exists())
+            .unwrap();
+
+        match self.printout.save_png(&path) {
+            Ok(_) => eprintln!("Printed {}", path.display()),
+            Err(error) => eprintln!("{}", error)
+        }
+
+        self.printout = Printout::new();
+    }
+
+    /**
+        A control byte with bit 7 set repeats the next byte (control & 0x7F) + 2 times, otherwise the next
+        control + 1 bytes are copied as they are.
+    */
+    fn decompress(data: &[u8]) -> Vec<u8> {
+        let mut decompressed = vec![];
+        let mut index = 0;
+
+        while index < data.len() {
+            let control = data[index];
+            index += 1;
+
+            if control & 0x80 != 0 {
+                if let Some(&value) = data.get(index) {
+                    decompressed.extend(std::iter::repeat(value).take((control & 0x7F) as usize + 2));
+                }
+                index += 1;
+            } else {
+                let end = (index + control as usize + 1).min(data.len());
+
+                decompressed.extend_from_slice(&data[index..end]);
+                index = end;
+            }
+        }
+
+        decompressed
+    }
+}
+
+impl SerialPeer for GameBoyPrinter {
+    fn start_transfer(&mut self, outgoing: u8) {
+        self.reply = Some(self.receive(outgoing));
+    }
+
+    fn poll(&mut self, _data: u8) -> Option<u8> {
+        if self.busy_clocks > 0 {
+            self.busy_clocks -= 1;
+        }
+
+        self.reply.take()
+    }
+}
+
+impl Drop for GameBoyPrinter {
+    fn drop(&mut self) {
+        self.save_printout(); //keeps anything printed without a bottom margin
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_printer() -> GameBoyPrinter {
+        GameBoyPrinter::new(std::env::temp_dir()) //only saves if something is printed
+    }
+
+    fn get_output_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("gameboy_printer_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        directory
+    }
+
+    fn send_packet(printer: &mut GameBoyPrinter, command: u8, compression: u8, data: &[u8]) -> Vec<u8> {
+        let length = data.len() as u16;
+        let mut packet = vec![0x88, 0x33, command, compression, length as u8, (length >> 8) as u8];
+        packet.extend_from_slice(data);
+
+        let checksum = packet[2..].iter().fold(0u16, |sum, &value| sum.wrapping_add(value as u16));
+        packet.extend_from_slice(&[checksum as u8, (checksum >> 8) as u8, 0x00, 0x00]);
+
+        packet.into_iter()
+            .map(|value| {
+                printer.start_transfer(value);
+                printer.poll(value).unwrap()
+            })
+            .collect()
+    }
+
+    fn get_status(printer: &mut GameBoyPrinter) -> u8 {
+        *send_packet(printer, 0x0F, 0x00, &[]).last().unwrap()
+    }
+
+    #[test]
+    fn replies_alive_and_status_at_end_of_packet() {
+        let mut printer = get_printer();
+
+        let replies = send_packet(&mut printer, 0x01, 0x00, &[]);
+
+        assert_eq!(vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x81, 0x00], replies);
+    }
+
+    #[test]
+    fn data_is_unprocessed_until_printed() {
+        let mut printer = get_printer();
+
+        send_packet(&mut printer, 0x01, 0x00, &[]);
+        let replies = send_packet(&mut printer, 0x04, 0x00, &[0x00; 0x280]);
+
+        assert_eq!(0x08, *replies.last().unwrap());
+        assert_eq!(0x08, get_status(&mut printer));
+    }
+
+    #[test]
+    fn bad_checksum_sets_error_and_ignores_packet() {
+        let mut printer = get_printer();
+        let packet = [0x88, 0x33, 0x04, 0x00, 0x01, 0x00, 0xFF, 0x00, 0x00, 0x00, 0x00];
+
+        let replies: Vec<u8> = packet.iter()
+            .map(|&value| {
+                printer.start_transfer(value);
+                printer.poll(value).unwrap()
+            })
+            .collect();
+
+        assert_eq!(0x01, *replies.last().unwrap());
+        assert!(printer.image_data.is_empty());
+        assert_eq!(0x00, get_status(&mut printer));
+    }
+
+    #[test]
+    fn decompresses_runs_and_literals() {
+        let decompressed = GameBoyPrinter::decompress(&[0x81, 0xAA, 0x01, 0x12, 0x34, 0x80, 0x55]);
+
+        assert_eq!(vec![0xAA, 0xAA, 0xAA, 0x12, 0x34, 0x55, 0x55], decompressed);
+    }
+
+    #[test]
+    fn compressed_data_packet_fills_band() {
+        let mut printer = get_printer();
+
+        send_packet(&mut printer, 0x04, 0x01, &[0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00]); //5 runs of 129
+
+        assert_eq!(0x280 + 5, printer.image_data.len());
+    }
+
+    #[test]
+    fn prints_band_with_margins_and_reports_busy() {
+        let directory = get_output_directory("busy");
+        let mut printer = GameBoyPrinter::new(directory.clone());
+
+        send_packet(&mut printer, 0x04, 0x00, &[0xFF; 0x280]);
+        send_packet(&mut printer, 0x04, 0x00, &[]);
+        send_packet(&mut printer, 0x02, 0x00, &[0x01, 0x10, 0xE4, 0x40]);
+
+        let mut expected = Printout::new();
+        expected.add_margin(8);
+        expected.add_band(&[0xFF; 0x280], 0xE4);
+        assert_eq!(expected, printer.printout);
+        assert_eq!(0x02, get_status(&mut printer));
+
+        for _ in 0..GameBoyPrinter::PRINT_CLOCKS {
+            printer.poll(0x00);
+        }
+
+        assert_eq!(0x00, get_status(&mut printer));
+        assert!(printer.image_data.is_empty());
+
+        drop(printer);
+        assert!(directory.join("print_0001.png").exists());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn bands_are_stitched_until_paper_is_fed_out() {
+        let directory = get_output_directory("stitched");
+        let mut printer = GameBoyPrinter::new(directory.clone());
+
+        for margins in [0x10, 0x00, 0x03] {
+            send_packet(&mut printer, 0x04, 0x00, &[0x00; 0x280]);
+            send_packet(&mut printer, 0x02, 0x00, &[0x01, margins, 0xE4, 0x40]);
+
+            if margins == 0x03 {
+                assert!(printer.printout.is_empty());
+            } else {
+                assert!(!printer.printout.is_empty());
+            }
+        }
+
+        assert!(directory.join("print_0001.png").exists());
+        assert!(!directory.join("print_0002.png").exists());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,5 @@
+mod game_boy_printer;
+mod printer_error;
+mod printout;
+
+pub use game_boy_printer::GameBoyPrinter;
@@ -0,0 +1,9 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum PrinterError {
+    #[error("Could not write printout: {error}")]
+    WriteError { error: std::io::Error },
+    #[error("Could not encode printout: {message}")]
+    EncodingError { message: String },
+}
@@ -0,0 +1,142 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use crate::printer::printer_error::PrinterError;
+
+/**
+    The strip of paper coming out of the printer, as 8 bit grey pixels. Each print command adds a band of tiles below
+    what is already there, so images printed in several parts end up in one file.
+*/
+#[derive(PartialEq, Debug)]
+pub struct Printout {
+    pixels: Vec<u8>,
+}
+
+impl Printout {
+
+    pub const WIDTH: usize = 160;
+    const TILES_PER_ROW: usize = Self::WIDTH / 8;
+    const BYTES_PER_TILE: usize = 16;
+    const SHADES: [u8; 4] = [0xFF, 0xAA, 0x55, 0x00];
+
+    pub fn new() -> Self {
+        Self {
+            pixels: vec![],
+        }
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.pixels.len() / Self::WIDTH
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pixels.is_empty()
+    }
+
+    /**
+        Adds rows of tiles in the same 2 bit format as VRAM, 20 tiles to a row. The palette maps colour n to bits 2n and 2n + 1
+        like BGP does.
+    */
+    pub fn add_band(&mut self, tile_data: &[u8], palette: u8) {
+        let tile_rows = tile_data.len() / (Self::TILES_PER_ROW * Self::BYTES_PER_TILE);
+
+        for tile_row in 0..tile_rows {
+            for line in 0..8 {
+                for tile in 0..Self::TILES_PER_ROW {
+                    let address = (tile_row * Self::TILES_PER_ROW + tile) * Self::BYTES_PER_TILE + line * 2;
+                    let low = tile_data[address];
+                    let high = tile_data[address + 1];
+
+                    for bit in (0..8).rev() {
+                        let colour = ((low >> bit) & 1) | (((high >> bit) & 1) << 1);
+                        let shade = (palette >> (colour * 2)) & 0x03;
+
+                        self.pixels.push(Self::SHADES[shade as usize]);
+                    }
+                }
+            }
+        }
+    }
+
+    /**
+        Feeds blank paper
+    */
+    pub fn add_margin(&mut self, lines: usize) {
+        self.pixels.resize(self.pixels.len() + lines * Self::WIDTH, Self::SHADES[0]);
+    }
+
+    pub fn save_png(&self, path: &Path) -> Result<(), PrinterError> {
+        let file = File::create(path).map_err(|error| PrinterError::WriteError { error })?;
+
+        let mut encoder = png::Encoder::new(BufWriter::new(file), Self::WIDTH as u32, self.get_height() as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let result = encoder.write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.pixels).and_then(|_| writer.finish()));
+
+        result.map_err(|error| PrinterError::EncodingError { message: error.to_string() })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_pixel(printout: &Printout, x: usize, y: usize) -> u8 {
+        printout.pixels[y * Printout::WIDTH + x]
+    }
+
+    #[test]
+    fn decodes_tiles_through_palette() {
+        let mut tile_data = vec![0u8; 20 * 16];
+        tile_data[0] = 0b1010_0000; //first line of the first tile is colours 1, 2, 3, 0...
+        tile_data[1] = 0b0110_0000;
+        let mut printout = Printout::new();
+
+        printout.add_band(&tile_data, 0b11_10_01_00);
+
+        assert_eq!(8, printout.get_height());
+        assert_eq!(0xAA, get_pixel(&printout, 0, 0));
+        assert_eq!(0x55, get_pixel(&printout, 1, 0));
+        assert_eq!(0x00, get_pixel(&printout, 2, 0));
+        assert_eq!(0xFF, get_pixel(&printout, 3, 0));
+        assert_eq!(0xFF, get_pixel(&printout, 0, 1));
+    }
+
+    #[test]
+    fn inverts_with_palette() {
+        let mut printout = Printout::new();
+
+        printout.add_band(&[0u8; 20 * 16], 0b00_01_10_11);
+
+        assert_eq!(0x00, get_pixel(&printout, 159, 7));
+    }
+
+    #[test]
+    fn places_tiles_left_to_right_then_top_to_bottom() {
+        let mut tile_data = vec![0u8; 40 * 16];
+        tile_data[16] = 0x80;       //second tile
+        tile_data[20 * 16 + 2] = 0x01; //first tile of the second row, second line
+        let mut printout = Printout::new();
+
+        printout.add_band(&tile_data, 0b11_10_01_00);
+
+        assert_eq!(16, printout.get_height());
+        assert_eq!(0xAA, get_pixel(&printout, 8, 0));
+        assert_eq!(0xAA, get_pixel(&printout, 7, 9));
+    }
+
+    #[test]
+    fn adds_blank_margin() {
+        let mut printout = Printout::new();
+
+        printout.add_band(&[0xFFu8; 20 * 16], 0b11_10_01_00);
+        printout.add_margin(3);
+
+        assert_eq!(11, printout.get_height());
+        assert_eq!(0x00, get_pixel(&printout, 0, 7));
+        assert_eq!(0xFF, get_pixel(&printout, 0, 10));
+    }
+}
@@ -1,7 +1,15 @@
 mod video_processor;
 mod renderer_error;
+mod renderer;
+mod screen_buffer;
+mod software_renderer;
+mod screen_presenter;
 
 pub use video_processor::LCDCMask;
 pub use video_processor::LCDStatMask;
 pub use video_processor::VideoProcessor;
 pub use renderer_error::RendererError;
+pub use renderer::Renderer;
+pub use screen_buffer::ScreenBuffer;
+pub use software_renderer::SoftwareRenderer;
+pub use screen_presenter::ScreenPresenter;
@@ -0,0 +1,9 @@
+use dec_gl::shader::ShaderManager;
+use crate::renderer::RendererError;
+
+/**
+    Draws the current scan line (LY) whenever the VDU asks for one
+*/
+pub trait Renderer {
+    fn draw_line(&mut self, shader_manager: &mut ShaderManager) -> Result<(), RendererError>;
+}
@@ -5,5 +5,13 @@ use thiserror::Error;
 #[derive(Error, Debug)]
 pub enum RendererError {
     #[error("OpenGL Error: {error}")]
-    GLError { error: RenderError }
+    GLError { error: RenderError },
+    #[error("Could not write screen: {error}")]
+    WriteError { error: std::io::Error },
+    #[error("Could not encode screen: {message}")]
+    EncodingError { message: String },
+    #[error("Could not read screen: {error}")]
+    ReadError { error: std::io::Error },
+    #[error("Could not decode screen: {message}")]
+    DecodingError { message: String },
 }
@@ -0,0 +1,122 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use crate::renderer::RendererError;
+
+/**
+    The 160x144 LCD as shades 0-3, after the palettes have been applied
+*/
+pub struct ScreenBuffer {
+    pixels: Vec<u8>,
+}
+
+impl ScreenBuffer {
+
+    pub const WIDTH: usize = 160;
+    pub const HEIGHT: usize = 144;
+    const GREYS: [u8; 4] = [0xFF, 0xAA, 0x55, 0x00];
+
+    pub fn new() -> Self {
+        Self {
+            pixels: vec![0; Self::WIDTH * Self::HEIGHT],
+        }
+    }
+
+    pub fn get_line_mut(&mut self, y: usize) -> &mut [u8] {
+        &mut self.pixels[y * Self::WIDTH..(y + 1) * Self::WIDTH]
+    }
+
+    pub fn get_pixels(&self) -> &Vec<u8> {
+        &self.pixels
+    }
+
+    /**
+        Saves the screen as an 8 bit greyscale PNG, shade 0 being white
+    */
+    pub fn save_png(&self, path: &Path) -> Result<(), RendererError> {
+        let file = File::create(path).map_err(|error| RendererError::WriteError { error })?;
+        let greys: Vec<u8> = self.pixels.iter().map(|&shade| Self::GREYS[(shade & 3) as usize]).collect();
+
+        let mut encoder = png::Encoder::new(BufWriter::new(file), Self::WIDTH as u32, Self::HEIGHT as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let result = encoder.write_header()
+            .and_then(|mut writer| writer.write_image_data(&greys).and_then(|_| writer.finish()));
+
+        result.map_err(|error| RendererError::EncodingError { message: error.to_string() })
+    }
+
+    /**
+        Loads a 160x144 screenshot, mapping each pixel to the nearest of the four greys.
+        Colour images are read from their first channel, so they must already be greyscale.
+    */
+    pub fn load_png(path: &Path) -> Result<Self, RendererError> {
+        let file = File::open(path).map_err(|error| RendererError::ReadError { error })?;
+
+        let mut decoder = png::Decoder::new(BufReader::new(file));
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+
+        let decoding_error = |error: png::DecodingError| RendererError::DecodingError { message: error.to_string() };
+        let mut reader = decoder.read_info().map_err(decoding_error)?;
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).map_err(decoding_error)?;
+
+        if info.width as usize != Self::WIDTH || info.height as usize != Self::HEIGHT {
+            return Err(RendererError::DecodingError { message: format!("Expected a {}x{} image, got {}x{}", Self::WIDTH, Self::HEIGHT, info.width, info.height) });
+        }
+
+        let samples = info.color_type.samples();
+        let pixels = (0..Self::WIDTH * Self::HEIGHT)
+            .map(|index| {
+                let (x, y) = (index % Self::WIDTH, index / Self::WIDTH);
+                let grey = data[y * info.line_size + x * samples];
+
+                ((0xFF - grey as u32 + 0x2A) / 0x55) as u8
+            })
+            .collect();
+
+        Ok(Self {
+            pixels,
+        })
+    }
+
+    /**
+        Number of pixels that differ from another screen
+    */
+    pub fn count_differences(&self, other: &ScreenBuffer) -> usize {
+        self.pixels.iter().zip(other.pixels.iter()).filter(|(a, b)| a != b).count()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_screen_loads_back_unchanged() {
+        let path = std::env::temp_dir().join(format!("screen_buffer_{}.png", std::process::id()));
+        let mut screen_buffer = ScreenBuffer::new();
+        for (x, pixel) in screen_buffer.get_line_mut(10).iter_mut().enumerate() {
+            *pixel = (x % 4) as u8;
+        }
+
+        screen_buffer.save_png(&path).unwrap();
+        let loaded = ScreenBuffer::load_png(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(0, screen_buffer.count_differences(&loaded));
+        assert_eq!(3, loaded.get_pixels()[10 * ScreenBuffer::WIDTH + 3]);
+    }
+
+    #[test]
+    fn counts_differing_pixels() {
+        let screen_buffer = ScreenBuffer::new();
+        let mut other = ScreenBuffer::new();
+        other.get_line_mut(0)[0] = 1;
+        other.get_line_mut(143)[159] = 2;
+
+        assert_eq!(2, screen_buffer.count_differences(&other));
+    }
+}
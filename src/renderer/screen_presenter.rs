@@ -0,0 +1,159 @@
+use std::sync::Arc;
+use dec_gl::renderable::Renderable;
+use dec_gl::shader::ShaderManager;
+use dec_gl::types::ivec2;
+use dec_gl::Vertex2d;
+use mockall_double::double;
+#[double]
+use dec_gl::texture::Texture2Du8;
+use parking_lot::Mutex;
+use crate::memory::io_map::VideoIO;
+use crate::renderer::{Renderer, RendererError, ScreenBuffer, SoftwareRenderer};
+
+/**
+    Runs the software renderer and uploads its screen buffer to a texture once the last line has been drawn,
+    so the only OpenGL work left is drawing one quad a frame.
+*/
+pub struct ScreenPresenter {
+    software_renderer: SoftwareRenderer,
+    screen_buffer: Arc<Mutex<ScreenBuffer>>,
+    video_io: Arc<Mutex<VideoIO>>,
+
+    screen_texture: Texture2Du8,
+    screen_renderable: Box<dyn Renderable<Vertex2d>>,
+}
+
+impl ScreenPresenter {
+
+    pub fn new(
+        software_renderer: SoftwareRenderer,
+        screen_texture: Texture2Du8,
+        mut screen_renderable: Box<dyn Renderable<Vertex2d>>,
+        video_io: Arc<Mutex<VideoIO>>
+    )
+        -> Result<ScreenPresenter, RendererError>
+    {
+        match screen_renderable.initialise(&vec![
+            Vertex2d { x: 0.0, y: 0.0, u: 0.0, v: 0.0},
+            Vertex2d { x: 0.0, y: 144.0, u: 0.0, v: 1.0},
+            Vertex2d { x: 160.0, y: 0.0, u: 1.0, v: 0.0},
+
+            Vertex2d { x: 0.0, y: 144.0, u: 0.0, v: 1.0},
+            Vertex2d { x: 160.0, y: 0.0, u: 1.0, v: 0.0},
+            Vertex2d { x: 160.0, y: 144.0, u: 1.0, v: 1.0}],
+                                         None)
+        {
+            Ok(_) => {}
+            Err(error) => return Err(RendererError::GLError { error })
+        }
+
+        Ok(ScreenPresenter {
+            screen_buffer: software_renderer.get_screen_buffer(),
+            software_renderer,
+            video_io,
+
+            screen_texture,
+            screen_renderable,
+        })
+    }
+
+    fn present(&mut self, shader_manager: &mut ShaderManager) -> Result<(), RendererError> {
+        {
+            let screen_buffer = self.screen_buffer.lock();
+
+            match self.screen_texture.set_data(screen_buffer.get_pixels(), ivec2(ScreenBuffer::WIDTH as i32, ScreenBuffer::HEIGHT as i32)) {
+                Ok(_) => {}
+                Err(error) => return Err(RendererError::GLError { error })
+            }
+        }
+
+        match shader_manager.bind("SCREEN".to_string()) {
+            Ok(_) => {
+                self.screen_texture.bind_to_unit(0);
+                self.screen_renderable.draw();
+
+                Ok(())
+            }
+            Err(error) => Err(RendererError::GLError { error })
+        }
+    }
+}
+
+impl Renderer for ScreenPresenter {
+    fn draw_line(&mut self, shader_manager: &mut ShaderManager) -> Result<(), RendererError> {
+        self.software_renderer.draw_line(shader_manager)?;
+
+        let ly = self.video_io.lock().get_ly();
+        if ly as usize == ScreenBuffer::HEIGHT - 1 {
+            self.present(shader_manager)?;
+        }
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+    use dec_gl::renderable::NullableRenderable;
+    use dec_gl::shader::NullableShaderProgram;
+    use dec_gl::texture::MockTexture2Du8;
+    use crate::memory::{OAM, VRAM};
+    use super::*;
+
+    fn get_presenter(screen_texture: MockTexture2Du8, draw_count: Rc<RefCell<u32>>) -> (ScreenPresenter, Arc<Mutex<VideoIO>>) {
+        let video_io = Arc::new(Mutex::new(VideoIO::new()));
+        let software_renderer = SoftwareRenderer::new(Arc::new(Mutex::new(VRAM::new())), Arc::new(Mutex::new(OAM::new())), video_io.clone());
+
+        let screen_renderable = Box::new(NullableRenderable::<Vertex2d>::new::<Vertex2d>(
+            Rc::new(RefCell::new(false)),
+            Rc::new(RefCell::new(vec![])),
+            Rc::new(RefCell::new(None)),
+            draw_count,
+        ));
+
+        (ScreenPresenter::new(software_renderer, screen_texture, screen_renderable, video_io.clone()).unwrap(), video_io)
+    }
+
+    fn get_shader_manager() -> ShaderManager {
+        let mut shader_manager = ShaderManager::new();
+        shader_manager.register_shader(
+            "SCREEN".to_string(),
+            Box::new(NullableShaderProgram::new(Rc::new(RefCell::new(HashMap::new())), Rc::new(RefCell::new(false))))
+        ).unwrap();
+
+        shader_manager
+    }
+
+    #[test]
+    fn does_not_upload_before_last_line() {
+        let draw_count = Rc::new(RefCell::new(0));
+        let mut screen_texture = MockTexture2Du8::default();
+        screen_texture.expect_set_data().never();
+
+        let (mut presenter, video_io) = get_presenter(screen_texture, draw_count.clone());
+        video_io.lock().set_ly(142);
+
+        presenter.draw_line(&mut get_shader_manager()).unwrap();
+
+        assert_eq!(0, *draw_count.borrow());
+    }
+
+    #[test]
+    fn uploads_and_draws_screen_after_last_line() {
+        let draw_count = Rc::new(RefCell::new(0));
+        let mut screen_texture = MockTexture2Du8::default();
+        screen_texture.expect_set_data().withf(|data, size| data.len() == 160 * 144 && *size == ivec2(160, 144)).times(1).returning(|_, _| Ok(()));
+        screen_texture.expect_bind_to_unit().times(1).returning(|_| ());
+
+        let (mut presenter, video_io) = get_presenter(screen_texture, draw_count.clone());
+        video_io.lock().set_ly(143);
+
+        presenter.draw_line(&mut get_shader_manager()).unwrap();
+
+        assert_eq!(1, *draw_count.borrow());
+    }
+}
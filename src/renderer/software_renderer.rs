@@ -0,0 +1,417 @@
+use std::sync::Arc;
+use dec_gl::shader::ShaderManager;
+use parking_lot::Mutex;
+use crate::memory::io_map::VideoIO;
+use crate::memory::{MemoryTrait, OAM, VRAM};
+use crate::renderer::{LCDCMask, Renderer, RendererError, ScreenBuffer};
+
+/**
+    Draws scan lines on the CPU into a ScreenBuffer, without needing an OpenGL context
+*/
+pub struct SoftwareRenderer {
+    vram: Arc<Mutex<VRAM>>,
+    oam: Arc<Mutex<OAM>>,
+    video_io: Arc<Mutex<VideoIO>>,
+
+    screen_buffer: Arc<Mutex<ScreenBuffer>>,
+    window_line: u8, //the window only moves down on lines where it was drawn
+}
+
+impl SoftwareRenderer {
+
+    const MAX_OBJECTS_PER_LINE: usize = 10;
+
+    pub fn new(vram: Arc<Mutex<VRAM>>, oam: Arc<Mutex<OAM>>, video_io: Arc<Mutex<VideoIO>>) -> Self {
+        Self {
+            vram,
+            oam,
+            video_io,
+
+            screen_buffer: Arc::new(Mutex::new(ScreenBuffer::new())),
+            window_line: 0,
+        }
+    }
+
+    pub fn get_screen_buffer(&self) -> Arc<Mutex<ScreenBuffer>> {
+        self.screen_buffer.clone()
+    }
+
+    fn get_colour(low: u8, high: u8, bit: u8) -> u8 {
+        ((low >> bit) & 1) | (((high >> bit) & 1) << 1)
+    }
+
+    fn get_map_colour(vram: &VRAM, lcd_ctrl: u8, map_address: u16, x: u8, y: u8) -> u8 {
+        let tile_id = vram.get(map_address + (y as u16 >> 3) * 32 + (x as u16 >> 3));
+
+        let tile_address = if LCDCMask::mask(lcd_ctrl, LCDCMask::WIN_AND_BG_MAP) {
+            0x8000 + tile_id as u16 * 16
+        } else {
+            (0x9000 + (tile_id as i8 as i32) * 16) as u16
+        };
+
+        let line_address = tile_address + (y as u16 & 7) * 2;
+
+        Self::get_colour(vram.get(line_address), vram.get(line_address + 1), 7 - (x & 7))
+    }
+
+    fn draw_background(vram: &VRAM, video_io: &VideoIO, colours: &mut [u8; ScreenBuffer::WIDTH]) {
+        let lcd_ctrl = video_io.get_lcd_ctrl();
+        let map_address = if LCDCMask::mask(lcd_ctrl, LCDCMask::BG_TILE_BANK) { 0x9C00 } else { 0x9800 };
+        let y = video_io.get_ly().wrapping_add(video_io.get_bg_y());
+
+        for (x, colour) in colours.iter_mut().enumerate() {
+            *colour = Self::get_map_colour(vram, lcd_ctrl, map_address, (x as u8).wrapping_add(video_io.get_bg_x()), y);
+        }
+    }
+
+    /**
+        Returns whether any of the window was on this line
+    */
+    fn draw_window(vram: &VRAM, video_io: &VideoIO, window_line: u8, colours: &mut [u8; ScreenBuffer::WIDTH]) -> bool {
+        let lcd_ctrl = video_io.get_lcd_ctrl();
+        let left = video_io.get_win_x() as i32 - 7;
+
+        if video_io.get_ly() < video_io.get_win_y() || left >= ScreenBuffer::WIDTH as i32 {
+            return false;
+        }
+
+        let map_address = if LCDCMask::mask(lcd_ctrl, LCDCMask::WIN_TILE_BANK) { 0x9C00 } else { 0x9800 };
+
+        for x in left.max(0)..ScreenBuffer::WIDTH as i32 {
+            colours[x as usize] = Self::get_map_colour(vram, lcd_ctrl, map_address, (x - left) as u8, window_line);
+        }
+
+        true
+    }
+
+    /**
+        Up to 10 objects are picked in OAM order, then the one with the lowest X (or earliest in OAM) owns each pixel
+        it isn't transparent on. If its priority bit is set it only shows over background colour 0.
+    */
+    fn draw_objects(vram: &VRAM, oam: &OAM, video_io: &VideoIO, colours: &[u8; ScreenBuffer::WIDTH], shades: &mut [u8; ScreenBuffer::WIDTH]) {
+        let ly = video_io.get_ly() as i16;
+        let height = if LCDCMask::mask(video_io.get_lcd_ctrl(), LCDCMask::OBJ_SIZE) { 16 } else { 8 };
+
+        let mut objects: Vec<_> = oam.get_objects().iter()
+            .filter(|object| {
+                let top = object.get_y() as i16 - 16;
+                ly >= top && ly < top + height
+            })
+            .take(Self::MAX_OBJECTS_PER_LINE)
+            .collect();
+        objects.sort_by_key(|object| object.get_x()); //stable, so OAM order breaks ties
+
+        let mut owned = [false; ScreenBuffer::WIDTH];
+
+        for object in objects {
+            let mut line = ly - (object.get_y() as i16 - 16);
+            if object.get_vertical_flip() {
+                line = height - 1 - line;
+            }
+
+            let tile = if height == 16 { object.get_tile() & 0xFE } else { object.get_tile() };
+            let line_address = 0x8000 + tile as u16 * 16 + line as u16 * 2;
+            let low = vram.get(line_address);
+            let high = vram.get(line_address + 1);
+
+            let palette = if object.get_dmg_palette() { video_io.get_obj_pal_1() } else { video_io.get_obj_pal_0() };
+
+            for pixel in 0..8 {
+                let x = object.get_x() as i16 - 8 + pixel;
+                if x < 0 || x >= ScreenBuffer::WIDTH as i16 || owned[x as usize] {
+                    continue;
+                }
+
+                let bit = if object.get_horizontal_flip() { pixel } else { 7 - pixel };
+                let colour = Self::get_colour(low, high, bit as u8);
+                if colour == 0 {
+                    continue;
+                }
+
+                owned[x as usize] = true;
+
+                if !object.get_priority() || colours[x as usize] == 0 {
+                    shades[x as usize] = (palette >> (colour * 2)) & 3;
+                }
+            }
+        }
+    }
+}
+
+impl Renderer for SoftwareRenderer {
+    fn draw_line(&mut self, _shader_manager: &mut ShaderManager) -> Result<(), RendererError> {
+        let video_io_mutex = self.video_io.clone();
+        let video_io = video_io_mutex.lock();
+        let lcd_ctrl = video_io.get_lcd_ctrl();
+        let ly = video_io.get_ly();
+
+        if !LCDCMask::mask(lcd_ctrl, LCDCMask::LCD_ENABLE) || ly as usize >= ScreenBuffer::HEIGHT {
+            return Ok(());
+        }
+        if ly == 0 {
+            self.window_line = 0;
+        }
+
+        let vram = self.vram.lock();
+        let mut colours = [0u8; ScreenBuffer::WIDTH]; //before BGP, objects need these for priority
+        let mut shades = [0u8; ScreenBuffer::WIDTH];
+
+        if LCDCMask::mask(lcd_ctrl, LCDCMask::BG_ENABLE) {
+            Self::draw_background(&vram, &video_io, &mut colours);
+
+            if LCDCMask::mask(lcd_ctrl, LCDCMask::WIN_ENABLE) && Self::draw_window(&vram, &video_io, self.window_line, &mut colours) {
+                self.window_line = self.window_line.wrapping_add(1);
+            }
+
+            for (shade, colour) in shades.iter_mut().zip(colours.iter()) {
+                *shade = (video_io.get_bg_pal() >> (colour * 2)) & 3;
+            }
+        }
+
+        if LCDCMask::mask(lcd_ctrl, LCDCMask::OBJ_ENABLE) {
+            Self::draw_objects(&vram, &self.oam.lock(), &video_io, &colours, &mut shades);
+        }
+
+        self.screen_buffer.lock().get_line_mut(ly as usize).copy_from_slice(&shades);
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_renderer() -> (SoftwareRenderer, Arc<Mutex<VRAM>>, Arc<Mutex<OAM>>, Arc<Mutex<VideoIO>>) {
+        let vram = Arc::new(Mutex::new(VRAM::new()));
+        let oam = Arc::new(Mutex::new(OAM::new()));
+        let video_io = Arc::new(Mutex::new(VideoIO::new()));
+
+        for address in 0x8000..0xA000 {
+            vram.lock().set(address, 0x00);
+        }
+        video_io.lock().set(0xFF40, 0x91);
+        video_io.lock().set(0xFF47, 0xE4);
+        video_io.lock().set(0xFF48, 0xE4);
+        video_io.lock().set(0xFF49, 0x1B);
+
+        (SoftwareRenderer::new(vram.clone(), oam.clone(), video_io.clone()), vram, oam, video_io)
+    }
+
+    fn draw_line(renderer: &mut SoftwareRenderer, video_io: &Arc<Mutex<VideoIO>>, ly: u8) -> Vec<u8> {
+        video_io.lock().set_ly(ly);
+        renderer.draw_line(&mut ShaderManager::new()).unwrap();
+
+        let screen_buffer = renderer.get_screen_buffer();
+        let line = screen_buffer.lock().get_pixels()[ly as usize * ScreenBuffer::WIDTH..(ly as usize + 1) * ScreenBuffer::WIDTH].to_vec();
+
+        line
+    }
+
+    fn set_tile_line(vram: &Arc<Mutex<VRAM>>, tile_address: u16, line: u16, low: u8, high: u8) {
+        vram.lock().set(tile_address + line * 2, low);
+        vram.lock().set(tile_address + line * 2 + 1, high);
+    }
+
+    fn set_object(oam: &Arc<Mutex<OAM>>, index: u16, y: u8, x: u8, tile: u8, attributes: u8) {
+        let address = 0xFE00 + index * 4;
+
+        oam.lock().set(address, y);
+        oam.lock().set(address + 1, x);
+        oam.lock().set(address + 2, tile);
+        oam.lock().set(address + 3, attributes);
+    }
+
+    #[test]
+    fn draws_background_through_palette() {
+        let (mut renderer, vram, _, video_io) = get_renderer();
+        set_tile_line(&vram, 0x8010, 0, 0b1010_0000, 0b0110_0000);
+        vram.lock().set(0x9801, 0x01);
+
+        let line = draw_line(&mut renderer, &video_io, 0);
+
+        assert_eq!(vec![0, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3, 0], line[0..12].to_vec());
+    }
+
+    #[test]
+    fn scrolls_background_and_wraps() {
+        let (mut renderer, vram, _, video_io) = get_renderer();
+        set_tile_line(&vram, 0x8010, 3, 0x80, 0x80);
+        vram.lock().set(0x9800 + 31 * 32 + 31, 0x01);
+        video_io.lock().set(0xFF42, 0xFB); //SCY, line 0 is line 3 of the last row
+        video_io.lock().set(0xFF43, 0xF6); //SCX, pixel 2 is the start of the last column
+
+        let line = draw_line(&mut renderer, &video_io, 0);
+
+        assert_eq!(vec![0, 0, 3, 0], line[0..4].to_vec());
+    }
+
+    #[test]
+    fn uses_signed_tile_ids_without_lcdc_bit_4() {
+        let (mut renderer, vram, _, video_io) = get_renderer();
+        set_tile_line(&vram, 0x8FF0, 0, 0x80, 0x00); //tile -1
+        set_tile_line(&vram, 0x9000, 0, 0x00, 0x80); //tile 0
+        vram.lock().set(0x9800, 0xFF);
+        video_io.lock().set(0xFF40, 0x81);
+
+        let line = draw_line(&mut renderer, &video_io, 0);
+
+        assert_eq!(1, line[0]);
+        assert_eq!(2, line[8]);
+    }
+
+    #[test]
+    fn uses_the_second_map_with_lcdc_bit_3() {
+        let (mut renderer, vram, _, video_io) = get_renderer();
+        set_tile_line(&vram, 0x8010, 0, 0xFF, 0xFF);
+        vram.lock().set(0x9C00, 0x01);
+        video_io.lock().set(0xFF40, 0x99);
+
+        let line = draw_line(&mut renderer, &video_io, 0);
+
+        assert_eq!(3, line[0]);
+    }
+
+    #[test]
+    fn draws_window_from_wx_and_wy() {
+        let (mut renderer, vram, _, video_io) = get_renderer();
+        for line in 0..8 {
+            set_tile_line(&vram, 0x8010, line, 0xFF, 0xFF);
+        }
+        vram.lock().set(0x9C00, 0x01);
+        video_io.lock().set(0xFF40, 0xF1);
+        video_io.lock().set(0xFF4A, 10); //WY
+        video_io.lock().set(0xFF4B, 27); //WX, window starts at pixel 20
+
+        let above = draw_line(&mut renderer, &video_io, 9);
+        let first = draw_line(&mut renderer, &video_io, 10);
+
+        assert!(above.iter().all(|&shade| shade == 0));
+        assert_eq!(0, first[19]);
+        assert_eq!(vec![3; 8], first[20..28].to_vec());
+        assert_eq!(0, first[28]);
+    }
+
+    #[test]
+    fn window_line_only_advances_when_window_is_drawn() {
+        let (mut renderer, vram, _, video_io) = get_renderer();
+        set_tile_line(&vram, 0x8010, 1, 0xFF, 0x00);
+        vram.lock().set(0x9C00, 0x01);
+        video_io.lock().set(0xFF40, 0xF1);
+        video_io.lock().set(0xFF4B, 7);
+
+        draw_line(&mut renderer, &video_io, 0);
+        video_io.lock().set(0xFF4B, 200); //moved off screen for a line
+        draw_line(&mut renderer, &video_io, 1);
+        video_io.lock().set(0xFF4B, 7);
+        let line = draw_line(&mut renderer, &video_io, 2);
+
+        assert_eq!(1, line[0]);
+    }
+
+    #[test]
+    fn blank_background_when_disabled() {
+        let (mut renderer, vram, _, video_io) = get_renderer();
+        set_tile_line(&vram, 0x8000, 0, 0xFF, 0xFF);
+        video_io.lock().set(0xFF40, 0x90);
+
+        let line = draw_line(&mut renderer, &video_io, 0);
+
+        assert!(line.iter().all(|&shade| shade == 0));
+    }
+
+    #[test]
+    fn draws_objects_with_transparency_and_palettes() {
+        let (mut renderer, vram, oam, video_io) = get_renderer();
+        set_tile_line(&vram, 0x8020, 0, 0b1100_0000, 0b0100_0000);
+        set_object(&oam, 0, 16, 8, 0x02, 0x00);
+        set_object(&oam, 1, 16, 20, 0x02, 0x10);
+        video_io.lock().set(0xFF40, 0x93);
+
+        let line = draw_line(&mut renderer, &video_io, 0);
+
+        assert_eq!(vec![1, 3, 0], line[0..3].to_vec());
+        assert_eq!(vec![2, 0, 0], line[12..15].to_vec()); //OBP1 is 0x1B
+    }
+
+    #[test]
+    fn flips_objects() {
+        let (mut renderer, vram, oam, video_io) = get_renderer();
+        set_tile_line(&vram, 0x8020, 7, 0x80, 0x00);
+        set_object(&oam, 0, 16, 8, 0x02, 0x60);
+        video_io.lock().set(0xFF40, 0x93);
+
+        let line = draw_line(&mut renderer, &video_io, 0);
+
+        assert_eq!(1, line[7]);
+    }
+
+    #[test]
+    fn uses_tile_pairs_for_tall_objects() {
+        let (mut renderer, vram, oam, video_io) = get_renderer();
+        set_tile_line(&vram, 0x8030, 1, 0x80, 0x00); //second half of tile pair 2
+        set_object(&oam, 0, 16, 8, 0x03, 0x00);
+        video_io.lock().set(0xFF40, 0x97);
+
+        let line = draw_line(&mut renderer, &video_io, 9);
+
+        assert_eq!(1, line[0]);
+    }
+
+    #[test]
+    fn objects_behind_background_show_over_colour_0_only() {
+        let (mut renderer, vram, oam, video_io) = get_renderer();
+        set_tile_line(&vram, 0x8010, 0, 0xF0, 0x00);
+        set_tile_line(&vram, 0x8020, 0, 0xFF, 0xFF);
+        vram.lock().set(0x9800, 0x01);
+        set_object(&oam, 0, 16, 8, 0x02, 0x80);
+        video_io.lock().set(0xFF40, 0x93);
+
+        let line = draw_line(&mut renderer, &video_io, 0);
+
+        assert_eq!(vec![1, 1, 1, 1, 3, 3, 3, 3], line[0..8].to_vec());
+    }
+
+    #[test]
+    fn lower_x_wins_then_oam_order() {
+        let (mut renderer, vram, oam, video_io) = get_renderer();
+        set_tile_line(&vram, 0x8020, 0, 0xFF, 0x00);
+        set_tile_line(&vram, 0x8030, 0, 0x00, 0xFF);
+        set_object(&oam, 0, 16, 12, 0x02, 0x00);
+        set_object(&oam, 1, 16, 8, 0x03, 0x00);
+        set_object(&oam, 2, 16, 8, 0x02, 0x00);
+        video_io.lock().set(0xFF40, 0x93);
+
+        let line = draw_line(&mut renderer, &video_io, 0);
+
+        assert_eq!(vec![2; 8], line[0..8].to_vec());
+        assert_eq!(vec![1; 4], line[8..12].to_vec());
+    }
+
+    #[test]
+    fn draws_at_most_ten_objects_per_line() {
+        let (mut renderer, vram, oam, video_io) = get_renderer();
+        set_tile_line(&vram, 0x8020, 0, 0xFF, 0x00);
+        for index in 0..11 {
+            set_object(&oam, index, 16, 8 + index as u8 * 8, 0x02, 0x00);
+        }
+        video_io.lock().set(0xFF40, 0x93);
+
+        let line = draw_line(&mut renderer, &video_io, 0);
+
+        assert_eq!(1, line[79]);
+        assert_eq!(0, line[80]);
+    }
+
+    #[test]
+    fn does_not_draw_with_lcd_off() {
+        let (mut renderer, vram, _, video_io) = get_renderer();
+        set_tile_line(&vram, 0x8000, 0, 0xFF, 0xFF);
+        video_io.lock().set(0xFF40, 0x11);
+
+        let line = draw_line(&mut renderer, &video_io, 0);
+
+        assert!(line.iter().all(|&shade| shade == 0));
+    }
+}
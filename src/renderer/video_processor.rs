@@ -9,7 +9,7 @@ use dec_gl::types::{ivec2, ivec3, IVec2};
 use parking_lot::Mutex;
 use crate::memory::io_map::VideoIO;
 use crate::memory::{OAM, VRAM};
-use crate::renderer::RendererError;
+use crate::renderer::{Renderer, RendererError};
 
 pub struct VideoProcessor {
     tilemap_bank_0: Texture3Du8,
@@ -334,6 +334,13 @@ impl VideoProcessor {
 }
 
 
+impl Renderer for VideoProcessor {
+    fn draw_line(&mut self, shader_manager: &mut ShaderManager) -> Result<(), RendererError> {
+        self.try_update_graphics_data();
+        self.draw(shader_manager)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::cell::RefCell;
@@ -0,0 +1,4 @@
+mod rewind_buffer;
+mod xor_delta;
+
+pub use rewind_buffer::RewindBuffer;
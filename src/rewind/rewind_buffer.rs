@@ -0,0 +1,206 @@
+use std::collections::VecDeque;
+use crate::rewind::xor_delta::XorDelta;
+use crate::save_state::SaveStateError;
+use crate::system::MainBoard;
+
+/**
+    Snapshots of the whole machine taken every few frames. Only the newest is kept in full, each older one
+    is stored as an XOR delta against the one after it, and the oldest are dropped once over the memory budget.
+*/
+pub struct RewindBuffer {
+    newest: Option<Vec<u8>>,
+    deltas: VecDeque<Vec<u8>>, //oldest first, each one decodes the snapshot before the one it is paired with
+
+    interval_frames: u32,
+    frames_since_snapshot: u32,
+    memory_budget: usize,
+    used_bytes: usize,
+}
+
+impl RewindBuffer {
+
+    pub const DEFAULT_INTERVAL_FRAMES: u32 = 5;
+    pub const DEFAULT_MEMORY_BUDGET: usize = 64 * 1024 * 1024;
+
+    pub fn new(interval_frames: u32, memory_budget: usize) -> Self {
+        Self {
+            newest: None,
+            deltas: VecDeque::new(),
+
+            interval_frames: interval_frames.max(1),
+            frames_since_snapshot: 0,
+            memory_budget,
+            used_bytes: 0,
+        }
+    }
+
+    /**
+        Call once per frame, a snapshot is taken every interval_frames
+    */
+    pub fn record_frame(&mut self, main_board: &MainBoard) {
+        if self.count_frame() {
+            self.push(main_board.save_state());
+        }
+    }
+
+    pub fn push(&mut self, state: Vec<u8>) {
+        match self.newest.take() {
+            Some(previous) => {
+                let delta = XorDelta::encode(&state, &previous);
+                self.used_bytes = self.used_bytes + delta.len() + state.len() - previous.len(); //previous is counted in used_bytes, so this can't underflow
+                self.deltas.push_back(delta);
+            }
+            None => self.used_bytes += state.len()
+        }
+
+        self.newest = Some(state);
+
+        while self.used_bytes > self.memory_budget {
+            match self.deltas.pop_front() {
+                Some(delta) => self.used_bytes -= delta.len(),
+                None => break
+            }
+        }
+    }
+
+    /**
+        Takes the newest snapshot, stepping back to the one before. Once only the oldest is left it is returned
+        each time, so holding rewind stays on it.
+    */
+    pub fn rewind(&mut self) -> Result<Option<Vec<u8>>, SaveStateError> {
+        let newest = match &self.newest {
+            Some(newest) => newest,
+            None => return Ok(None)
+        };
+
+        let delta = match self.deltas.pop_back() {
+            Some(delta) => delta,
+            None => return Ok(Some(newest.clone()))
+        };
+
+        let older = XorDelta::decode(newest, &delta)?;
+        self.used_bytes = self.used_bytes + older.len() - newest.len() - delta.len();
+        self.frames_since_snapshot = 0;
+
+        Ok(self.newest.replace(older))
+    }
+
+    /**
+        Returns whether a snapshot is due
+    */
+    fn count_frame(&mut self) -> bool {
+        self.frames_since_snapshot += 1;
+
+        if self.frames_since_snapshot < self.interval_frames {
+            return false;
+        }
+
+        self.frames_since_snapshot = 0;
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.newest = None;
+        self.deltas.clear();
+        self.frames_since_snapshot = 0;
+        self.used_bytes = 0;
+    }
+
+    /**
+        How many snapshots can be rewound to
+    */
+    pub fn get_snapshot_count(&self) -> usize {
+        match self.newest {
+            Some(_) => self.deltas.len() + 1,
+            None => 0
+        }
+    }
+
+    pub fn get_used_bytes(&self) -> usize {
+        self.used_bytes
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_state(value: u8) -> Vec<u8> {
+        let mut state = vec![0x55; 4096];
+        state[100] = value;
+        state
+    }
+
+    #[test]
+    fn rewinds_newest_snapshot_first() {
+        let mut rewind_buffer = RewindBuffer::new(1, usize::MAX);
+        for value in 0..5 {
+            rewind_buffer.push(get_state(value));
+        }
+
+        for value in (0..5).rev() {
+            assert_eq!(Some(get_state(value)), rewind_buffer.rewind().unwrap());
+        }
+    }
+
+    #[test]
+    fn stays_on_oldest_snapshot() {
+        let mut rewind_buffer = RewindBuffer::new(1, usize::MAX);
+        rewind_buffer.push(get_state(1));
+        rewind_buffer.push(get_state(2));
+
+        rewind_buffer.rewind().unwrap();
+
+        assert_eq!(Some(get_state(1)), rewind_buffer.rewind().unwrap());
+        assert_eq!(Some(get_state(1)), rewind_buffer.rewind().unwrap());
+        assert_eq!(1, rewind_buffer.get_snapshot_count());
+    }
+
+    #[test]
+    fn compresses_older_snapshots() {
+        let mut rewind_buffer = RewindBuffer::new(1, usize::MAX);
+        for value in 0..10 {
+            rewind_buffer.push(get_state(value));
+        }
+
+        assert_eq!(10, rewind_buffer.get_snapshot_count());
+        assert!(rewind_buffer.get_used_bytes() < 4096 * 2);
+    }
+
+    #[test]
+    fn drops_oldest_snapshots_over_budget() {
+        let mut rewind_buffer = RewindBuffer::new(1, 4096 + 100);
+        for value in 0..10 {
+            rewind_buffer.push(get_state(value));
+        }
+
+        assert!(rewind_buffer.get_used_bytes() <= 4096 + 100);
+        assert!(rewind_buffer.get_snapshot_count() < 10);
+
+        while rewind_buffer.get_snapshot_count() > 1 {
+            rewind_buffer.rewind().unwrap();
+        }
+        assert_ne!(Some(get_state(0)), rewind_buffer.rewind().unwrap());
+    }
+
+    #[test]
+    fn takes_snapshot_every_interval() {
+        let mut rewind_buffer = RewindBuffer::new(3, usize::MAX);
+
+        let due: Vec<bool> = (0..7).map(|_| rewind_buffer.count_frame()).collect();
+
+        assert_eq!(vec![false, false, true, false, false, true, false], due);
+    }
+
+    #[test]
+    fn clear_empties_buffer() {
+        let mut rewind_buffer = RewindBuffer::new(1, usize::MAX);
+        rewind_buffer.push(get_state(1));
+
+        rewind_buffer.clear();
+
+        assert_eq!(None, rewind_buffer.rewind().unwrap());
+        assert_eq!(0, rewind_buffer.get_used_bytes());
+    }
+}
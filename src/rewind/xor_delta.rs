@@ -0,0 +1,98 @@
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
+
+/**
+    Consecutive states are mostly the same, so XORing them leaves long runs of zeros. These are stored as
+    a zero run length followed by the bytes up to the next long run, with the older state's length first.
+*/
+pub struct XorDelta {}
+
+impl XorDelta {
+
+    const MIN_ZERO_RUN: usize = 8; //shorter runs stay in the literal bytes, a run costs 8 bytes to write
+
+    /**
+        Encodes older against newer, so decode can get older back from newer
+    */
+    pub fn encode(newer: &[u8], older: &[u8]) -> Vec<u8> {
+        let length = newer.len().max(older.len());
+        let xor: Vec<u8> = (0..length)
+            .map(|index| newer.get(index).unwrap_or(&0) ^ older.get(index).unwrap_or(&0))
+            .collect();
+
+        let mut writer = StateWriter::new();
+        writer.write_u32(older.len() as u32);
+
+        let mut position = 0;
+        while position < xor.len() {
+            let zero_run = xor[position..].iter().take_while(|byte| **byte == 0).count();
+            position += zero_run;
+
+            let literal_start = position;
+            while position < xor.len() && !Self::starts_zero_run(&xor[position..]) {
+                position += 1;
+            }
+
+            writer.write_u32(zero_run as u32);
+            writer.write_bytes(&xor[literal_start..position]);
+        }
+
+        writer.into_data()
+    }
+
+    pub fn decode(newer: &[u8], delta: &[u8]) -> Result<Vec<u8>, SaveStateError> {
+        let mut reader = StateReader::new(delta);
+        let older_length = reader.read_u32()? as usize;
+
+        let mut older = newer.to_vec();
+        older.resize(newer.len().max(older_length), 0);
+
+        let mut position = 0;
+        while !reader.is_finished() {
+            position += reader.read_u32()? as usize;
+
+            for byte in reader.read_bytes()? {
+                match older.get_mut(position) {
+                    Some(value) => *value ^= byte,
+                    None => return Err(SaveStateError::InvalidState { message: "Rewind delta is longer than its state".to_string() })
+                }
+                position += 1;
+            }
+        }
+
+        older.truncate(older_length);
+        Ok(older)
+    }
+
+    fn starts_zero_run(bytes: &[u8]) -> bool {
+        bytes.len() >= Self::MIN_ZERO_RUN && bytes[..Self::MIN_ZERO_RUN].iter().all(|byte| *byte == 0)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_older_state() {
+        let mut older = vec![0x11; 1000];
+        older[500] = 0x22;
+        let mut newer = older.clone();
+        newer[10] = 0x33;
+        newer[900] = 0x44;
+
+        let delta = XorDelta::encode(&newer, &older);
+
+        assert!(delta.len() < 50);
+        assert_eq!(older, XorDelta::decode(&newer, &delta).unwrap());
+    }
+
+    #[test]
+    fn decodes_states_of_different_lengths() {
+        let older = vec![1, 2, 3, 4, 5, 6];
+        let newer = vec![1, 2, 9];
+
+        assert_eq!(older, XorDelta::decode(&newer, &XorDelta::encode(&newer, &older)).unwrap());
+        assert_eq!(newer, XorDelta::decode(&older, &XorDelta::encode(&older, &newer)).unwrap());
+    }
+}
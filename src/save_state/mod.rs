@@ -0,0 +1,9 @@
+mod save_state_error;
+mod save_state_file;
+mod state_reader;
+mod state_writer;
+
+pub use save_state_error::SaveStateError;
+pub use save_state_file::SaveStateFile;
+pub use state_reader::StateReader;
+pub use state_writer::StateWriter;
@@ -0,0 +1,22 @@
+use thiserror::Error;
+use crate::memory::CartridgeError;
+
+#[derive(Error, Debug)]
+pub enum SaveStateError {
+    #[error("Could not write save state: {error}")]
+    WriteError { error: std::io::Error },
+    #[error("Could not read save state: {error}")]
+    ReadError { error: std::io::Error },
+    #[error("Not a save state file")]
+    NotASaveState,
+    #[error("Save state version {version} is not supported, expected {expected}")]
+    UnsupportedVersion { version: u16, expected: u16 },
+    #[error("Save state is for a different ROM (checksum {found:#06X}, expected {expected:#06X})")]
+    RomMismatch { expected: u16, found: u16 },
+    #[error("Save state ended early")]
+    Truncated,
+    #[error("Invalid save state: {message}")]
+    InvalidState { message: String },
+    #[error("Invalid mapper state: {error}")]
+    MapperError { error: CartridgeError },
+}
@@ -0,0 +1,104 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::save_state::SaveStateError;
+
+/**
+    A save state on disk: the magic bytes, the format version and the global checksum of the ROM it was taken
+    from, followed by the machine state
+*/
+pub struct SaveStateFile {}
+
+impl SaveStateFile {
+
+    const MAGIC: &'static [u8; 8] = b"GBSTATE\0";
+    const HEADER_SIZE: usize = 12;
+    pub const VERSION: u16 = 3;
+
+    /**
+        Slots live next to the ROM, as game.ss1, game.ss2 and so on
+    */
+    pub fn get_slot_path(rom_path: &Path, slot: u8) -> PathBuf {
+        rom_path.with_extension(format!("ss{}", slot))
+    }
+
+    pub fn write(path: &Path, rom_checksum: u16, state: &[u8]) -> Result<(), SaveStateError> {
+        let mut data = Vec::with_capacity(Self::HEADER_SIZE + state.len());
+        data.extend_from_slice(Self::MAGIC);
+        data.extend_from_slice(&Self::VERSION.to_le_bytes());
+        data.extend_from_slice(&rom_checksum.to_le_bytes());
+        data.extend_from_slice(state);
+
+        fs::write(path, data).map_err(|error| SaveStateError::WriteError { error })
+    }
+
+    pub fn read(path: &Path, rom_checksum: u16) -> Result<Vec<u8>, SaveStateError> {
+        let mut data = fs::read(path).map_err(|error| SaveStateError::ReadError { error })?;
+
+        if data.len() < Self::HEADER_SIZE || &data[0..8] != Self::MAGIC {
+            return Err(SaveStateError::NotASaveState);
+        }
+
+        let version = u16::from_le_bytes([data[8], data[9]]);
+        if version != Self::VERSION {
+            return Err(SaveStateError::UnsupportedVersion { version, expected: Self::VERSION });
+        }
+
+        let found = u16::from_le_bytes([data[10], data[11]]);
+        if found != rom_checksum {
+            return Err(SaveStateError::RomMismatch { expected: rom_checksum, found });
+        }
+
+        Ok(data.split_off(Self::HEADER_SIZE))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("save_state_file_{}_{}.ss1", name, std::process::id()))
+    }
+
+    #[test]
+    fn reads_back_written_state() {
+        let path = get_path("round_trip");
+
+        SaveStateFile::write(&path, 0x1234, &[1, 2, 3]).unwrap();
+        let state = SaveStateFile::read(&path, 0x1234).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(vec![1, 2, 3], state);
+    }
+
+    #[test]
+    fn rejects_state_from_another_rom() {
+        let path = get_path("other_rom");
+
+        SaveStateFile::write(&path, 0x1234, &[1, 2, 3]).unwrap();
+        let result = SaveStateFile::read(&path, 0x4321);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(SaveStateError::RomMismatch { expected: 0x4321, found: 0x1234 })));
+    }
+
+    #[test]
+    fn rejects_other_versions_and_files() {
+        let path = get_path("version");
+
+        fs::write(&path, b"GBSTATE\0\x63\x00\x34\x12").unwrap();
+        let version_result = SaveStateFile::read(&path, 0x1234);
+        fs::write(&path, b"not a save state").unwrap();
+        let magic_result = SaveStateFile::read(&path, 0x1234);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(version_result, Err(SaveStateError::UnsupportedVersion { version: 99, .. })));
+        assert!(matches!(magic_result, Err(SaveStateError::NotASaveState)));
+    }
+
+    #[test]
+    fn slot_path_is_next_to_rom() {
+        assert_eq!(PathBuf::from("roms/game.ss3"), SaveStateFile::get_slot_path(Path::new("roms/game.gb"), 3));
+    }
+}
@@ -0,0 +1,127 @@
+use crate::save_state::SaveStateError;
+
+pub struct StateReader<'a> {
+    state: &'a [u8],
+    position: usize,
+}
+
+impl<'a> StateReader<'a> {
+
+    pub fn new(state: &'a [u8]) -> Self {
+        Self {
+            state,
+            position: 0,
+        }
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, SaveStateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, SaveStateError> {
+        Ok(self.read_u8()? != 0)
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, SaveStateError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, SaveStateError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn read_i32(&mut self) -> Result<i32, SaveStateError> {
+        Ok(self.read_u32()? as i32)
+    }
+
+    pub fn read_f32(&mut self) -> Result<f32, SaveStateError> {
+        Ok(f32::from_bits(self.read_u32()?))
+    }
+
+    /**
+        Reads bytes written by `StateWriter::write_bytes`
+    */
+    pub fn read_bytes(&mut self) -> Result<&'a [u8], SaveStateError> {
+        let length = self.read_u32()? as usize;
+        self.take(length)
+    }
+
+    /**
+        Reads bytes written by `StateWriter::write_bytes`, failing unless there are exactly as many as expected
+    */
+    pub fn read_bytes_of_length(&mut self, expected: usize, name: &str) -> Result<&'a [u8], SaveStateError> {
+        let bytes = self.read_bytes()?;
+
+        if bytes.len() == expected {
+            Ok(bytes)
+        } else {
+            Err(SaveStateError::InvalidState { message: format!("{} has {} bytes, expected {}", name, bytes.len(), expected) })
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position == self.state.len()
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], SaveStateError> {
+        if self.state.len() - self.position < length {
+            return Err(SaveStateError::Truncated);
+        }
+
+        let bytes = &self.state[self.position..self.position + length];
+        self.position += length;
+
+        Ok(bytes)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::save_state::StateWriter;
+    use super::*;
+
+    #[test]
+    fn reads_back_written_values() {
+        let mut writer = StateWriter::new();
+        writer.write_u8(0x12);
+        writer.write_bool(true);
+        writer.write_u16(0x3456);
+        writer.write_u32(0x789ABCDE);
+        writer.write_i32(-5);
+        writer.write_f32(0.25);
+        writer.write_bytes(&[1, 2, 3]);
+        let state = writer.into_data();
+
+        let mut reader = StateReader::new(&state);
+
+        assert_eq!(0x12, reader.read_u8().unwrap());
+        assert_eq!(true, reader.read_bool().unwrap());
+        assert_eq!(0x3456, reader.read_u16().unwrap());
+        assert_eq!(0x789ABCDE, reader.read_u32().unwrap());
+        assert_eq!(-5, reader.read_i32().unwrap());
+        assert_eq!(0.25, reader.read_f32().unwrap());
+        assert_eq!(&[1, 2, 3], reader.read_bytes().unwrap());
+        assert!(reader.is_finished());
+    }
+
+    #[test]
+    fn reading_past_the_end_is_an_error() {
+        let mut reader = StateReader::new(&[0x12]);
+
+        assert!(matches!(reader.read_u16(), Err(SaveStateError::Truncated)));
+    }
+
+    #[test]
+    fn bytes_of_the_wrong_length_are_an_error() {
+        let mut writer = StateWriter::new();
+        writer.write_bytes(&[1, 2, 3]);
+        let state = writer.into_data();
+
+        let result = StateReader::new(&state).read_bytes_of_length(4, "HRAM");
+
+        assert!(matches!(result, Err(SaveStateError::InvalidState { .. })));
+    }
+}
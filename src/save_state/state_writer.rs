@@ -0,0 +1,51 @@
+/**
+    Builds a save state as little endian values in a fixed order, which `StateReader` reads back in the same order
+*/
+pub struct StateWriter {
+    data: Vec<u8>,
+}
+
+impl StateWriter {
+
+    pub fn new() -> Self {
+        Self {
+            data: vec![],
+        }
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.data.push(value as u8);
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_i32(&mut self, value: i32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /**
+        Writes the length first, for data whose size isn't fixed
+    */
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_u32(bytes.len() as u32);
+        self.data.extend_from_slice(bytes);
+    }
+
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+}
@@ -4,7 +4,7 @@ use parking_lot::Mutex;
 use crate::app::PerformanceTimer;
 use crate::cpu::{Interrupt, CPU};
 use crate::memory::MemoryController;
-use crate::renderer::VideoProcessor;
+use crate::renderer::Renderer;
 use crate::system::clock_event::ClockEvent;
 use crate::system::system_error::SystemError;
 
@@ -19,7 +19,7 @@ impl EventHandler {
     pub fn handle_event(&mut self,
                         cpu: &mut Box<dyn CPU>,
                         memory: Arc<Mutex<MemoryController>>,
-                        video_processor: &mut VideoProcessor,
+                        renderer: &mut dyn Renderer,
                         shader_manager: &mut ShaderManager,
                         event: &ClockEvent,
                         performance_timer: &mut PerformanceTimer
@@ -29,15 +29,17 @@ impl EventHandler {
         match event {
             ClockEvent::CPUClock => {
                 performance_timer.set_category("CPU");
-                memory.lock().clock();
+                let interrupts = memory.lock().clock();
+
+                for interrupt in interrupts {
+                    cpu.try_interrupt(memory.clone(), interrupt);
+                }
 
                 cpu.clock(memory.clone());
             }
             ClockEvent::DrawLine => {
                 performance_timer.set_category("Draw");
-                video_processor.try_update_graphics_data();
-
-                match video_processor.draw(shader_manager) {
+                match renderer.draw_line(shader_manager) {
                     Ok(_) => {}
                     Err(error) => return Err(SystemError::RendererError { error }),
                 }
@@ -72,6 +74,7 @@ mod tests { //these are not very nice
     use dec_gl::Vertex2d;
     use crate::cpu::NullableCPU;
     use crate::memory::MemoryTrait;
+    use crate::renderer::VideoProcessor;
     use super::*;
 
     fn get_mock_textures_with_expectations() -> (MockTexture3Du8, MockTexture3Du8, MockTexture3Du8, MockTexture2Du8, MockTexture2Du8) {
@@ -238,4 +241,40 @@ mod tests { //these are not very nice
 
         assert_eq!(Some(Interrupt::VBlank), *interrupt.borrow());
     }
+
+    #[test]
+    fn cpu_clock_event_forwards_io_interrupts() {
+        let mut event_handler = EventHandler::new();
+        let interrupt = Rc::new(RefCell::new(None));
+        let mut cpu: Box<dyn CPU> = Box::new(NullableCPU::new( Rc::new(RefCell::new(0)),  interrupt.clone()));
+        let memory = Arc::new(Mutex::new(MemoryController::new()));
+
+        let (tile_bank_0, tile_bank_1, tile_bank_2, map_bank_0, map_bank_1) = get_mock_textures_with_expectations();
+
+        let vram = memory.lock().get_vram_arc();
+        let oam = memory.lock().get_oam_arc();
+        let video_io = memory.lock().get_io_map().lock().get_video_io();
+
+        let mut video_processor = VideoProcessor::new(
+            tile_bank_0, tile_bank_1, tile_bank_2,
+            map_bank_0, map_bank_1,
+            get_generic_renderable(),
+            get_generic_renderable(),
+            vram,
+            oam,
+            video_io).unwrap();
+
+        let mut shader_manager = ShaderManager::new();
+
+        memory.lock().set(0xFF07, 0x05);
+        memory.lock().set(0xFF05, 0xFF);
+
+        let event = ClockEvent::CPUClock;
+
+        for _ in 0..8 {
+            event_handler.handle_event(&mut cpu, memory.clone(), &mut video_processor, &mut shader_manager, &event, &mut PerformanceTimer::new_fake()).unwrap();
+        }
+
+        assert_eq!(Some(Interrupt::Timer), *interrupt.borrow());
+    }
 }
@@ -0,0 +1,144 @@
+use dec_gl::shader::ShaderManager;
+use crate::app::PerformanceTimer;
+use crate::link::DirectLinkPeer;
+use crate::system::main_board::MainBoard;
+use crate::system::system_error::SystemError;
+
+/**
+    Two Game Boys in one process with their link ports connected directly. They are stepped alternately at most a scan
+    line at a time, so serial transfers between them always play out the same way.
+*/
+pub struct LinkedMainBoards {
+    main_boards: [MainBoard; 2],
+}
+
+impl LinkedMainBoards {
+
+    const MAX_TICKS_PER_STEP: u32 = 228; //one scan line
+
+    pub fn new(mut main_board_1: MainBoard, mut main_board_2: MainBoard) -> Self {
+        let (peer_1, peer_2) = DirectLinkPeer::new_pair();
+
+        main_board_1.set_serial_peer(Some(Box::new(peer_1)));
+        main_board_2.set_serial_peer(Some(Box::new(peer_2)));
+
+        Self {
+            main_boards: [main_board_1, main_board_2],
+        }
+    }
+
+    /**
+        Runs the first board for up to the given number of ticks, or a scan line if that is fewer, then the second board
+        for as many ticks as the first performed. Returns the ticks performed and whether the first board sent a frame.
+    */
+    pub fn perform_ticks(&mut self,
+                         ticks: u32,
+                         shader_manager: &mut ShaderManager,
+                         performance_timer: &mut PerformanceTimer
+    ) -> Result<(u32, bool), SystemError>
+    {
+        let (ticks, send_frame) = self.main_boards[0].perform_ticks(ticks.min(Self::MAX_TICKS_PER_STEP), shader_manager, performance_timer)?;
+
+        let mut ticks_remaining = ticks;
+        while ticks_remaining > 0 { //keeps the second board on the same tick even if its frame ends at a different point
+            let (ticks_performed, _) = self.main_boards[1].perform_ticks(ticks_remaining, shader_manager, performance_timer)?;
+            ticks_remaining -= ticks_performed;
+        }
+
+        Ok((ticks, send_frame))
+    }
+
+    pub fn get_main_board(&self, index: usize) -> &MainBoard {
+        &self.main_boards[index]
+    }
+
+    pub fn get_main_board_mut(&mut self, index: usize) -> &mut MainBoard {
+        &mut self.main_boards[index]
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use dec_gl::renderable::NullableRenderable;
+    use dec_gl::texture::{MockTexture2Du8, MockTexture3Du8};
+    use dec_gl::Vertex2d;
+    use parking_lot::Mutex;
+    use crate::cpu::NullableCPU;
+    use crate::memory::{MemoryController, MemoryTrait};
+    use crate::renderer::VideoProcessor;
+    use super::*;
+
+    fn get_main_board() -> (MainBoard, Arc<Mutex<MemoryController>>) {
+        let memory = Arc::new(Mutex::new(MemoryController::new()));
+        memory.lock().set(0xFF40, 0x00); //LCD off, so nothing is drawn
+
+        let vram = memory.lock().get_vram_arc();
+        let oam = memory.lock().get_oam_arc();
+        let video_io = memory.lock().get_io_map().lock().get_video_io();
+
+        let video_processor = VideoProcessor::new(
+            MockTexture3Du8::default(), MockTexture3Du8::default(), MockTexture3Du8::default(),
+            MockTexture2Du8::default(), MockTexture2Du8::default(),
+            Box::new(NullableRenderable::<Vertex2d>::new::<Vertex2d>(Rc::new(RefCell::new(false)), Rc::new(RefCell::new(vec![])), Rc::new(RefCell::new(None)), Rc::new(RefCell::new(0)))),
+            Box::new(NullableRenderable::<Vertex2d>::new::<Vertex2d>(Rc::new(RefCell::new(false)), Rc::new(RefCell::new(vec![])), Rc::new(RefCell::new(None)), Rc::new(RefCell::new(0)))),
+            vram,
+            oam,
+            video_io).unwrap();
+
+        let cpu = Box::new(NullableCPU::new(Rc::new(RefCell::new(0)), Rc::new(RefCell::new(None))));
+
+        (MainBoard::new(cpu, memory.clone(), Box::new(video_processor)), memory)
+    }
+
+    fn perform_frame(linked_main_boards: &mut LinkedMainBoards) -> u32 {
+        let mut steps = 0;
+
+        loop {
+            steps += 1;
+            let (_, send_frame) = linked_main_boards.perform_ticks(u32::MAX, &mut ShaderManager::new(), &mut PerformanceTimer::new_fake()).unwrap();
+
+            if send_frame {
+                return steps;
+            }
+        }
+    }
+
+    #[test]
+    fn exchanges_serial_bytes_between_boards() {
+        let (main_board_1, memory_1) = get_main_board();
+        let (main_board_2, memory_2) = get_main_board();
+        let mut linked_main_boards = LinkedMainBoards::new(main_board_1, main_board_2);
+
+        memory_1.lock().set(0xFF01, 0x12);
+        memory_2.lock().set(0xFF01, 0x34);
+        memory_2.lock().set(0xFF02, 0x80);
+        memory_1.lock().set(0xFF02, 0x81);
+
+        for _ in 0..2 {
+            perform_frame(&mut linked_main_boards);
+        }
+
+        assert_eq!(0x34, memory_1.lock().get(0xFF01));
+        assert_eq!(0x12, memory_2.lock().get(0xFF01));
+        assert_eq!(0x7F, memory_1.lock().get(0xFF02));
+        assert_eq!(0x7E, memory_2.lock().get(0xFF02));
+    }
+
+    #[test]
+    fn steps_at_most_a_scan_line_at_a_time() {
+        let (main_board_1, _) = get_main_board();
+        let (main_board_2, _) = get_main_board();
+        let mut linked_main_boards = LinkedMainBoards::new(main_board_1, main_board_2);
+
+        perform_frame(&mut linked_main_boards);
+
+        let (ticks, send_frame) = linked_main_boards.perform_ticks(u32::MAX, &mut ShaderManager::new(), &mut PerformanceTimer::new_fake()).unwrap();
+
+        assert_eq!(228, ticks);
+        assert!(!send_frame);
+    }
+}
@@ -3,9 +3,12 @@ use std::sync::Arc;
 use dec_gl::shader::ShaderManager;
 use parking_lot::Mutex;
 use crate::app::PerformanceTimer;
+use crate::audio::{AudioSink, APU};
 use crate::cpu::CPU;
 use crate::memory::MemoryController;
-use crate::renderer::VideoProcessor;
+use crate::memory::io_map::SerialPeer;
+use crate::renderer::Renderer;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
 use crate::system::clock_event::ClockEvent;
 use crate::system::event_handler::EventHandler;
 use crate::system::system_error::SystemError;
@@ -15,30 +18,60 @@ pub struct MainBoard {
     cpu: Box<dyn CPU>,
     vdu_counter: VDUCounter,
     memory: Arc<Mutex<MemoryController>>,
-    video_processor: VideoProcessor,
+    renderer: Box<dyn Renderer>,
     event_handler: EventHandler,
     events: VecDeque<ClockEvent>,
+    apu: Arc<Mutex<APU>>,
+    audio_sink: Option<Arc<Mutex<dyn AudioSink>>>,
 }
 
 impl MainBoard {
 
-    pub fn new(cpu: Box<dyn CPU>, memory: Arc<Mutex<MemoryController>>, video_processor: VideoProcessor) -> Self {
+    pub fn new(cpu: Box<dyn CPU>, memory: Arc<Mutex<MemoryController>>, renderer: Box<dyn Renderer>) -> Self {
+        let apu = memory.lock().get_io_map().lock().get_apu(); //locked separately, the guards in the struct expression live until it ends
+
         Self {
             cpu,
             vdu_counter: VDUCounter::new(memory.clone().lock().get_io_map().lock().get_video_io()),
+            apu,
             memory,
-            video_processor,
+            renderer,
             event_handler: EventHandler::new(),
             events: VecDeque::new(),
+            audio_sink: None,
         }
     }
 
+    /**
+        The APU is switched to the sink's sample rate so no resampling is needed
+    */
+    pub fn set_audio_sink(&mut self, audio_sink: Option<Arc<Mutex<dyn AudioSink>>>) {
+        match &audio_sink {
+            Some(sink) => self.apu.lock().set_sample_rate(sink.lock().get_sample_rate()),
+            None => {}
+        }
+
+        self.audio_sink = audio_sink;
+    }
+
     pub fn perform_frame(&mut self, shader_manager: &mut ShaderManager, performance_timer: &mut PerformanceTimer) -> Result<(), SystemError> {
+        self.perform_ticks(u32::MAX, shader_manager, performance_timer)?;
+
+        Ok(())
+    }
+
+    /**
+        Performs up to the given number of VDU ticks, stopping early once a frame has been sent.
+        Returns how many ticks were performed and whether a frame was sent.
+    */
+    pub fn perform_ticks(&mut self, ticks: u32, shader_manager: &mut ShaderManager, performance_timer: &mut PerformanceTimer) -> Result<(u32, bool), SystemError> {
         let mut send_frame = false;
+        let mut ticks_performed = 0;
 
-        while !send_frame {
+        while !send_frame && ticks_performed < ticks {
             performance_timer.set_category("Main Board (timing)");
             self.vdu_counter.tick(&mut self.events);
+            ticks_performed += 1;
 
             performance_timer.set_category("Event Handling");
             for event in self.events.drain(..) {
@@ -46,21 +79,124 @@ impl MainBoard {
                 send_frame = send_frame || self.event_handler.handle_event(
                     &mut self.cpu,
                     self.memory.clone(),
-                    &mut self.video_processor,
+                    self.renderer.as_mut(),
                     shader_manager,
                     &event,
                     performance_timer)?;
             }
         }
 
+        performance_timer.set_category("Audio");
+        let samples = self.apu.lock().take_samples();
+        match &self.audio_sink {
+            Some(sink) => sink.lock().push_samples(&samples),
+            None => {}
+        }
+
+        Ok((ticks_performed, send_frame))
+    }
+
+    /**
+        Address of the instruction the CPU is executing
+    */
+    pub fn get_pc(&self) -> u16 {
+        self.cpu.get_pc()
+    }
+
+    pub fn get_bc_de_hl(&self) -> (u16, u16, u16) {
+        self.cpu.get_bc_de_hl()
+    }
+
+    /**
+        Can be taken between any two ticks, including part way through an instruction
+    */
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new();
+        self.cpu.save_state(&mut writer);
+        self.memory.lock().save_state(&mut writer);
+        self.vdu_counter.save_state(&mut writer);
+
+        writer.into_data()
+    }
+
+    /**
+        The ROM the state was saved from must already be loaded
+    */
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), SaveStateError> {
+        let mut reader = StateReader::new(state);
+
+        self.cpu.load_state(&mut reader)?;
+        self.memory.lock().load_state(&mut reader)?;
+        self.vdu_counter.load_state(&mut reader)?;
+
+        if !reader.is_finished() {
+            return Err(SaveStateError::InvalidState { message: "Unexpected data after the machine state".to_string() });
+        }
+
         Ok(())
     }
 
+    pub fn set_serial_peer(&mut self, peer: Option<Box<dyn SerialPeer>>) {
+        self.memory.lock().get_io_map().lock().set_serial_peer(peer);
+    }
+
     pub fn reset(&mut self) -> Result<(), SystemError> {
-        self.memory.lock().reset();
+        let save_result = self.memory.lock().reset();
         self.cpu.reset();
         self.vdu_counter.reset();
 
-        Ok(())
+        save_result.map_err(|error| SystemError::SaveError { error })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use dec_gl::renderable::NullableRenderable;
+    use dec_gl::texture::{MockTexture2Du8, MockTexture3Du8};
+    use dec_gl::Vertex2d;
+    use crate::audio::RingBufferSink;
+    use crate::cpu::NullableCPU;
+    use crate::memory::MemoryTrait;
+    use crate::renderer::VideoProcessor;
+    use super::*;
+
+    fn get_main_board() -> MainBoard {
+        let memory = Arc::new(Mutex::new(MemoryController::new()));
+        memory.lock().set(0xFF40, 0x00); //LCD off, so nothing is drawn
+
+        let vram = memory.lock().get_vram_arc();
+        let oam = memory.lock().get_oam_arc();
+        let video_io = memory.lock().get_io_map().lock().get_video_io();
+
+        let video_processor = VideoProcessor::new(
+            MockTexture3Du8::default(), MockTexture3Du8::default(), MockTexture3Du8::default(),
+            MockTexture2Du8::default(), MockTexture2Du8::default(),
+            Box::new(NullableRenderable::<Vertex2d>::new::<Vertex2d>(Rc::new(RefCell::new(false)), Rc::new(RefCell::new(vec![])), Rc::new(RefCell::new(None)), Rc::new(RefCell::new(0)))),
+            Box::new(NullableRenderable::<Vertex2d>::new::<Vertex2d>(Rc::new(RefCell::new(false)), Rc::new(RefCell::new(vec![])), Rc::new(RefCell::new(None)), Rc::new(RefCell::new(0)))),
+            vram,
+            oam,
+            video_io).unwrap();
+
+        let cpu = Box::new(NullableCPU::new(Rc::new(RefCell::new(0)), Rc::new(RefCell::new(None))));
+
+        MainBoard::new(cpu, memory, Box::new(video_processor))
+    }
+
+    #[test]
+    fn pushes_a_frame_of_samples_to_audio_sink() {
+        let mut main_board = get_main_board();
+        let sink = Arc::new(Mutex::new(RingBufferSink::new(32768, 4096)));
+        main_board.set_audio_sink(Some(sink.clone()));
+
+        main_board.perform_frame(&mut ShaderManager::new(), &mut PerformanceTimer::new_fake()).unwrap(); //with the LCD off the first frame is sent straight away
+        sink.lock().take_samples();
+        main_board.perform_frame(&mut ShaderManager::new(), &mut PerformanceTimer::new_fake()).unwrap();
+
+        let samples = sink.lock().take_samples();
+        assert!((540..=550).contains(&samples.len()), "{}", samples.len()); //about 70224 clocks at 32768Hz
+        assert_eq!(0, sink.lock().len());
     }
 }
@@ -3,5 +3,8 @@ mod clock_event;
 mod vdu_counter;
 mod event_handler;
 mod system_error;
+mod linked_main_boards;
 
 pub use main_board::MainBoard;
+pub use linked_main_boards::LinkedMainBoards;
+pub use system_error::SystemError;
@@ -5,5 +5,7 @@ use crate::renderer::RendererError;
 #[derive(Error, Debug)]
 pub enum SystemError {
     #[error("Renderer Error: {error}")]
-    RendererError { error: RendererError }
+    RendererError { error: RendererError },
+    #[error("Save Error: {error}")]
+    SaveError { error: std::io::Error }
 }
@@ -4,6 +4,7 @@ use parking_lot::Mutex;
 use crate::memory::io_map::VideoIO;
 use crate::renderer::{LCDCMask, LCDStatMask};
 use crate::system::clock_event::ClockEvent;
+use crate::save_state::{SaveStateError, StateReader, StateWriter};
 
 /*
  * This will have to be changed quite a bit for GBC double speed support
@@ -178,6 +179,40 @@ impl VDUCounter {
             *counter = 274 + 224;
         }
     }
+
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        match self {
+            VDUCounter::LCDOn { line_counter, vblank, .. } => {
+                writer.write_bool(true);
+                writer.write_u32(*line_counter);
+                writer.write_bool(*vblank);
+            }
+            VDUCounter::LCDOff { generic_frame_counter, .. } => {
+                writer.write_bool(false);
+                writer.write_u32(*generic_frame_counter);
+                writer.write_bool(false);
+            }
+        }
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        let video_io = match self {
+            VDUCounter::LCDOn { video_io, .. } => video_io.clone(),
+            VDUCounter::LCDOff { video_io, .. } => video_io.clone(),
+        };
+
+        let lcd_on = reader.read_bool()?;
+        let counter = reader.read_u32()?;
+        let vblank = reader.read_bool()?;
+
+        *self = if lcd_on {
+            VDUCounter::LCDOn { video_io, line_counter: counter, vblank }
+        } else {
+            VDUCounter::LCDOff { video_io, generic_frame_counter: counter }
+        };
+
+        Ok(())
+    }
 }
 
 
@@ -409,4 +444,19 @@ mod tests {
 
         assert!(matches!(events[1], ClockEvent::SendFrame));
     }
+
+    #[test]
+    fn load_state_restores_saved_counter() {
+        let video_io = Arc::new(Mutex::new(VideoIO::new()));
+        let vdu_counter = VDUCounter::LCDOn { video_io: video_io.clone(), line_counter: 123, vblank: true };
+
+        let mut writer = StateWriter::new();
+        vdu_counter.save_state(&mut writer);
+        let state = writer.into_data();
+
+        let mut loaded = VDUCounter::LCDOff { video_io: video_io.clone(), generic_frame_counter: 0 };
+        loaded.load_state(&mut StateReader::new(&state)).unwrap();
+
+        assert!(matches!(loaded, VDUCounter::LCDOn { line_counter: 123, vblank: true, .. }));
+    }
 }